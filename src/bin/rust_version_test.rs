@@ -11,12 +11,6 @@ use olympus::core::types::Signature;
 struct TestData {
     transactions: Vec<TransactionData>,
     blocks: Vec<BlockData>,
-    addresses: Vec<String>,
-    timestamps: Vec<u64>,
-    gas_prices: Vec<u64>,
-    values: Vec<u64>,
-    gas_limits: Vec<u64>,
-    data_payloads: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -35,7 +29,6 @@ struct BlockData {
     from_address: String,
     previous_hash: String,
     timestamp: u64,
-    transaction_count: u32,
     gas_used: u64,
 }
 
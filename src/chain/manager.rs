@@ -0,0 +1,528 @@
+//! Chain manager applying blocks to state
+
+use crate::consensus::dag::DagConsensus;
+use crate::core::block::Block;
+use crate::core::transaction::{CheckTransaction, Transaction};
+use crate::db::BlockStore;
+use crate::evm::executive::ExecutionStatus;
+use crate::evm::revm_database::BLOCK_HASH_WINDOW;
+use crate::evm::state::State;
+use crate::evm::transaction_executor::{TransactionExecutionResult, TransactionExecutor, TransactionPool};
+use crate::{Address, H256, U256, Result, OlympusError};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::Arc;
+
+/// Chain manager
+///
+/// Owns the executor and the transaction pool, and keeps the pool in sync with
+/// the state as blocks are applied. With a block store, applied blocks, their
+/// transactions and the state roots they produced are persisted as the
+/// canonical chain.
+pub struct ChainManager {
+    /// Transaction executor holding the chain state
+    executor: TransactionExecutor,
+    /// Transaction pool
+    pool: TransactionPool,
+    /// Number of blocks applied
+    head_number: u64,
+    /// Applied blocks in consensus order
+    history: Vec<AppliedBlock>,
+    /// Hashes of the blocks BLOCKHASH can read, by number
+    recent_hashes: BTreeMap<u64, H256>,
+    /// Store persisting the canonical chain
+    block_store: Option<BlockStore>,
+}
+
+/// Block applied to the chain, with the state root it produced
+#[derive(Debug, Clone)]
+pub struct AppliedBlock {
+    /// Block
+    pub block: Block,
+    /// Transactions linked by the block, in order
+    pub transactions: Vec<Transaction>,
+    /// State root after applying the block
+    pub state_root: H256,
+}
+
+impl ChainManager {
+    /// Create new chain manager
+    pub fn new(executor: TransactionExecutor, pool: TransactionPool) -> Self {
+        Self {
+            executor,
+            pool,
+            head_number: 0,
+            history: Vec::new(),
+            recent_hashes: BTreeMap::new(),
+            block_store: None,
+        }
+    }
+
+    /// Persist applied blocks to a block store, and replay from it
+    pub fn set_block_store(&mut self, block_store: BlockStore) {
+        self.block_store = Some(block_store);
+    }
+
+    /// Apply a block and the transactions it links
+    ///
+    /// `transactions` must match the block's links in order. After execution the
+    /// pool evicts every transaction occupying the sender and nonce of an executed
+    /// one, including replaced transactions, and is notified of every sender whose
+    /// nonce advanced.
+    pub fn apply_block(&mut self, block: &Block, transactions: Vec<Transaction>) -> Result<Vec<TransactionExecutionResult>> {
+        let (applied, results) = Self::apply_to(&mut self.executor, &mut self.recent_hashes, self.head_number + 1, block, transactions)?;
+        self.head_number += 1;
+        self.update_pool(&applied.transactions, &results);
+        self.store_block(self.head_number, &applied)?;
+        self.history.push(applied);
+
+        Ok(results)
+    }
+
+    /// Import a block received from the network
+    ///
+    /// The block must be signed by its creator and every transaction it links
+    /// must be in the pool, pass full validation and carry a recoverable
+    /// sender; it is then applied as the next block.
+    pub fn import_block(&mut self, block: &Block) -> Result<Vec<TransactionExecutionResult>> {
+        block.validate()?;
+        let transactions = block.links.iter()
+            .map(|link| {
+                let transaction = self.pool.get_transaction(*link).cloned()
+                    .ok_or_else(|| OlympusError::InvalidBlock(format!("Block links unknown transaction {:?}", link)))?;
+                transaction.validate(CheckTransaction::Everything)?;
+                transaction.sender()?;
+                Ok(transaction)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.apply_block(block, transactions)
+    }
+
+    /// Persist an applied block as the canonical block `number` and the new head
+    fn store_block(&self, number: u64, applied: &AppliedBlock) -> Result<()> {
+        let Some(store) = &self.block_store else {
+            return Ok(());
+        };
+        let hash = applied.block.hash();
+        for transaction in &applied.transactions {
+            store.put_transaction(transaction)?;
+        }
+        store.put_block(&applied.block)?;
+        store.put_state_root(hash, applied.state_root)?;
+        store.put_block_number(number, hash)?;
+        store.set_head(hash)
+    }
+
+    /// Load the canonical chain from the block store, in consensus order
+    fn stored_blocks(&self) -> Result<Vec<AppliedBlock>> {
+        let store = self.block_store.as_ref()
+            .ok_or_else(|| OlympusError::InvalidBlock("No block store to replay from".to_string()))?;
+        let missing = |what: &str, hash: H256| OlympusError::InvalidBlock(format!("Block store is missing {} {:?}", what, hash));
+
+        let mut blocks = Vec::new();
+        for number in 1.. {
+            let Some(block) = store.get_block_by_number(number)? else {
+                break;
+            };
+            let hash = block.hash();
+            let transactions = block.links.iter()
+                .map(|link| store.get_transaction(*link)?.ok_or_else(|| missing("transaction", *link)))
+                .collect::<Result<Vec<_>>>()?;
+            let state_root = store.get_state_root(hash)?.ok_or_else(|| missing("the state root of block", hash))?;
+            blocks.push(AppliedBlock { block, transactions, state_root });
+        }
+        Ok(blocks)
+    }
+
+    /// Execute a block on `executor` as block `number`, checking its links
+    fn apply_to(
+        executor: &mut TransactionExecutor,
+        recent_hashes: &mut BTreeMap<u64, H256>,
+        number: u64,
+        block: &Block,
+        transactions: Vec<Transaction>,
+    ) -> Result<(AppliedBlock, Vec<TransactionExecutionResult>)> {
+        let hashes: Vec<_> = transactions.iter().map(|tx| tx.hash()).collect();
+        if hashes != block.links {
+            return Err(OlympusError::InvalidBlock("Transactions do not match block links".to_string()));
+        }
+
+        let results = Self::execute_block(executor, recent_hashes, number, block, transactions.clone())?;
+        let applied = AppliedBlock {
+            block: block.clone(),
+            transactions,
+            state_root: executor.state().state_root(),
+        };
+        Ok((applied, results))
+    }
+
+    /// Bring the pool in line with the state after a block's execution
+    fn update_pool(&mut self, transactions: &[Transaction], results: &[TransactionExecutionResult]) {
+        // Rejected and skipped transactions did not use their nonce, so they or a replacement may still be valid
+        let executed: Vec<Transaction> = transactions.iter().zip(results)
+            .filter(|(_, result)| !matches!(result.status, ExecutionStatus::Rejected | ExecutionStatus::Skipped))
+            .map(|(transaction, _)| transaction.clone())
+            .collect();
+        self.pool.remove_included(&executed);
+
+        let senders: BTreeSet<Address> = transactions.iter().map(|tx| tx.from()).collect();
+        for sender in senders {
+            let nonce = self.executor.state().get_nonce(sender);
+            self.pool.on_nonce_advanced(sender, nonce);
+        }
+        self.pool.prune_expired(self.head_number + 1);
+    }
+
+    /// Rebuild the state by replaying the stored chain from the genesis state
+    ///
+    /// The canonical blocks are loaded from the block store in consensus order
+    /// and re-executed, and the state root after each must match the root
+    /// recorded when it was first applied. The chain only switches to the
+    /// rebuilt state once the whole chain has replayed.
+    pub fn replay_from_genesis(&mut self, genesis_state: Box<dyn State>) -> Result<()> {
+        let blocks = self.stored_blocks()?;
+        (self.executor, self.recent_hashes) = self.replay(genesis_state, &blocks)?;
+        self.head_number = blocks.len() as u64;
+        self.history = blocks;
+        Ok(())
+    }
+
+    /// Switch to another branch forking after the first `fork_point` applied blocks
+    ///
+    /// The state is rebuilt from the genesis state up to the fork point on a
+    /// scratch executor and the new branch applied on top; the chain switches
+    /// to it only if every block of the branch applies, and is left untouched
+    /// otherwise. Transactions of reverted blocks that the new branch does not
+    /// include are returned to the pool if their nonce is still unused and the
+    /// sender can still pay for them. Returns their hashes.
+    pub fn reorg(
+        &mut self,
+        fork_point: u64,
+        new_branch: Vec<(Block, Vec<Transaction>)>,
+        genesis_state: Box<dyn State>,
+    ) -> Result<Vec<H256>> {
+        if fork_point > self.head_number {
+            return Err(OlympusError::InvalidBlock(format!(
+                "Fork point {} is beyond the head block {}", fork_point, self.head_number
+            )));
+        }
+
+        let (mut executor, mut recent_hashes) = self.replay(genesis_state, &self.history[..fork_point as usize])?;
+        let mut applied = Vec::with_capacity(new_branch.len());
+        for (index, (block, transactions)) in new_branch.into_iter().enumerate() {
+            applied.push(Self::apply_to(&mut executor, &mut recent_hashes, fork_point + index as u64 + 1, &block, transactions)?);
+        }
+
+        self.executor = executor;
+        self.recent_hashes = recent_hashes;
+        let reverted = self.history.split_off(fork_point as usize);
+        self.head_number = fork_point;
+        let mut included = HashSet::new();
+        for (block, results) in applied {
+            included.extend(block.transactions.iter().map(|tx| tx.hash()));
+            self.head_number += 1;
+            self.update_pool(&block.transactions, &results);
+            self.store_block(self.head_number, &block)?;
+            self.history.push(block);
+        }
+        if let Some(store) = &self.block_store {
+            for number in self.head_number + 1..=fork_point + reverted.len() as u64 {
+                store.remove_block_number(number)?;
+            }
+            match self.history.last() {
+                Some(head) => store.set_head(head.block.hash())?,
+                None => store.remove_head()?,
+            }
+        }
+
+        let mut reinjected = Vec::new();
+        for transaction in reverted.into_iter().flat_map(|applied| applied.transactions) {
+            let hash = transaction.hash();
+            if included.contains(&hash) {
+                continue;
+            }
+
+            let state = self.executor.state();
+            let sender = transaction.from();
+            let cost = transaction.value() + transaction.gas() * transaction.gas_price();
+            if state.get_balance(sender) < cost {
+                continue;
+            }
+            if self.pool.add_transaction_for_nonce(transaction, state.get_nonce(sender)).is_ok() {
+                reinjected.push(hash);
+            }
+        }
+
+        Ok(reinjected)
+    }
+
+    /// Replay `blocks` as the first blocks of the chain on top of `genesis_state`
+    ///
+    /// Fails if any block's state root differs from the one recorded. Returns
+    /// the executor and the hashes BLOCKHASH can read after the last block.
+    fn replay(&self, genesis_state: Box<dyn State>, blocks: &[AppliedBlock]) -> Result<(TransactionExecutor, BTreeMap<u64, H256>)> {
+        let mut executor = TransactionExecutor::new(genesis_state, self.executor.get_context().clone());
+        let mut recent_hashes = BTreeMap::new();
+
+        for (index, applied) in blocks.iter().enumerate() {
+            let number = index as u64 + 1;
+            Self::execute_block(&mut executor, &mut recent_hashes, number, &applied.block, applied.transactions.clone())?;
+
+            let state_root = executor.state().state_root();
+            if state_root != applied.state_root {
+                return Err(OlympusError::InvalidBlock(format!(
+                    "State root mismatch replaying block {} ({:?}): expected {:?}, got {:?}",
+                    number, applied.block.hash(), applied.state_root, state_root
+                )));
+            }
+        }
+
+        Ok((executor, recent_hashes))
+    }
+
+    /// Execute a block's transactions as block `number`
+    ///
+    /// BLOCKHASH reads the hashes in `recent_hashes`, which then records the
+    /// block's own hash and drops the one falling out of BLOCKHASH's window.
+    fn execute_block(
+        executor: &mut TransactionExecutor,
+        recent_hashes: &mut BTreeMap<u64, H256>,
+        number: u64,
+        block: &Block,
+        transactions: Vec<Transaction>,
+    ) -> Result<Vec<TransactionExecutionResult>> {
+        let mut context = executor.get_context().clone();
+        context.block_number = U256::from(number);
+        context.prevrandao = DagConsensus::block_randomness(block);
+        executor.update_context(context);
+        executor.set_block_hashes(Arc::new(recent_hashes.clone()));
+
+        let results = executor.execute_block_transactions(transactions)?;
+        recent_hashes.insert(number, block.hash());
+        while recent_hashes.len() > BLOCK_HASH_WINDOW as usize {
+            recent_hashes.pop_first();
+        }
+        Ok(results)
+    }
+
+    /// Get the blocks applied so far, in consensus order
+    pub fn history(&self) -> &[AppliedBlock] {
+        &self.history
+    }
+
+    /// Get the number of blocks applied
+    pub fn head_number(&self) -> u64 {
+        self.head_number
+    }
+
+    /// Get transaction executor
+    pub fn executor(&self) -> &TransactionExecutor {
+        &self.executor
+    }
+
+    /// Get mutable transaction executor
+    pub fn executor_mut(&mut self) -> &mut TransactionExecutor {
+        &mut self.executor
+    }
+
+    /// Get transaction pool
+    pub fn pool(&self) -> &TransactionPool {
+        &self.pool
+    }
+
+    /// Get mutable transaction pool
+    pub fn pool_mut(&mut self) -> &mut TransactionPool {
+        &mut self.pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::Signature;
+    use crate::evm::state::{MemoryState, State};
+    use crate::evm::transaction_executor::TransactionExecutionContext;
+    use crate::H256;
+
+    /// Signed transfer to an ordinary account
+    fn transaction(nonce: u64) -> Transaction {
+        let mut transaction = Transaction::new(
+            U256::from(1_000),
+            U256::from(2_000_000_000u64),
+            U256::from(21_000),
+            Address::repeat_byte(0x42),
+            vec![],
+            U256::from(nonce),
+        );
+        transaction.sign_with_secret(&[0x21u8; 32]).unwrap();
+        transaction
+    }
+
+    fn block(links: Vec<H256>) -> Block {
+        Block::new(
+            Address::zero(),
+            H256::zero(),
+            vec![],
+            links,
+            vec![],
+            H256::zero(),
+            H256::zero(),
+            H256::zero(),
+            0,
+            U256::zero(),
+            Signature { v: 27, r: H256::zero(), s: H256::zero() },
+        )
+    }
+
+    #[test]
+    fn test_block_application_promotes_queued_transaction() {
+        let mut state = MemoryState::new();
+        let sender = transaction(0).from();
+        state.set_balance(sender, U256::from(10u64).pow(U256::from(18)));
+        let executor = TransactionExecutor::new(Box::new(state), TransactionExecutionContext::default());
+        let mut chain = ChainManager::new(executor, TransactionPool::new(10));
+
+        chain.pool_mut().add_transaction_for_nonce(transaction(1), 0).unwrap();
+        assert_eq!(chain.pool().get_queued_transactions().len(), 1);
+
+        let included = transaction(0);
+        let results = chain.apply_block(&block(vec![included.hash()]), vec![included]).unwrap();
+        assert!(results[0].success);
+
+        assert!(chain.pool().get_queued_transactions().is_empty());
+        let pending = chain.pool().get_pending_transactions();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].hash(), transaction(1).hash());
+    }
+
+    #[test]
+    fn test_included_replacement_evicts_replaced_transaction() {
+        let mut state = MemoryState::new();
+        let sender = transaction(0).from();
+        state.set_balance(sender, U256::from(10u64).pow(U256::from(18)));
+        let executor = TransactionExecutor::new(Box::new(state), TransactionExecutionContext::default());
+        let mut chain = ChainManager::new(executor, TransactionPool::new(10));
+
+        let replaced = transaction(0);
+        let mut replacement = transaction(0);
+        replacement.gas_price = U256::from(3_000_000_000u64);
+        replacement.sign_with_secret(&[0x21u8; 32]).unwrap();
+        chain.pool_mut().add_transaction(replaced.clone()).unwrap();
+        chain.pool_mut().add_transaction(replacement.clone()).unwrap();
+        chain.pool_mut().add_transaction(transaction(1)).unwrap();
+
+        let results = chain.apply_block(&block(vec![replacement.hash()]), vec![replacement.clone()]).unwrap();
+        assert!(results[0].success);
+
+        let pending: Vec<_> = chain.pool().get_pending_transactions().iter().map(|tx| tx.hash()).collect();
+        assert_eq!(pending, vec![transaction(1).hash()]);
+        assert!(chain.pool_mut().remove_included(&[replacement]).is_empty());
+    }
+
+    #[test]
+    fn test_reorg_returns_reverted_transactions_to_pool() {
+        let mut other = transaction(0);
+        other.sign_with_secret(&[0x31u8; 32]).unwrap();
+        let sender = transaction(0).from();
+        let genesis = || {
+            let mut state = MemoryState::new();
+            state.set_balance(sender, U256::from(10u64).pow(U256::from(18)));
+            state.set_balance(other.from(), U256::from(10u64).pow(U256::from(18)));
+            Box::new(state)
+        };
+        let executor = TransactionExecutor::new(genesis(), TransactionExecutionContext::default());
+        let mut chain = ChainManager::new(executor, TransactionPool::new(10));
+
+        let (first, second) = (transaction(0), transaction(1));
+        chain.apply_block(&block(vec![first.hash()]), vec![first]).unwrap();
+        chain.apply_block(&block(vec![second.hash(), other.hash()]), vec![second.clone(), other.clone()]).unwrap();
+        assert!(chain.pool().get_pending_transactions().is_empty());
+
+        // The new branch re-includes `other` but not `second`
+        let branch = vec![(block(vec![other.hash()]), vec![other.clone()])];
+        let reinjected = chain.reorg(1, branch, genesis()).unwrap();
+        assert_eq!(reinjected, vec![second.hash()]);
+        assert_eq!(chain.head_number(), 2);
+        assert_eq!(chain.executor().state().get_nonce(sender), 1);
+        assert_eq!(chain.executor().state().get_nonce(other.from()), 1);
+
+        let pending: Vec<_> = chain.pool().get_pending_transactions().iter().map(|tx| tx.hash()).collect();
+        assert_eq!(pending, vec![second.hash()]);
+    }
+
+    #[test]
+    fn test_reorg_to_invalid_branch_leaves_chain_untouched() {
+        let sender = transaction(0).from();
+        let genesis = || {
+            let mut state = MemoryState::new();
+            state.set_balance(sender, U256::from(10u64).pow(U256::from(18)));
+            Box::new(state)
+        };
+        let executor = TransactionExecutor::new(genesis(), TransactionExecutionContext::default());
+        let mut chain = ChainManager::new(executor, TransactionPool::new(10));
+
+        let (first, second) = (transaction(0), transaction(1));
+        chain.apply_block(&block(vec![first.hash()]), vec![first]).unwrap();
+        chain.apply_block(&block(vec![second.hash()]), vec![second.clone()]).unwrap();
+        let state_root = chain.executor().state().state_root();
+
+        // The second block of the new branch does not match its links
+        let replacement = transaction(1);
+        let branch = vec![
+            (block(vec![replacement.hash()]), vec![replacement]),
+            (block(vec![]), vec![transaction(2)]),
+        ];
+        assert!(chain.reorg(1, branch, genesis()).is_err());
+
+        assert_eq!(chain.head_number(), 2);
+        assert_eq!(chain.history().len(), 2);
+        assert_eq!(chain.history()[1].transactions[0].hash(), second.hash());
+        assert_eq!(chain.executor().state().state_root(), state_root);
+        assert_eq!(chain.executor().state().get_nonce(sender), 2);
+        assert!(chain.pool().get_pending_transactions().is_empty());
+    }
+
+    #[test]
+    fn test_block_with_mismatched_links_rejected() {
+        let executor = TransactionExecutor::new(Box::new(MemoryState::new()), TransactionExecutionContext::default());
+        let mut chain = ChainManager::new(executor, TransactionPool::new(10));
+
+        assert!(chain.apply_block(&block(vec![]), vec![transaction(0)]).is_err());
+        assert_eq!(chain.head_number(), 0);
+    }
+
+    #[test]
+    fn test_import_block_applies_pooled_transactions() {
+        let mut state = MemoryState::new();
+        let sender = transaction(0).from();
+        state.set_balance(sender, U256::from(10u64).pow(U256::from(18)));
+        let executor = TransactionExecutor::new(Box::new(state), TransactionExecutionContext::default());
+        let mut chain = ChainManager::new(executor, TransactionPool::new(10));
+
+        let (first, second) = (transaction(0), transaction(1));
+        chain.pool_mut().add_transaction(first.clone()).unwrap();
+        let signed = |links: Vec<H256>| {
+            let secp = secp256k1::Secp256k1::new();
+            let key = secp256k1::SecretKey::from_slice(&[0x77; 32]).unwrap();
+            let mut block = block(links);
+            block.from = crate::common::public_key_to_address(&secp256k1::PublicKey::from_secret_key(&secp, &key));
+            block.sign(&[0x77; 32]).unwrap();
+            block
+        };
+
+        // Unsigned blocks and blocks linking transactions the pool lacks are refused
+        assert!(chain.import_block(&block(vec![first.hash()])).is_err());
+        assert!(chain.import_block(&signed(vec![first.hash(), second.hash()])).is_err());
+        // So are blocks linking a pooled transaction without a recoverable sender
+        let unsigned = Transaction::new(U256::zero(), U256::from(1_000_000_000u64), U256::from(21_000), Address::repeat_byte(0x43), vec![], U256::zero());
+        chain.pool_mut().add_transaction(unsigned.clone()).unwrap();
+        assert!(chain.import_block(&signed(vec![unsigned.hash()])).is_err());
+        assert_eq!(chain.head_number(), 0);
+
+        let results = chain.import_block(&signed(vec![first.hash()])).unwrap();
+        assert!(results[0].success);
+        assert_eq!(chain.head_number(), 1);
+        assert_eq!(chain.executor().state().get_nonce(sender), 1);
+        assert_eq!(chain.executor().state().get_balance(Address::repeat_byte(0x42)), U256::from(1_000));
+        assert!(chain.pool().get_transaction(first.hash()).is_none());
+    }
+}
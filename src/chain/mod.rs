@@ -0,0 +1,5 @@
+//! Chain management: block application and mempool coordination
+
+pub mod manager;
+
+pub use manager::*;
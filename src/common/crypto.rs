@@ -1,6 +1,6 @@
 //! Cryptographic utilities
 
-use crate::H256;
+use crate::{Address, H256, U256};
 use sha3::{Digest, Keccak256};
 
 /// Calculate Keccak256 hash
@@ -16,3 +16,38 @@ pub fn keccak256_rlp<T: rlp::Encodable>(data: &T) -> H256 {
     data.rlp_append(&mut stream);
     keccak256(&stream.out())
 }
+
+/// Derive an account address from a secp256k1 public key
+pub fn public_key_to_address(public_key: &secp256k1::PublicKey) -> Address {
+    let public_key_bytes = public_key.serialize_uncompressed();
+    let hash = keccak256(&public_key_bytes[1..]); // Skip the 0x04 prefix
+    Address::from_slice(&hash[12..])
+}
+
+/// Address of a contract created by `sender` at `nonce`: keccak256(rlp([sender, nonce]))[12..]
+pub fn contract_address_from_sender(sender: Address, nonce: U256) -> Address {
+    let mut stream = rlp::RlpStream::new_list(2);
+    stream.append(&sender);
+    stream.append(&nonce);
+    let hash = keccak256(&stream.out());
+    Address::from_slice(&hash[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_contract_address_from_sender() {
+        let sender = Address::from_str("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0").unwrap();
+        assert_eq!(
+            contract_address_from_sender(sender, U256::zero()),
+            Address::from_str("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d").unwrap()
+        );
+        assert_eq!(
+            contract_address_from_sender(sender, U256::one()),
+            Address::from_str("343c43a37d37dff08ae8c4a11544c718abb4fcf8").unwrap()
+        );
+    }
+}
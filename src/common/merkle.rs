@@ -0,0 +1,128 @@
+//! Binary Merkle trees over keccak256
+//!
+//! Leaves are hashed as keccak256(0x00 || leaf) and each parent as
+//! keccak256(0x01 || left || right), so a leaf can never pass for an inner
+//! node. A level with an odd number of nodes pairs its last node with the zero
+//! hash; duplicating it instead would let `[a, b, c]` and `[a, b, c, c]` share
+//! a root (CVE-2012-2459).
+
+use crate::H256;
+
+/// Compute the Merkle root of `leaves`, zero for no leaves
+pub fn merkle_root(leaves: &[H256]) -> H256 {
+    if leaves.is_empty() {
+        return H256::zero();
+    }
+
+    let mut level: Vec<H256> = leaves.iter().map(hash_leaf).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Build the proof that the leaf at `index` is in the tree
+///
+/// The proof lists the sibling at each level, from the leaves up.
+pub fn merkle_proof(leaves: &[H256], index: usize) -> Option<Vec<H256>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut proof = Vec::new();
+    let mut level: Vec<H256> = leaves.iter().map(hash_leaf).collect();
+    let mut index = index;
+    while level.len() > 1 {
+        let sibling = if index.is_multiple_of(2) { index + 1 } else { index - 1 };
+        proof.push(level.get(sibling).copied().unwrap_or_default());
+        level = next_level(&level);
+        index /= 2;
+    }
+    Some(proof)
+}
+
+/// Check that `proof` places `leaf` at `index` under `root`
+pub fn verify_proof(root: H256, leaf: H256, index: usize, proof: &[H256]) -> bool {
+    let mut node = hash_leaf(&leaf);
+    let mut index = index;
+    for sibling in proof {
+        node = if index.is_multiple_of(2) { hash_pair(&node, sibling) } else { hash_pair(sibling, &node) };
+        index /= 2;
+    }
+    index == 0 && node == root
+}
+
+/// Hash each pair of nodes into the level above
+fn next_level(level: &[H256]) -> Vec<H256> {
+    level.chunks(2)
+        .map(|pair| hash_pair(&pair[0], &pair.get(1).copied().unwrap_or_default()))
+        .collect()
+}
+
+/// Hash a leaf into the bottom level
+fn hash_leaf(leaf: &H256) -> H256 {
+    let mut data = [0u8; 33];
+    data[1..].copy_from_slice(leaf.as_bytes());
+    crate::common::keccak256(&data)
+}
+
+/// Hash two child nodes into their parent
+fn hash_pair(left: &H256, right: &H256) -> H256 {
+    let mut data = [0u8; 65];
+    data[0] = 0x01;
+    data[1..33].copy_from_slice(left.as_bytes());
+    data[33..].copy_from_slice(right.as_bytes());
+    crate::common::keccak256(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: u8) -> Vec<H256> {
+        (1..=count).map(H256::repeat_byte).collect()
+    }
+
+    #[test]
+    fn test_roots_of_small_trees() {
+        let [a, b, c, d] = [1, 2, 3, 4].map(H256::repeat_byte).map(|leaf| hash_leaf(&leaf));
+        let ab = hash_pair(&a, &b);
+
+        assert_eq!(merkle_root(&[]), H256::zero());
+        assert_eq!(merkle_root(&leaves(1)), a);
+        assert_eq!(merkle_root(&leaves(2)), ab);
+        assert_eq!(merkle_root(&leaves(3)), hash_pair(&ab, &hash_pair(&c, &H256::zero())));
+        assert_eq!(merkle_root(&leaves(4)), hash_pair(&ab, &hash_pair(&c, &d)));
+    }
+
+    #[test]
+    fn test_duplicated_last_leaf_changes_root() {
+        let [a, b, c] = [1, 2, 3].map(H256::repeat_byte);
+        assert_ne!(merkle_root(&[a, b, c]), merkle_root(&[a, b, c, c]));
+        assert_ne!(merkle_root(&[a, b, c]), merkle_root(&[a, b, c, H256::zero()]));
+    }
+
+    #[test]
+    fn test_inner_node_not_accepted_as_leaf() {
+        let leaves = leaves(4);
+        let root = merkle_root(&leaves);
+        let [a, b] = [leaves[0], leaves[1]].map(|leaf| hash_leaf(&leaf));
+        let proof = merkle_proof(&leaves, 0).unwrap();
+        assert!(!verify_proof(root, hash_pair(&a, &b), 0, &proof[1..]));
+    }
+
+    #[test]
+    fn test_proofs_verify_every_leaf() {
+        for count in 1..=7 {
+            let leaves = leaves(count);
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = merkle_proof(&leaves, index).unwrap();
+                assert!(verify_proof(root, *leaf, index, &proof));
+                assert!(!verify_proof(root, H256::repeat_byte(0xff), index, &proof));
+                assert!(!verify_proof(root, *leaf, index + (1 << proof.len()), &proof));
+            }
+            assert!(merkle_proof(&leaves, leaves.len()).is_none());
+        }
+    }
+}
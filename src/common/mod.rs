@@ -1,7 +1,13 @@
 //! Common utilities and helpers
 
+pub mod clock;
 pub mod crypto;
+pub mod merkle;
+pub mod trie;
 pub mod utils;
 
+pub use clock::*;
 pub use crypto::*;
+pub use merkle::*;
+pub use trie::*;
 pub use utils::*;
@@ -0,0 +1,153 @@
+//! Merkle-Patricia trie roots
+//!
+//! Roots are computed from the full key set in one pass, without keeping the
+//! trie around. Node encoding follows the Ethereum yellow paper: children
+//! whose RLP is shorter than 32 bytes are inlined, longer ones are referenced
+//! by keccak256.
+
+use crate::common::keccak256;
+use crate::H256;
+use rlp::RlpStream;
+
+/// Root of the empty trie, keccak256 of the RLP empty string
+pub const EMPTY_TRIE_ROOT: H256 = H256([
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+]);
+
+/// Compute the root of a trie holding `entries`
+///
+/// Later entries replace earlier ones with the same key.
+pub fn trie_root(entries: impl IntoIterator<Item = (Vec<u8>, Vec<u8>)>) -> H256 {
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = entries.into_iter()
+        .map(|(key, value)| (to_nibbles(&key), value))
+        .collect();
+    entries.reverse();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries.dedup_by(|a, b| a.0 == b.0);
+
+    let mut stream = RlpStream::new();
+    encode_node(&mut stream, &entries, 0);
+    keccak256(&stream.out())
+}
+
+/// Compute the root of a trie keyed by keccak256 of each key
+pub fn secure_trie_root(entries: impl IntoIterator<Item = (Vec<u8>, Vec<u8>)>) -> H256 {
+    trie_root(entries.into_iter().map(|(key, value)| (keccak256(&key).as_bytes().to_vec(), value)))
+}
+
+/// Split bytes into nibbles, high nibble first
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+}
+
+/// Hex-prefix encode a nibble path
+fn hex_prefix(nibbles: &[u8], leaf: bool) -> Vec<u8> {
+    let flag = if leaf { 0x20 } else { 0x00 };
+    let mut encoded = Vec::with_capacity(nibbles.len() / 2 + 1);
+    let rest = if !nibbles.len().is_multiple_of(2) {
+        encoded.push(flag | 0x10 | nibbles[0]);
+        &nibbles[1..]
+    } else {
+        encoded.push(flag);
+        nibbles
+    };
+    encoded.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+    encoded
+}
+
+/// Append the node holding `entries`, whose keys all share the first `depth` nibbles
+fn encode_node(stream: &mut RlpStream, entries: &[(Vec<u8>, Vec<u8>)], depth: usize) {
+    match entries {
+        [] => {
+            stream.append_empty_data();
+        }
+        [(key, value)] => {
+            stream.begin_list(2).append(&hex_prefix(&key[depth..], true)).append(value);
+        }
+        _ => {
+            // Entries are sorted, so the first and last bound the shared prefix
+            let first = &entries[0].0;
+            let last = &entries[entries.len() - 1].0;
+            let shared = first[depth..].iter()
+                .zip(&last[depth..])
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if shared > 0 {
+                stream.begin_list(2).append(&hex_prefix(&first[depth..depth + shared], false));
+                append_child(stream, entries, depth + shared);
+                return;
+            }
+
+            stream.begin_list(17);
+            // Only the first entry can end here, since it sorts before its extensions
+            let (value, mut rest) = if first.len() == depth {
+                (Some(&entries[0].1), &entries[1..])
+            } else {
+                (None, entries)
+            };
+            for nibble in 0..16u8 {
+                let count = rest.iter().take_while(|(key, _)| key[depth] == nibble).count();
+                if count == 0 {
+                    stream.append_empty_data();
+                } else {
+                    append_child(stream, &rest[..count], depth + 1);
+                }
+                rest = &rest[count..];
+            }
+            match value {
+                Some(value) => stream.append(value),
+                None => stream.append_empty_data(),
+            };
+        }
+    }
+}
+
+/// Append a reference to a child node, inlining nodes shorter than a hash
+fn append_child(stream: &mut RlpStream, entries: &[(Vec<u8>, Vec<u8>)], depth: usize) {
+    let mut child = RlpStream::new();
+    encode_node(&mut child, entries, depth);
+    let encoded = child.out();
+    if encoded.len() < 32 {
+        stream.append_raw(&encoded, 1);
+    } else {
+        stream.append(&keccak256(&encoded));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, value: &str) -> (Vec<u8>, Vec<u8>) {
+        (key.as_bytes().to_vec(), value.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_known_roots() {
+        assert_eq!(trie_root(Vec::new()), EMPTY_TRIE_ROOT);
+        assert_eq!(keccak256(&rlp::NULL_RLP), EMPTY_TRIE_ROOT);
+
+        let puppy = vec![
+            entry("do", "verb"),
+            entry("horse", "stallion"),
+            entry("doge", "coin"),
+            entry("dog", "puppy"),
+        ];
+        assert_eq!(
+            trie_root(puppy),
+            "5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84".parse().unwrap()
+        );
+
+        let dogs = vec![
+            entry("doe", "reindeer"),
+            entry("dog", "puppy"),
+            entry("dogglesworth", "cat"),
+        ];
+        assert_eq!(
+            trie_root(dogs),
+            "8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3".parse().unwrap()
+        );
+    }
+}
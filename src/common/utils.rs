@@ -1,13 +1,10 @@
 //! General utilities
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::common::clock::{Clock, SystemClock};
 
 /// Get current timestamp in seconds
 pub fn current_timestamp() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
+    SystemClock.now()
 }
 
 /// Convert bytes to hex string
@@ -1,11 +1,21 @@
 //! DAG consensus implementation
 
 use crate::{Address, H256, Result, OlympusError};
+use crate::core::approve::Approve;
 use crate::core::block::Block;
-use crate::consensus::witness::WitnessManager;
-use std::collections::{HashMap, HashSet};
+use crate::consensus::witness::{WitnessHistory, WitnessManager};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
 use serde::{Deserialize, Serialize};
 
+/// Share of total witness stake, as numerator and denominator, a block's
+/// approvers must exceed for it to be confirmed
+pub const CONFIRMATION_STAKE_FRACTION: (u64, u64) = (2, 3);
+
+/// Default number of blocks above the stable frontier one consensus pass may
+/// stabilize; deeper blocks wait for the next pass
+pub const MAX_STABILITY_DEPTH: usize = 16_384;
+
 /// DAG consensus engine
 pub struct DagConsensus {
     /// Current epoch
@@ -16,10 +26,22 @@ pub struct DagConsensus {
     pub witness_manager: WitnessManager,
     /// Block DAG
     pub dag: BlockDag,
-    /// Confirmation threshold
-    pub confirmation_threshold: u64,
     /// Epoch duration in blocks
     pub epoch_duration: u64,
+    /// Most recently processed block
+    pub head: Option<H256>,
+    /// Check DAG invariants after every processed block
+    pub verify_invariants: bool,
+    /// Witness sets of past epochs
+    pub witness_history: WitnessHistory,
+    /// Approvals ignored because their signer was not a current witness
+    pub rejected_approvals: u64,
+    /// Traversal depth cap for a single stabilization pass
+    pub max_stability_depth: usize,
+    /// Seed election proofs for the current epoch must be made against
+    pub epoch_seed: H256,
+    /// Accounts holding a verified election proof for the current epoch
+    pub elected: HashSet<Address>,
 }
 
 /// Block DAG structure
@@ -29,7 +51,7 @@ pub struct BlockDag {
     pub blocks: HashMap<H256, Block>,
     /// Block references (parents)
     pub references: HashMap<H256, Vec<H256>>,
-    /// Block approvals
+    /// Blocks approving each block
     pub approvals: HashMap<H256, Vec<H256>>,
     /// Confirmed blocks
     pub confirmed: HashSet<H256>,
@@ -37,6 +59,31 @@ pub struct BlockDag {
     pub stable: HashSet<H256>,
     /// Maximum number of blocks to keep in memory
     pub max_blocks: usize,
+    /// Block hashes in insertion order, oldest first
+    pub order: VecDeque<H256>,
+    /// First block seen for each creator and previous block
+    pub successors: HashMap<(Address, H256), H256>,
+    /// Pairs of blocks from one creator claiming the same previous block
+    pub conflicts: Vec<(H256, H256)>,
+}
+
+/// Snapshot of the consensus state, for debugging
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsensusSnapshot {
+    /// Current epoch
+    pub epoch: u64,
+    /// Witnesses for current epoch
+    pub witnesses: Vec<Address>,
+    /// Number of blocks in the DAG
+    pub block_count: usize,
+    /// Number of confirmed blocks
+    pub confirmed_count: usize,
+    /// Number of stable blocks
+    pub stable_count: usize,
+    /// Most recently processed block
+    pub head: Option<H256>,
+    /// Number of approvals ignored from non-witnesses
+    pub rejected_approvals: u64,
 }
 
 /// Consensus result
@@ -54,28 +101,39 @@ pub struct ConsensusResult {
 
 impl DagConsensus {
     /// Create new DAG consensus engine with configurable parameters
-    pub fn new(min_witnesses: u64, max_witnesses: u64, confirmation_threshold: u64, epoch_duration: u64) -> Self {
+    pub fn new(min_witnesses: u64, max_witnesses: u64, epoch_duration: u64) -> Self {
         Self {
             current_epoch: 0,
             witnesses: Vec::new(),
             witness_manager: WitnessManager::new(min_witnesses, max_witnesses),
             dag: BlockDag::new_default(),
-            confirmation_threshold,
             epoch_duration,
+            head: None,
+            verify_invariants: false,
+            witness_history: WitnessHistory::default(),
+            rejected_approvals: 0,
+            max_stability_depth: MAX_STABILITY_DEPTH,
+            epoch_seed: H256::zero(),
+            elected: HashSet::new(),
         }
     }
     
     /// Create new DAG consensus engine with default parameters
     pub fn new_default() -> Self {
-        Self::new(3, 21, 2, 100)
+        Self::new(3, 21, 100)
     }
 
     /// Process new block
+    ///
+    /// The block must be signed by its creator; otherwise it is rejected
+    /// before it can record approvals in the creator's name.
     pub fn process_block(&mut self, block: Block) -> Result<ConsensusResult> {
+        block.validate_signature()?;
         let block_hash = block.hash();
         
         // Add block to DAG
         self.dag.add_block(block_hash, block)?;
+        self.head = Some(block_hash);
         
         // Update references and approvals
         self.update_dag_structure(&block_hash)?;
@@ -85,27 +143,74 @@ impl DagConsensus {
         
         // Update epoch if necessary
         if self.should_update_epoch() {
-            self.update_epoch()?;
+            self.advance_epoch(consensus_result.next_witnesses.clone())?;
+        }
+        
+        if self.verify_invariants {
+            self.check_invariants()?;
         }
         
         Ok(consensus_result)
     }
 
+    /// Accept an election proof for the current epoch
+    ///
+    /// The proof is checked against the epoch seed and the sender's stake;
+    /// only accounts with an accepted proof may become next epoch's witnesses.
+    pub fn process_approve(&mut self, approve: &Approve) -> Result<()> {
+        let stake = self.witness_manager.get_stake(approve.from);
+        approve.validate_election(self.epoch_seed, stake)?;
+        self.elected.insert(approve.from);
+        Ok(())
+    }
+
+    /// Derive the randomness of a block, exposed to contracts as PREVRANDAO
+    ///
+    /// Mixes the last stable block hash, which the block creator cannot choose,
+    /// with the block's approvals and its signing hash. The signature is left
+    /// out, so the creator cannot grind randomness by re-signing the block.
+    pub fn block_randomness(block: &Block) -> H256 {
+        let mut approves = block.approves.clone();
+        approves.sort();
+
+        let mut data = Vec::with_capacity(32 * (approves.len() + 2));
+        data.extend_from_slice(block.last_stable_block.as_bytes());
+        for approve in &approves {
+            data.extend_from_slice(approve.as_bytes());
+        }
+        data.extend_from_slice(block.signing_hash().as_bytes());
+        crate::common::keccak256(&data)
+    }
+
     /// Update DAG structure with new block
     fn update_dag_structure(&mut self, block_hash: &H256) -> Result<()> {
         if let Some(block) = self.dag.blocks.get(block_hash) {
             // Add parent references
             for parent in &block.parents {
                 self.dag.references.entry(*block_hash)
-                    .or_insert_with(Vec::new)
+                    .or_default()
                     .push(*parent);
             }
             
-            // Add approvals
-            for approval in &block.approves {
-                self.dag.approvals.entry(*block_hash)
-                    .or_insert_with(Vec::new)
-                    .push(*approval);
+            // A second block from one account on the same previous block forks its chain
+            match self.dag.successors.entry((block.from, block.previous)) {
+                Entry::Occupied(existing) => self.dag.conflicts.push((*existing.get(), *block_hash)),
+                Entry::Vacant(vacant) => {
+                    vacant.insert(*block_hash);
+                }
+            }
+
+            // Approvals are signed by the block creator, who must be a current witness
+            if !self.witnesses.contains(&block.from) {
+                self.rejected_approvals += block.approves.len() as u64;
+                return Ok(());
+            }
+
+            // Record this block as an approval of each block it approves
+            for approved in &block.approves {
+                self.dag.approvals.entry(*approved)
+                    .or_default()
+                    .push(*block_hash);
             }
         }
         
@@ -113,28 +218,28 @@ impl DagConsensus {
     }
 
     /// Check for consensus
+    ///
+    /// Of two conflicting blocks only one is ever confirmed: once a block is
+    /// confirmed, the other side of each of its conflicts is not. When both
+    /// sides qualify at once, the lower hash wins.
     fn check_consensus(&mut self) -> Result<ConsensusResult> {
-        let mut confirmed_blocks = Vec::new();
-        let mut stable_blocks = Vec::new();
-        
         // Find blocks that can be confirmed
-        for (block_hash, _block) in &self.dag.blocks {
-            if self.dag.confirmed.contains(block_hash) {
+        let mut candidates: Vec<H256> = self.dag.blocks.keys()
+            .filter(|block_hash| !self.dag.confirmed.contains(block_hash) && self.has_enough_confirmations(**block_hash))
+            .copied()
+            .collect();
+        candidates.sort();
+
+        let mut confirmed_blocks = Vec::with_capacity(candidates.len());
+        for block_hash in candidates {
+            if self.conflicts_with_confirmed(block_hash) {
                 continue;
             }
-            
-            // Check if block has enough confirmations
-            if self.has_enough_confirmations(*block_hash) {
-                self.dag.confirmed.insert(*block_hash);
-                confirmed_blocks.push(*block_hash);
-                
-                // Check if block can be marked as stable
-                if self.can_be_stable(*block_hash) {
-                    self.dag.stable.insert(*block_hash);
-                    stable_blocks.push(*block_hash);
-                }
-            }
+            self.dag.confirmed.insert(block_hash);
+            confirmed_blocks.push(block_hash);
         }
+
+        let stable_blocks = self.stabilize_confirmed_blocks();
         
         // Determine next epoch witnesses based on stable blocks
         let next_witnesses = self.select_next_witnesses(&stable_blocks)?;
@@ -147,31 +252,85 @@ impl DagConsensus {
         })
     }
 
+    /// Check if a block conflicts with a block already confirmed
+    fn conflicts_with_confirmed(&self, block_hash: H256) -> bool {
+        self.dag.conflicts.iter().any(|&(first, second)| {
+            (first == block_hash && self.dag.confirmed.contains(&second))
+                || (second == block_hash && self.dag.confirmed.contains(&first))
+        })
+    }
+
     /// Check if block has enough confirmations
+    ///
+    /// The stake of the distinct witnesses among the creators of approving
+    /// blocks must exceed `CONFIRMATION_STAKE_FRACTION` of the total stake of
+    /// the current witness set. Stake held by non-witnesses counts for neither.
     fn has_enough_confirmations(&self, block_hash: H256) -> bool {
-        if let Some(approvals) = self.dag.approvals.get(&block_hash) {
-            approvals.len() >= self.confirmation_threshold as usize
-        } else {
-            false
-        }
+        let Some(approvals) = self.dag.approvals.get(&block_hash) else {
+            return false;
+        };
+        let witnesses: HashSet<Address> = self.witnesses.iter().copied().collect();
+        let approvers: HashSet<Address> = approvals.iter()
+            .filter_map(|approval| self.dag.blocks.get(approval))
+            .map(|block| block.from)
+            .filter(|approver| witnesses.contains(approver))
+            .collect();
+
+        let approved_stake: u128 = approvers.iter().map(|&approver| self.witness_manager.get_stake(approver) as u128).sum();
+        let total_stake: u128 = witnesses.iter().map(|&witness| self.witness_manager.get_stake(witness) as u128).sum();
+        let (numerator, denominator) = CONFIRMATION_STAKE_FRACTION;
+        approved_stake * denominator as u128 > total_stake * numerator as u128
     }
 
-    /// Check if block can be marked as stable
-    fn can_be_stable(&self, block_hash: H256) -> bool {
-        // A block is stable if it's confirmed and all its dependencies are stable
-        if !self.dag.confirmed.contains(&block_hash) {
-            return false;
+    /// Mark every confirmed block whose references are all stable as stable
+    ///
+    /// A block is stable if it's confirmed and all its references are stable.
+    /// Stability spreads from already stable blocks through an explicit work
+    /// queue, each block counting its unstable references, so deep chains
+    /// need neither recursion nor repeated passes over the DAG. A pass walks
+    /// at most `max_stability_depth` blocks above the stable frontier. Returns
+    /// the newly stable blocks in the order they became stable.
+    fn stabilize_confirmed_blocks(&mut self) -> Vec<H256> {
+        let mut unstable_references: HashMap<H256, usize> = HashMap::new();
+        let mut dependents: HashMap<H256, Vec<H256>> = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        for block_hash in self.dag.confirmed.iter().filter(|hash| !self.dag.stable.contains(hash)) {
+            let references = self.dag.references.get(block_hash).map(Vec::as_slice).unwrap_or_default();
+            let mut pending = HashSet::new();
+            // References no longer in the DAG were evicted once stable
+            let unstable = |reference: &&H256| self.dag.blocks.contains_key(reference) && !self.dag.stable.contains(reference);
+            for reference in references.iter().filter(unstable) {
+                if pending.insert(*reference) {
+                    dependents.entry(*reference).or_default().push(*block_hash);
+                }
+            }
+            if pending.is_empty() {
+                queue.push_back((*block_hash, 1));
+            } else {
+                unstable_references.insert(*block_hash, pending.len());
+            }
         }
-        
-        if let Some(references) = self.dag.references.get(&block_hash) {
-            for reference in references {
-                if !self.dag.stable.contains(reference) {
-                    return false;
+
+        let mut stable_blocks = Vec::new();
+        while let Some((block_hash, depth)) = queue.pop_front() {
+            if !self.dag.stable.insert(block_hash) {
+                continue;
+            }
+            stable_blocks.push(block_hash);
+
+            for dependent in dependents.remove(&block_hash).unwrap_or_default() {
+                if let Some(count) = unstable_references.get_mut(&dependent) {
+                    *count -= 1;
+                    if *count == 0 && depth < self.max_stability_depth {
+                        unstable_references.remove(&dependent);
+                        queue.push_back((dependent, depth + 1));
+                    }
                 }
             }
         }
-        
-        true
+
+        stable_blocks
     }
 
     /// Select next epoch witnesses
@@ -180,25 +339,32 @@ impl DagConsensus {
         let mut witness_candidates = HashMap::new();
         
         for block_hash in stable_blocks {
-            if let Some(block) = self.dag.blocks.get(block_hash) {
+            if let Some(block) = self.dag.blocks.get(block_hash).filter(|block| self.elected.contains(&block.from)) {
                 let count = witness_candidates.entry(block.from).or_insert(0);
                 *count += 1;
             }
         }
         
-        // Select top witnesses by block count
+        // Select top witnesses by block count, ties broken by address so every node agrees
         let mut candidates: Vec<_> = witness_candidates.into_iter().collect();
-        candidates.sort_by(|a, b| b.1.cmp(&a.1));
+        candidates.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
         
-        let mut witnesses = Vec::new();
-        for (address, _) in candidates.into_iter().take(self.witness_manager.max_witnesses as usize) {
-            witnesses.push(address);
-        }
+        let mut witnesses: Vec<Address> = candidates.into_iter()
+            .take(self.witness_manager.max_witnesses as usize)
+            .map(|(address, _)| address)
+            .collect();
         
-        // Ensure minimum witnesses
-        while witnesses.len() < self.witness_manager.min_witnesses as usize {
-            // Add default witnesses if not enough candidates
-            witnesses.push(Address::zero());
+        // Ensure minimum witnesses by keeping current witnesses, highest stake first
+        let mut current = self.witnesses.clone();
+        current.sort_by(|a, b| self.witness_manager.get_stake(*b).cmp(&self.witness_manager.get_stake(*a)).then(a.cmp(b)));
+        current.dedup();
+        for address in current {
+            if witnesses.len() >= self.witness_manager.min_witnesses as usize {
+                break;
+            }
+            if !witnesses.contains(&address) {
+                witnesses.push(address);
+            }
         }
         
         Ok(witnesses)
@@ -209,9 +375,25 @@ impl DagConsensus {
         self.dag.stable.len() >= self.epoch_duration as usize
     }
 
-    /// Update epoch
-    fn update_epoch(&mut self) -> Result<()> {
+    /// Move to the next epoch with a new witness set
+    ///
+    /// The outgoing witness set and stakes are recorded in the witness history.
+    /// The epoch seed is mixed with the head block's randomness and election
+    /// proofs of the outgoing epoch are dropped. Fails, leaving the epoch
+    /// unchanged, if the new set is outside the witness manager's size limits.
+    pub fn advance_epoch(&mut self, next_witnesses: Vec<Address>) -> Result<()> {
+        self.witness_manager.rotate_witnesses(next_witnesses.clone())?;
+        let outgoing = self.witness_manager.stakes_of(&self.witnesses);
+        self.witness_history.record(self.current_epoch, outgoing);
         self.current_epoch += 1;
+        self.witnesses = next_witnesses;
+
+        let mut seed = self.epoch_seed.as_bytes().to_vec();
+        if let Some(head) = self.head.and_then(|head| self.dag.blocks.get(&head)) {
+            seed.extend_from_slice(Self::block_randomness(head).as_bytes());
+        }
+        self.epoch_seed = crate::common::keccak256(&seed);
+        self.elected.clear();
         
         // Clear old blocks to prevent memory growth
         self.dag.clear_old_blocks();
@@ -219,6 +401,14 @@ impl DagConsensus {
         Ok(())
     }
 
+    /// Get the witness sets of past epochs together with the current one
+    pub fn witness_sets(&self) -> WitnessHistory {
+        let mut history = self.witness_history.clone();
+        history.current_epoch = self.current_epoch;
+        history.record(self.current_epoch, self.witness_manager.stakes_of(&self.witnesses));
+        history
+    }
+
     /// Get stable blocks
     pub fn get_stable_blocks(&self) -> Vec<H256> {
         self.dag.stable.iter().cloned().collect()
@@ -229,6 +419,11 @@ impl DagConsensus {
         self.dag.confirmed.iter().cloned().collect()
     }
 
+    /// Get pairs of blocks from one creator claiming the same previous block
+    pub fn get_conflicts(&self) -> Vec<(H256, H256)> {
+        self.dag.conflicts.clone()
+    }
+
     /// Check if block is stable
     pub fn is_stable(&self, block_hash: H256) -> bool {
         self.dag.stable.contains(&block_hash)
@@ -238,6 +433,45 @@ impl DagConsensus {
     pub fn is_confirmed(&self, block_hash: H256) -> bool {
         self.dag.confirmed.contains(&block_hash)
     }
+
+    /// Take a snapshot of the consensus state
+    pub fn snapshot(&self) -> ConsensusSnapshot {
+        ConsensusSnapshot {
+            epoch: self.current_epoch,
+            witnesses: self.witnesses.clone(),
+            block_count: self.dag.blocks.len(),
+            confirmed_count: self.dag.confirmed.len(),
+            stable_count: self.dag.stable.len(),
+            head: self.head,
+            rejected_approvals: self.rejected_approvals,
+        }
+    }
+
+    /// Check the internal consistency of the DAG
+    ///
+    /// Confirmed blocks must be in the DAG, stable blocks must be confirmed, and
+    /// every reference of a stable block still held in the DAG must be stable.
+    pub fn check_invariants(&self) -> Result<()> {
+        if let Some(hash) = self.dag.confirmed.iter().find(|hash| !self.dag.blocks.contains_key(hash)) {
+            return Err(OlympusError::Consensus(format!("Confirmed block {:?} is not in the DAG", hash)));
+        }
+
+        for hash in &self.dag.stable {
+            if !self.dag.confirmed.contains(hash) {
+                return Err(OlympusError::Consensus(format!("Stable block {:?} is not confirmed", hash)));
+            }
+
+            for reference in self.dag.get_references(*hash) {
+                if self.dag.blocks.contains_key(&reference) && !self.dag.stable.contains(&reference) {
+                    return Err(OlympusError::Consensus(
+                        format!("Stable block {:?} references unstable block {:?}", hash, reference)
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl BlockDag {
@@ -250,6 +484,9 @@ impl BlockDag {
             confirmed: HashSet::new(),
             stable: HashSet::new(),
             max_blocks,
+            order: VecDeque::new(),
+            successors: HashMap::new(),
+            conflicts: Vec::new(),
         }
     }
     
@@ -259,39 +496,79 @@ impl BlockDag {
     }
 
     /// Add block to DAG
+    ///
+    /// Every parent must already be in the DAG. A new block then has no
+    /// children, so the only cycle it can close is a reference to itself.
     pub fn add_block(&mut self, block_hash: H256, block: Block) -> Result<()> {
         if self.blocks.contains_key(&block_hash) {
             return Err(OlympusError::Consensus("Block already exists in DAG".to_string()));
         }
+
+        for parent in &block.parents {
+            if *parent == block_hash {
+                return Err(OlympusError::Consensus(format!("Block {:?} references itself as a parent", block_hash)));
+            }
+            if !self.blocks.contains_key(parent) {
+                return Err(OlympusError::Consensus(format!("Parent {:?} of block {:?} is not in the DAG", parent, block_hash)));
+            }
+        }
         
         self.blocks.insert(block_hash, block);
+        self.order.push_back(block_hash);
         Ok(())
     }
 
     /// Clear old blocks to prevent memory growth
+    ///
+    /// Blocks are evicted oldest first. Stable blocks are final and go unless
+    /// they are on the stable frontier, referenced by an unstable block or
+    /// approving an unconfirmed one; unstable blocks go only if no block
+    /// references them. The DAG may stay over capacity if too few qualify.
     pub fn clear_old_blocks(&mut self) {
-        // Keep only recent blocks up to max_blocks limit
-        if self.blocks.len() > self.max_blocks {
-            let mut to_remove = Vec::new();
-            let mut count = 0;
-            
-            for (hash, _) in &self.blocks {
-                if count < self.blocks.len() - self.max_blocks {
-                    to_remove.push(*hash);
-                    count += 1;
-                } else {
-                    break;
-                }
+        if self.blocks.len() <= self.max_blocks {
+            return;
+        }
+
+        let mut referenced = HashSet::new();
+        let mut frontier = HashSet::new();
+        for (hash, references) in self.references.iter().filter(|(hash, _)| self.blocks.contains_key(hash)) {
+            referenced.extend(references.iter().copied());
+            if !self.stable.contains(hash) {
+                frontier.extend(references.iter().copied());
             }
-            
-            for hash in to_remove {
-                self.blocks.remove(&hash);
-                self.references.remove(&hash);
-                self.approvals.remove(&hash);
-                self.confirmed.remove(&hash);
-                self.stable.remove(&hash);
+        }
+        for (hash, approvers) in &self.approvals {
+            if self.blocks.contains_key(hash) && !self.confirmed.contains(hash) {
+                frontier.extend(approvers.iter().copied());
+            }
+        }
+        let mut excess = self.blocks.len() - self.max_blocks;
+        let mut retained = VecDeque::with_capacity(self.order.len());
+        let mut evicted = HashSet::with_capacity(excess);
+
+        while let Some(hash) = self.order.pop_front() {
+            if excess == 0 {
+                retained.push_back(hash);
+                continue;
+            }
+            let kept = if self.stable.contains(&hash) { &frontier } else { &referenced };
+            if kept.contains(&hash) {
+                retained.push_back(hash);
+                continue;
             }
+
+            self.blocks.remove(&hash);
+            self.references.remove(&hash);
+            self.approvals.remove(&hash);
+            self.confirmed.remove(&hash);
+            self.stable.remove(&hash);
+            evicted.insert(hash);
+            excess -= 1;
         }
+
+        self.order = retained;
+        self.successors.retain(|_, successor| !evicted.contains(successor));
+        self.conflicts.retain(|(first, second)| !evicted.contains(first) && !evicted.contains(second));
     }
 
     /// Get block by hash
@@ -304,7 +581,7 @@ impl BlockDag {
         self.references.get(&block_hash).cloned().unwrap_or_default()
     }
 
-    /// Get block approvals
+    /// Get the blocks approving a block
     pub fn get_approvals(&self, block_hash: H256) -> Vec<H256> {
         self.approvals.get(&block_hash).cloned().unwrap_or_default()
     }
@@ -321,3 +598,496 @@ impl Default for BlockDag {
         Self::new_default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::types::Signature;
+    use crate::U256;
+
+    /// Address of the test key `[byte; 32]`
+    fn witness(byte: u8) -> Address {
+        let secp = secp256k1::Secp256k1::new();
+        let key = secp256k1::SecretKey::from_slice(&[byte; 32]).unwrap();
+        crate::common::public_key_to_address(&secp256k1::PublicKey::from_secret_key(&secp, &key))
+    }
+
+    /// Make the block created and signed by the test key `[byte; 32]`
+    fn signed(mut block: Block, byte: u8) -> Block {
+        block.from = witness(byte);
+        block.sign(&[byte; 32]).unwrap();
+        block
+    }
+
+    fn block(last_stable_block: H256, approves: Vec<H256>) -> Block {
+        let block = Block::new(
+            witness(1),
+            H256::zero(),
+            vec![],
+            vec![],
+            approves,
+            H256::zero(),
+            H256::zero(),
+            last_stable_block,
+            1_600_000_000,
+            U256::zero(),
+            Signature { v: 0, r: H256::zero(), s: H256::zero() },
+        );
+        signed(block, 1)
+    }
+
+    fn child(parents: Vec<H256>, approves: Vec<H256>) -> Block {
+        let mut block = block(H256::zero(), approves);
+        // Extend the creator's own chain from the first parent
+        block.previous = parents.first().copied().unwrap_or_default();
+        block.parents = parents;
+        signed(block, 1)
+    }
+
+    fn approval(byte: u8, approves: Vec<H256>) -> Block {
+        signed(block(H256::zero(), approves), byte)
+    }
+
+    #[test]
+    fn test_invariants_hold_on_scripted_dag_and_catch_corruption() {
+        let mut consensus = DagConsensus::new_default();
+        consensus.verify_invariants = true;
+        consensus.witnesses = vec![witness(1)];
+        consensus.witness_manager.set_stake(witness(1), 1000);
+
+        // Each block approves its parent
+        let root = child(vec![], vec![]);
+        let root_hash = root.hash();
+        consensus.process_block(root).unwrap();
+        let leaf = child(vec![root_hash], vec![root_hash]);
+        let leaf_hash = leaf.hash();
+        consensus.process_block(leaf).unwrap();
+        let pending = child(vec![leaf_hash], vec![leaf_hash]);
+        let pending_hash = pending.hash();
+        consensus.process_block(pending).unwrap();
+
+        assert!(consensus.is_stable(root_hash) && consensus.is_stable(leaf_hash));
+        assert!(consensus.check_invariants().is_ok());
+        let snapshot = consensus.snapshot();
+        assert_eq!((snapshot.block_count, snapshot.confirmed_count, snapshot.stable_count), (3, 2, 2));
+        assert_eq!(snapshot.head, Some(pending_hash));
+
+        // A stable block referencing an unstable one
+        let mut corrupted = DagConsensus::new_default();
+        corrupted.dag = consensus.dag.clone();
+        corrupted.dag.references.insert(leaf_hash, vec![pending_hash]);
+        assert!(corrupted.check_invariants().is_err());
+
+        // A confirmed block missing from the DAG
+        let mut corrupted = DagConsensus::new_default();
+        corrupted.dag = consensus.dag.clone();
+        corrupted.dag.confirmed.insert(H256::repeat_byte(0xcc));
+        assert!(corrupted.check_invariants().is_err());
+    }
+
+    #[test]
+    fn test_deep_linear_dag_becomes_stable() {
+        // Confirmed all at once, so stability must propagate down the whole chain
+        const DEPTH: u64 = 10_000;
+        let mut consensus = DagConsensus::new_default();
+        let template = block(H256::zero(), vec![]);
+        consensus.witnesses = vec![template.from];
+        consensus.witness_manager.set_stake(template.from, 1000);
+        let approver = H256::repeat_byte(0xaa);
+        consensus.dag.blocks.insert(approver, template.clone());
+        for index in 0..DEPTH {
+            let hash = H256::from_low_u64_be(index + 1);
+            consensus.dag.blocks.insert(hash, template.clone());
+            consensus.dag.approvals.insert(hash, vec![approver]);
+            if index > 0 {
+                consensus.dag.references.insert(hash, vec![H256::from_low_u64_be(index)]);
+            }
+        }
+
+        let result = consensus.check_consensus().unwrap();
+        assert_eq!(result.confirmed_blocks.len(), DEPTH as usize);
+        assert_eq!(result.stable_blocks.len(), DEPTH as usize);
+        assert_eq!(result.stable_blocks.first(), Some(&H256::from_low_u64_be(1)));
+        assert_eq!(result.stable_blocks.last(), Some(&H256::from_low_u64_be(DEPTH)));
+        assert!(consensus.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_stability_depth_capped_per_pass() {
+        const DEPTH: u64 = 10;
+        let mut consensus = DagConsensus::new_default();
+        consensus.max_stability_depth = 4;
+        let template = block(H256::zero(), vec![]);
+        consensus.witnesses = vec![template.from];
+        consensus.witness_manager.set_stake(template.from, 1000);
+        let approver = H256::repeat_byte(0xaa);
+        consensus.dag.blocks.insert(approver, template.clone());
+        for index in 0..DEPTH {
+            let hash = H256::from_low_u64_be(index + 1);
+            consensus.dag.blocks.insert(hash, template.clone());
+            consensus.dag.approvals.insert(hash, vec![approver]);
+            if index > 0 {
+                consensus.dag.references.insert(hash, vec![H256::from_low_u64_be(index)]);
+            }
+        }
+
+        // Each pass resumes from the frontier the previous one left
+        let result = consensus.check_consensus().unwrap();
+        assert_eq!(result.confirmed_blocks.len(), DEPTH as usize);
+        assert_eq!(result.stable_blocks, (1..=4).map(H256::from_low_u64_be).collect::<Vec<_>>());
+        assert!(consensus.check_invariants().is_ok());
+        assert_eq!(consensus.check_consensus().unwrap().stable_blocks.len(), 4);
+        assert_eq!(consensus.check_consensus().unwrap().stable_blocks.len(), 2);
+        assert!(consensus.is_stable(H256::from_low_u64_be(DEPTH)));
+    }
+
+    #[test]
+    fn test_confirmation_weighs_approvals_by_stake() {
+        let mut consensus = DagConsensus::new_default();
+        consensus.witnesses = (1..=6).map(witness).collect();
+        for byte in 1..=2 {
+            consensus.witness_manager.set_stake(witness(byte), 5000);
+        }
+        for byte in 3..=6 {
+            consensus.witness_manager.set_stake(witness(byte), 1000);
+        }
+
+        let high = block(H256::repeat_byte(0x01), vec![]);
+        let high_hash = high.hash();
+        let low = block(H256::repeat_byte(0x02), vec![]);
+        let low_hash = low.hash();
+        consensus.process_block(high).unwrap();
+        consensus.process_block(low).unwrap();
+
+        // 10000 of 14000 stake exceeds two thirds
+        for byte in 1..=2 {
+            consensus.process_block(approval(byte, vec![high_hash])).unwrap();
+        }
+        assert!(consensus.is_confirmed(high_hash));
+
+        // Four witnesses, and a second approval from the same one, only reach 9000
+        for byte in 3..=6 {
+            consensus.process_block(approval(byte, vec![low_hash])).unwrap();
+        }
+        consensus.process_block(approval(1, vec![low_hash])).unwrap();
+        consensus.process_block(approval(1, vec![low_hash, high_hash])).unwrap();
+        assert_eq!(consensus.dag.get_approvals(low_hash).len(), 6);
+        assert!(!consensus.is_confirmed(low_hash));
+    }
+
+    #[test]
+    fn test_only_witness_approvals_count_toward_confirmation() {
+        let mut consensus = DagConsensus::new_default();
+        consensus.witnesses = vec![witness(1), witness(2), witness(3)];
+        for byte in 1..=3 {
+            consensus.witness_manager.set_stake(witness(byte), 1000);
+        }
+        // A non-witness whose stake would otherwise carry the vote
+        consensus.witness_manager.set_stake(witness(9), 1000);
+
+        let target = block(H256::repeat_byte(0x01), vec![]);
+        let target_hash = target.hash();
+        consensus.process_block(target).unwrap();
+
+        consensus.process_block(approval(9, vec![target_hash])).unwrap();
+        consensus.process_block(approval(1, vec![target_hash])).unwrap();
+        consensus.process_block(approval(2, vec![target_hash])).unwrap();
+        assert_eq!(consensus.dag.get_approvals(target_hash).len(), 2);
+        assert_eq!(consensus.snapshot().rejected_approvals, 1);
+        assert!(!consensus.is_confirmed(target_hash));
+
+        consensus.process_block(approval(3, vec![target_hash])).unwrap();
+        assert!(consensus.is_confirmed(target_hash));
+    }
+
+    #[test]
+    fn test_non_witness_stake_excluded_from_total() {
+        let mut consensus = DagConsensus::new_default();
+        consensus.witnesses = vec![witness(1), witness(2), witness(3)];
+        for byte in 1..=3 {
+            consensus.witness_manager.set_stake(witness(byte), 1000);
+        }
+        // Stake outside the witness set would otherwise make two thirds unreachable
+        consensus.witness_manager.set_stake(witness(9), 100_000);
+
+        let target = block(H256::repeat_byte(0x01), vec![]);
+        let target_hash = target.hash();
+        consensus.process_block(target).unwrap();
+        for byte in 1..=2 {
+            consensus.process_block(approval(byte, vec![target_hash])).unwrap();
+        }
+        assert!(!consensus.is_confirmed(target_hash));
+
+        consensus.process_block(approval(3, vec![target_hash])).unwrap();
+        assert!(consensus.is_confirmed(target_hash));
+    }
+
+    #[test]
+    fn test_self_referencing_and_orphan_blocks_rejected() {
+        let mut dag = BlockDag::new_default();
+        let root = child(vec![], vec![]);
+        let root_hash = root.hash();
+        dag.add_block(root_hash, root).unwrap();
+
+        let mut looped = child(vec![root_hash], vec![]);
+        let looped_hash = looped.hash();
+        looped.parents.push(looped_hash);
+        assert!(matches!(dag.add_block(looped_hash, looped), Err(OlympusError::Consensus(_))));
+
+        let orphan = child(vec![H256::repeat_byte(0xee)], vec![]);
+        assert!(matches!(dag.add_block(orphan.hash(), orphan), Err(OlympusError::Consensus(_))));
+        assert_eq!(dag.blocks.len(), 1);
+    }
+
+    #[test]
+    fn test_witness_selection_is_deterministic() {
+        let witness = |byte: u8| Address::repeat_byte(byte);
+        let mut consensus = DagConsensus::new(3, 3, 100);
+        consensus.elected.extend((1..=4).map(witness));
+        let mut stable_blocks = Vec::new();
+        for byte in [4, 2, 3, 1] {
+            let mut block = block(H256::zero(), vec![]);
+            block.from = witness(byte);
+            stable_blocks.push(block.hash());
+            consensus.dag.blocks.insert(block.hash(), block);
+        }
+
+        // Four creators tied on one block each; the lowest addresses win
+        let selected = consensus.select_next_witnesses(&stable_blocks).unwrap();
+        assert_eq!(selected, vec![witness(1), witness(2), witness(3)]);
+        assert_eq!(consensus.select_next_witnesses(&stable_blocks).unwrap(), selected);
+
+        // Short of the minimum, current witnesses are kept by stake instead of padding with zero
+        consensus.witnesses = vec![witness(7), witness(4), witness(8)];
+        consensus.witness_manager.set_stake(witness(8), 500);
+        let selected = consensus.select_next_witnesses(&stable_blocks[..1]).unwrap();
+        assert_eq!(selected, vec![witness(4), witness(8), witness(7)]);
+        assert_eq!(consensus.select_next_witnesses(&stable_blocks[..1]).unwrap(), selected);
+    }
+
+    #[test]
+    fn test_only_elected_creators_become_witnesses() {
+        let mut consensus = DagConsensus::new(1, 3, 100);
+        consensus.witnesses = vec![witness(9)];
+        let mut stable_blocks = Vec::new();
+        for byte in 1..=2 {
+            let block = approval(byte, vec![]);
+            stable_blocks.push(block.hash());
+            consensus.dag.blocks.insert(block.hash(), block);
+            consensus.witness_manager.set_stake(witness(byte), crate::core::approve::ELECTION_FULL_STAKE);
+        }
+
+        let proof = |byte: u8, epoch_seed: H256| {
+            let proof = Approve::create_election_proof(&[byte; 32], epoch_seed).unwrap();
+            Approve::new(witness(byte), proof, Signature { v: 27, r: H256::repeat_byte(1), s: H256::repeat_byte(2) })
+        };
+        assert!(consensus.process_approve(&proof(1, consensus.epoch_seed)).is_ok());
+        assert!(consensus.process_approve(&proof(2, H256::repeat_byte(0x22))).is_err());
+        assert_eq!(consensus.select_next_witnesses(&stable_blocks).unwrap(), vec![witness(1)]);
+
+        // Proofs are tied to the epoch they were made for
+        let seed = consensus.epoch_seed;
+        consensus.advance_epoch(vec![witness(1)]).unwrap();
+        assert_ne!(consensus.epoch_seed, seed);
+        assert!(consensus.elected.is_empty());
+        assert!(consensus.process_approve(&proof(1, seed)).is_err());
+    }
+
+    #[test]
+    fn test_clear_old_blocks_keeps_stable_frontier_and_referenced_blocks() {
+        let witness = witness(1);
+        let mut consensus = DagConsensus::new_default();
+        consensus.dag = BlockDag::new(3);
+        consensus.witnesses = vec![witness];
+        consensus.witness_manager.set_stake(witness, 1000);
+
+        let root = child(vec![], vec![]);
+        let root_hash = root.hash();
+        consensus.process_block(root).unwrap();
+        let middle = child(vec![root_hash], vec![root_hash]);
+        let middle_hash = middle.hash();
+        consensus.process_block(middle).unwrap();
+        let leaf = child(vec![middle_hash], vec![]);
+        let leaf_hash = leaf.hash();
+        consensus.process_block(leaf).unwrap();
+        let unrelated: Vec<Block> = (1..=3).map(|byte| block(H256::repeat_byte(byte), vec![])).collect();
+        let unrelated_hashes: Vec<H256> = unrelated.iter().map(Block::hash).collect();
+        for block in unrelated {
+            consensus.process_block(block).unwrap();
+        }
+        assert!(consensus.is_stable(root_hash) && !consensus.is_stable(middle_hash));
+
+        consensus.dag.clear_old_blocks();
+
+        // The root is on the stable frontier and the middle block is referenced; the oldest others go
+        let mut kept: Vec<H256> = consensus.dag.blocks.keys().copied().collect();
+        kept.sort();
+        let mut expected = vec![root_hash, middle_hash, unrelated_hashes[2]];
+        expected.sort();
+        assert_eq!(kept, expected);
+        assert!(!consensus.dag.blocks.contains_key(&leaf_hash));
+        assert_eq!(consensus.dag.order, VecDeque::from(vec![root_hash, middle_hash, unrelated_hashes[2]]));
+        assert!(consensus.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_clear_old_blocks_bounds_long_stable_chain() {
+        let witness = witness(1);
+        let mut consensus = DagConsensus::new_default();
+        consensus.verify_invariants = true;
+        consensus.dag = BlockDag::new(10);
+        consensus.witnesses = vec![witness];
+        consensus.witness_manager.set_stake(witness, 1000);
+
+        // Each block approves its parent, so the chain stabilizes one block behind the tip
+        let mut hashes = vec![];
+        for _ in 0..200 {
+            let parents = hashes.last().map(|hash| vec![*hash]).unwrap_or_default();
+            let block = child(parents.clone(), parents);
+            hashes.push(block.hash());
+            consensus.process_block(block).unwrap();
+            consensus.dag.clear_old_blocks();
+            assert!(consensus.dag.blocks.len() <= 10);
+        }
+
+        assert!(!consensus.dag.blocks.contains_key(&hashes[0]));
+        assert!(consensus.dag.blocks.contains_key(&hashes[199]));
+        assert!(consensus.is_stable(hashes[198]));
+        assert_eq!(consensus.dag.order.len(), consensus.dag.blocks.len());
+        assert!(consensus.check_invariants().is_ok());
+    }
+
+    #[test]
+    fn test_epoch_transition_installs_selected_witnesses() {
+        let mut consensus = DagConsensus::new(1, 21, 1);
+        consensus.witnesses = vec![witness(1)];
+        consensus.witness_manager.set_stake(witness(1), 1000);
+        consensus.witness_manager.set_stake(witness(2), crate::core::approve::ELECTION_FULL_STAKE);
+        let proof = Approve::create_election_proof(&[2; 32], consensus.epoch_seed).unwrap();
+        let signature = Signature { v: 27, r: H256::repeat_byte(1), s: H256::repeat_byte(2) };
+        consensus.process_approve(&Approve::new(witness(2), proof, signature)).unwrap();
+
+        let proposed = approval(2, vec![]);
+        let proposed_hash = proposed.hash();
+        consensus.process_block(proposed).unwrap();
+        let result = consensus.process_block(approval(1, vec![proposed_hash])).unwrap();
+
+        // The elected creator of the first stable block becomes the next witness set
+        assert_eq!(result.next_witnesses, vec![witness(2)]);
+        assert_eq!(consensus.current_epoch, 1);
+        assert_eq!(consensus.witnesses, vec![witness(2)]);
+        assert_eq!(consensus.witness_manager.witnesses, vec![witness(2)]);
+        assert_eq!(consensus.witness_history.get(Some(0)).unwrap()[0].address, witness(1));
+
+        assert!(consensus.advance_epoch(vec![]).is_err());
+        assert_eq!(consensus.current_epoch, 1);
+        assert_eq!(consensus.witnesses, vec![witness(2)]);
+    }
+
+    #[test]
+    fn test_blocks_sharing_previous_reported_as_conflict() {
+        let mut consensus = DagConsensus::new_default();
+        let spend = |byte: u8, signer: u8| {
+            let mut block = block(H256::repeat_byte(byte), vec![]);
+            block.previous = H256::repeat_byte(0x77);
+            signed(block, signer)
+        };
+
+        let first = spend(1, 1);
+        let first_hash = first.hash();
+        consensus.process_block(first).unwrap();
+        consensus.process_block(spend(2, 2)).unwrap();
+        assert!(consensus.get_conflicts().is_empty());
+
+        let second = spend(3, 1);
+        let second_hash = second.hash();
+        consensus.process_block(second).unwrap();
+        assert_eq!(consensus.get_conflicts(), vec![(first_hash, second_hash)]);
+    }
+
+    #[test]
+    fn test_losing_side_of_conflict_never_confirmed() {
+        let mut consensus = DagConsensus::new_default();
+        consensus.witnesses = vec![witness(1), witness(2), witness(3)];
+        for byte in 1..=3 {
+            consensus.witness_manager.set_stake(witness(byte), 1000);
+        }
+        let spend = |byte: u8| {
+            let mut block = block(H256::repeat_byte(byte), vec![]);
+            block.previous = H256::repeat_byte(0x77);
+            signed(block, 4)
+        };
+
+        let first = spend(1);
+        let first_hash = first.hash();
+        let second = spend(2);
+        let second_hash = second.hash();
+        consensus.process_block(first).unwrap();
+        consensus.process_block(second).unwrap();
+        assert_eq!(consensus.get_conflicts(), vec![(first_hash, second_hash)]);
+
+        for byte in 1..=3 {
+            consensus.process_block(approval(byte, vec![first_hash])).unwrap();
+        }
+        assert!(consensus.is_confirmed(first_hash));
+
+        // Full witness approval cannot confirm the double spend
+        for byte in 1..=3 {
+            let mut vote = approval(byte, vec![second_hash]);
+            vote.last_stable_block = H256::repeat_byte(0x99);
+            consensus.process_block(signed(vote, byte)).unwrap();
+        }
+        assert_eq!(consensus.dag.get_approvals(second_hash).len(), 3);
+        assert!(!consensus.is_confirmed(second_hash));
+
+        // When both sides qualify in the same pass, only the lower hash is confirmed
+        consensus.dag.confirmed.clear();
+        consensus.dag.stable.clear();
+        let confirmed = consensus.check_consensus().unwrap().confirmed_blocks;
+        let winner = first_hash.min(second_hash);
+        assert!(confirmed.contains(&winner));
+        assert!(!confirmed.contains(&first_hash.max(second_hash)));
+    }
+
+    #[test]
+    fn test_block_with_spoofed_creator_rejected() {
+        let mut consensus = DagConsensus::new_default();
+        consensus.witnesses = vec![witness(1), witness(2)];
+        for byte in 1..=2 {
+            consensus.witness_manager.set_stake(witness(byte), 1000);
+        }
+        let target = block(H256::repeat_byte(0x01), vec![]);
+        let target_hash = target.hash();
+        consensus.process_block(target).unwrap();
+
+        // Signed by a non-witness but claiming to come from witness 2
+        let mut spoofed = approval(9, vec![target_hash]);
+        spoofed.from = witness(2);
+        assert!(matches!(consensus.process_block(spoofed.clone()), Err(OlympusError::InvalidBlock(_))));
+        assert!(!consensus.dag.blocks.contains_key(&spoofed.hash()));
+        assert!(consensus.dag.get_approvals(target_hash).is_empty());
+
+        let mut unsigned = approval(2, vec![target_hash]);
+        unsigned.signature = Signature { v: 0, r: H256::zero(), s: H256::zero() };
+        assert!(consensus.process_block(unsigned).is_err());
+        assert!(!consensus.is_confirmed(target_hash));
+    }
+
+    #[test]
+    fn test_block_randomness_differs_per_block() {
+        let a = block(H256::repeat_byte(0x01), vec![H256::repeat_byte(0xaa)]);
+        let b = block(H256::repeat_byte(0x02), vec![H256::repeat_byte(0xaa)]);
+        let c = block(H256::repeat_byte(0x01), vec![H256::repeat_byte(0xbb)]);
+
+        assert_eq!(DagConsensus::block_randomness(&a), DagConsensus::block_randomness(&a.clone()));
+        assert_ne!(DagConsensus::block_randomness(&a), DagConsensus::block_randomness(&b));
+        assert_ne!(DagConsensus::block_randomness(&a), DagConsensus::block_randomness(&c));
+        assert!(!DagConsensus::block_randomness(&a).is_zero());
+
+        // Another signature over the same contents leaves the randomness unchanged
+        let mut resigned = a.clone();
+        resigned.signature = Signature { v: 1, r: H256::repeat_byte(0x11), s: H256::repeat_byte(0x22) };
+        assert_ne!(resigned.hash(), a.hash());
+        assert_eq!(DagConsensus::block_randomness(&resigned), DagConsensus::block_randomness(&a));
+    }
+}
@@ -1,7 +1,7 @@
 //! Witness management
 
 use crate::{Address, Result, OlympusError};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use serde::{Deserialize, Serialize};
 
 /// Witness manager
@@ -18,6 +18,36 @@ pub struct WitnessManager {
     pub performance_scores: HashMap<Address, f64>,
 }
 
+/// Witness and its stake in an epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessStake {
+    /// Witness address
+    pub address: Address,
+    /// Stake held by the witness
+    pub stake: u64,
+}
+
+/// Witness sets by epoch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WitnessHistory {
+    /// Current epoch
+    pub current_epoch: u64,
+    /// Witness set of each recorded epoch
+    pub sets: BTreeMap<u64, Vec<WitnessStake>>,
+}
+
+impl WitnessHistory {
+    /// Record the witness set of an epoch
+    pub fn record(&mut self, epoch: u64, witnesses: Vec<WitnessStake>) {
+        self.sets.insert(epoch, witnesses);
+    }
+
+    /// Get the witness set of an epoch, or of the current epoch if none is given
+    pub fn get(&self, epoch: Option<u64>) -> Option<&[WitnessStake]> {
+        self.sets.get(&epoch.unwrap_or(self.current_epoch)).map(Vec::as_slice)
+    }
+}
+
 /// Witness selection criteria
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WitnessCriteria {
@@ -79,6 +109,13 @@ impl WitnessManager {
         self.stakes.get(&witness).cloned().unwrap_or(0)
     }
 
+    /// Pair witnesses with their current stakes
+    pub fn stakes_of(&self, witnesses: &[Address]) -> Vec<WitnessStake> {
+        witnesses.iter()
+            .map(|&address| WitnessStake { address, stake: self.get_stake(address) })
+            .collect()
+    }
+
     /// Update witness performance score
     pub fn update_performance(&mut self, witness: Address, score: f64) {
         self.performance_scores.insert(witness, score);
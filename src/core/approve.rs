@@ -1,10 +1,17 @@
 //! Approve (election proof) data structure and operations
 
 use crate::core::types::*;
-use crate::{Address, H256, Result, OlympusError};
+use crate::core::transaction::SECP256K1_HALF_ORDER;
+use crate::{Address, H256, U256, Result, OlympusError};
 use rlp::{Rlp, RlpStream, Encodable, Decodable};
 use serde::{Deserialize, Serialize};
 
+/// Length of an election proof in bytes
+pub const ELECTION_PROOF_LEN: usize = 81;
+
+/// Stake at or above which an approver always passes the election threshold
+pub const ELECTION_FULL_STAKE: u64 = 1_000_000;
+
 /// Approve (election proof) structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Approve {
@@ -67,6 +74,101 @@ impl Approve {
         Ok(())
     }
 
+    /// Validate approve structure and its election proof for an epoch
+    pub fn validate_election(&self, epoch_seed: H256, stake: u64) -> Result<()> {
+        self.validate()?;
+
+        if !self.verify_election_proof(epoch_seed, stake) {
+            return Err(OlympusError::InvalidTransaction("Invalid election proof".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Create an election proof for an epoch
+    ///
+    /// The proof is a recoverable signature (r || s || recovery id) over the
+    /// election message, the keccak256 of the epoch seed and the signer's
+    /// address, followed by the first 16 bytes of that message, which serve as
+    /// the election ticket. The signature uses the RFC 6979 deterministic
+    /// nonce; the ticket does not depend on it, so re-signing cannot change it.
+    pub fn create_election_proof(secret: &[u8], epoch_seed: H256) -> Result<Vec<u8>> {
+        use secp256k1::{Secp256k1, SecretKey, PublicKey, Message};
+
+        let secp = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(secret)
+            .map_err(|_| OlympusError::InvalidTransaction("Invalid private key".to_string()))?;
+        let from = crate::common::public_key_to_address(&PublicKey::from_secret_key(&secp, &secret_key));
+
+        let election_message = Self::election_message(epoch_seed, from);
+        let message = Message::from_digest_slice(election_message.as_bytes())
+            .map_err(|_| OlympusError::InvalidTransaction("Invalid message hash".to_string()))?;
+        let (recovery_id, signature_bytes) = secp.sign_ecdsa_recoverable(&message, &secret_key).serialize_compact();
+
+        let mut proof = Vec::with_capacity(ELECTION_PROOF_LEN);
+        proof.extend_from_slice(&signature_bytes);
+        proof.push(recovery_id.to_i32() as u8);
+        proof.extend_from_slice(&election_message[0..16]);
+
+        Ok(proof)
+    }
+
+    /// Verify the election proof against the epoch seed and the sender's stake
+    ///
+    /// The proof must be signed by `from` for this exact epoch seed with a low
+    /// `s`, and its ticket must fall under a threshold proportional to the
+    /// stake.
+    pub fn verify_election_proof(&self, epoch_seed: H256, stake: u64) -> bool {
+        use secp256k1::{Secp256k1, Message};
+        use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+
+        if self.proof.len() != ELECTION_PROOF_LEN || stake == 0 {
+            return false;
+        }
+
+        let recovery_id = match RecoveryId::from_i32(self.proof[64] as i32) {
+            Ok(id) => id,
+            Err(_) => return false,
+        };
+        let signature = match RecoverableSignature::from_compact(&self.proof[0..64], recovery_id) {
+            Ok(sig) => sig,
+            Err(_) => return false,
+        };
+        if U256::from_big_endian(&self.proof[32..64]) > SECP256K1_HALF_ORDER {
+            return false;
+        }
+        let election_message = Self::election_message(epoch_seed, self.from);
+        let message = match Message::from_digest_slice(election_message.as_bytes()) {
+            Ok(msg) => msg,
+            Err(_) => return false,
+        };
+
+        let secp = Secp256k1::verification_only();
+        match secp.recover_ecdsa(&message, &signature) {
+            Ok(public_key) if crate::common::public_key_to_address(&public_key) == self.from => {}
+            _ => return false,
+        }
+
+        if election_message[0..16] != self.proof[65..ELECTION_PROOF_LEN] {
+            return false;
+        }
+
+        let mut ticket_bytes = [0u8; 16];
+        ticket_bytes.copy_from_slice(&election_message[0..16]);
+        let stake = stake.min(ELECTION_FULL_STAKE) as u128;
+        let threshold = (u128::MAX / ELECTION_FULL_STAKE as u128) * stake;
+
+        u128::from_be_bytes(ticket_bytes) <= threshold
+    }
+
+    /// Message signed by an election proof
+    fn election_message(epoch_seed: H256, from: Address) -> H256 {
+        let mut data = Vec::with_capacity(52);
+        data.extend_from_slice(epoch_seed.as_bytes());
+        data.extend_from_slice(from.as_bytes());
+        crate::common::keccak256(&data)
+    }
+
     /// Get sender address
     pub fn from(&self) -> Address {
         self.from
@@ -151,3 +253,59 @@ impl ApproveReceipt {
 
 /// Collection of approves
 pub type Approves = Vec<Approve>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approve_for(secret: &[u8], epoch_seed: H256) -> Approve {
+        let secp = secp256k1::Secp256k1::new();
+        let secret_key = secp256k1::SecretKey::from_slice(secret).unwrap();
+        let from = crate::common::public_key_to_address(&secp256k1::PublicKey::from_secret_key(&secp, &secret_key));
+        let proof = Approve::create_election_proof(secret, epoch_seed).unwrap();
+
+        Approve::new(from, proof, Signature { v: 27, r: H256::repeat_byte(1), s: H256::repeat_byte(2) })
+    }
+
+    #[test]
+    fn test_valid_election_proof() {
+        let epoch_seed = H256::repeat_byte(0x11);
+        let approve = approve_for(&[0x07; 32], epoch_seed);
+
+        assert_eq!(approve.proof().len(), ELECTION_PROOF_LEN);
+        assert!(approve.verify_election_proof(epoch_seed, ELECTION_FULL_STAKE));
+        assert!(approve.validate_election(epoch_seed, ELECTION_FULL_STAKE).is_ok());
+        assert!(!approve.verify_election_proof(epoch_seed, 0));
+    }
+
+    #[test]
+    fn test_election_proof_wrong_epoch() {
+        let approve = approve_for(&[0x07; 32], H256::repeat_byte(0x11));
+        let other_epoch = H256::repeat_byte(0x22);
+
+        assert!(!approve.verify_election_proof(other_epoch, ELECTION_FULL_STAKE));
+        assert!(approve.validate_election(other_epoch, ELECTION_FULL_STAKE).is_err());
+    }
+
+    #[test]
+    fn test_election_ticket_cannot_be_ground_by_resigning() {
+        let epoch_seed = H256::repeat_byte(0x11);
+        let approve = approve_for(&[0x07; 32], epoch_seed);
+
+        // RFC 6979 signing is deterministic
+        assert_eq!(Approve::create_election_proof(&[0x07; 32], epoch_seed).unwrap(), approve.proof);
+
+        // A different ticket is rejected even with a valid signature
+        let mut ground = approve.clone();
+        ground.proof[80] ^= 1;
+        assert!(!ground.verify_election_proof(epoch_seed, ELECTION_FULL_STAKE));
+
+        // So is the high-s twin of the signature
+        let mut malleated = approve.clone();
+        let order: U256 = SECP256K1_HALF_ORDER * 2 + 1;
+        let s = order - U256::from_big_endian(&approve.proof[32..64]);
+        s.to_big_endian(&mut malleated.proof[32..64]);
+        malleated.proof[64] ^= 1;
+        assert!(!malleated.verify_election_proof(epoch_seed, ELECTION_FULL_STAKE));
+    }
+}
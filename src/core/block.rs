@@ -1,6 +1,8 @@
 //! Block data structure and operations
 
+use crate::common::clock::{Clock, SystemClock};
 use crate::core::types::*;
+use crate::core::transaction::SECP256K1_HALF_ORDER;
 use crate::{Address, H256, U256, Result, OlympusError};
 use rlp::{Rlp, RlpStream, Encodable, Decodable};
 use serde::{Deserialize, Serialize};
@@ -34,6 +36,7 @@ pub struct Block {
 
 impl Block {
     /// Create a new block
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         from: Address,
         previous: BlockHash,
@@ -68,6 +71,36 @@ impl Block {
         crate::common::keccak256(&rlp)
     }
 
+    /// Get the hash signed by the creator
+    ///
+    /// This is the hash of the block's RLP without the signature fields.
+    pub fn signing_hash(&self) -> H256 {
+        let mut stream = RlpStream::new_list(10);
+        self.rlp_append_unsigned(&mut stream);
+        crate::common::keccak256(&stream.out())
+    }
+
+    /// Sign the block with a secp256k1 secret key
+    ///
+    /// The signature's `v` holds the bare recovery id.
+    pub fn sign(&mut self, secret: &[u8]) -> Result<()> {
+        use secp256k1::{Secp256k1, SecretKey, Message};
+
+        let secp = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(secret)
+            .map_err(|_| OlympusError::InvalidBlock("Invalid private key".to_string()))?;
+        let message = Message::from_digest_slice(self.signing_hash().as_bytes())
+            .map_err(|_| OlympusError::InvalidBlock("Invalid message hash".to_string()))?;
+        let (recovery_id, signature_bytes) = secp.sign_ecdsa_recoverable(&message, &secret_key).serialize_compact();
+
+        self.signature = Signature {
+            v: recovery_id.to_i32() as u64,
+            r: H256::from_slice(&signature_bytes[0..32]),
+            s: H256::from_slice(&signature_bytes[32..64]),
+        };
+        Ok(())
+    }
+
     /// Get RLP encoded bytes
     pub fn rlp_bytes(&self) -> Vec<u8> {
         let mut stream = RlpStream::new();
@@ -77,33 +110,23 @@ impl Block {
 
     /// Calculate block root (Merkle root of transactions)
     pub fn root(&self) -> H256 {
-        if self.links.is_empty() {
-            return H256::zero();
-        }
-        
-        // For now, return a simple hash of all links
-        // In a full implementation, this would be a proper Merkle tree
-        let mut data = Vec::new();
-        for link in &self.links {
-            data.extend_from_slice(link.as_bytes());
-        }
-        crate::common::keccak256(&data)
+        crate::common::merkle_root(&self.links)
     }
 
     /// Validate block structure
     pub fn validate(&self) -> Result<()> {
+        self.validate_with_clock(&SystemClock)
+    }
+
+    /// Validate block against the time read from `clock`
+    pub fn validate_with_clock(&self, clock: &dyn Clock) -> Result<()> {
         // Check that from address is not zero
         if self.from == Address::zero() {
             return Err(OlympusError::InvalidBlock("From address cannot be zero".to_string()));
         }
 
         // Check timestamp is reasonable (not too far in future/past)
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        
-        if self.exec_timestamp > now + 300 { // 5 minutes tolerance
+        if self.exec_timestamp > clock.now() + 300 { // 5 minutes tolerance
             return Err(OlympusError::InvalidBlock("Block timestamp too far in future".to_string()));
         }
 
@@ -114,11 +137,28 @@ impl Block {
     }
 
     /// Validate block signature
-    fn validate_signature(&self) -> Result<()> {
-        // This is a simplified validation
-        // In a full implementation, you would verify the ECDSA signature
-        if self.signature.r == H256::zero() && self.signature.s == H256::zero() {
-            return Err(OlympusError::InvalidBlock("Invalid signature".to_string()));
+    ///
+    /// The signer recovered from the signature must be the block creator.
+    pub fn validate_signature(&self) -> Result<()> {
+        use secp256k1::{Secp256k1, Message};
+        use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+
+        let invalid = || OlympusError::InvalidBlock("Invalid signature".to_string());
+        if U256::from_big_endian(self.signature.s.as_bytes()) > SECP256K1_HALF_ORDER {
+            return Err(OlympusError::InvalidBlock("Signature s is in the upper half of the curve order".to_string()));
+        }
+        let recovery_id = i32::try_from(self.signature.v).ok()
+            .and_then(|v| RecoveryId::from_i32(v).ok())
+            .ok_or_else(invalid)?;
+        let mut signature_bytes = [0u8; 64];
+        signature_bytes[0..32].copy_from_slice(self.signature.r.as_bytes());
+        signature_bytes[32..64].copy_from_slice(self.signature.s.as_bytes());
+        let signature = RecoverableSignature::from_compact(&signature_bytes, recovery_id).map_err(|_| invalid())?;
+        let message = Message::from_digest_slice(self.signing_hash().as_bytes()).map_err(|_| invalid())?;
+
+        let public_key = Secp256k1::verification_only().recover_ecdsa(&message, &signature).map_err(|_| invalid())?;
+        if crate::common::public_key_to_address(&public_key) != self.from {
+            return Err(OlympusError::InvalidBlock("Block not signed by its creator".to_string()));
         }
         Ok(())
     }
@@ -152,9 +192,9 @@ impl Block {
     }
 }
 
-impl Encodable for Block {
-    fn rlp_append(&self, s: &mut RlpStream) {
-        s.begin_list(12);
+impl Block {
+    /// Append every field except the signature
+    fn rlp_append_unsigned(&self, s: &mut RlpStream) {
         s.append(&self.from);
         s.append(&self.previous);
         s.append_list(&self.parents);
@@ -165,6 +205,13 @@ impl Encodable for Block {
         s.append(&self.last_stable_block);
         s.append(&self.exec_timestamp);
         s.append(&self.gas_used);
+    }
+}
+
+impl Encodable for Block {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(13);
+        self.rlp_append_unsigned(s);
         s.append(&self.signature.v);
         s.append(&self.signature.r);
         s.append(&self.signature.s);
@@ -173,7 +220,7 @@ impl Encodable for Block {
 
 impl Decodable for Block {
     fn decode(rlp: &Rlp) -> std::result::Result<Self, rlp::DecoderError> {
-        if rlp.item_count()? != 12 {
+        if rlp.item_count()? != 13 {
             return Err(rlp::DecoderError::RlpIncorrectListLen);
         }
 
@@ -244,17 +291,8 @@ impl LocalizedBlock {
             }
         }
 
-        // Calculate transactions root
-        let transactions_root = if transactions.is_empty() {
-            H256::zero()
-        } else {
-            // Simplified - in full implementation would use proper Merkle tree
-            let mut data = Vec::new();
-            for tx in &transactions {
-                data.extend_from_slice(&tx.hash().as_bytes());
-            }
-            crate::common::keccak256(&data)
-        };
+        let transaction_hashes: Vec<H256> = transactions.iter().map(|tx| tx.hash()).collect();
+        let transactions_root = crate::common::merkle_root(&transaction_hashes);
 
         Self {
             block,
@@ -287,3 +325,121 @@ impl Block {
         self.gas_used
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::clock::MockClock;
+
+    const SECRET: [u8; 32] = [0x42; 32];
+
+    fn signed_block() -> Block {
+        let secp = secp256k1::Secp256k1::new();
+        let key = secp256k1::SecretKey::from_slice(&SECRET).unwrap();
+        let from = crate::common::public_key_to_address(&secp256k1::PublicKey::from_secret_key(&secp, &key));
+        let mut block = Block::new(
+            from,
+            H256::zero(),
+            vec![],
+            vec![H256::repeat_byte(0x0a)],
+            vec![],
+            H256::zero(),
+            H256::zero(),
+            H256::zero(),
+            1_600_000_000,
+            U256::zero(),
+            Signature { v: 0, r: H256::zero(), s: H256::zero() },
+        );
+        block.sign(&SECRET).unwrap();
+        block
+    }
+
+    #[test]
+    fn test_future_timestamp_checked_against_clock() {
+        let block = signed_block();
+
+        // Within the 5 minute tolerance
+        let clock = MockClock::new(1_600_000_000 - 300);
+        assert!(block.validate_with_clock(&clock).is_ok());
+
+        clock.set(1_600_000_000 - 301);
+        assert!(block.validate_with_clock(&clock).is_err());
+
+        clock.advance(1);
+        assert!(block.validate_with_clock(&clock).is_ok());
+    }
+
+    #[test]
+    fn test_signing_hash_excludes_signature() {
+        let mut block = signed_block();
+        let signing_hash = block.signing_hash();
+        let hash = block.hash();
+        assert_ne!(hash, signing_hash);
+
+        block.signature = Signature { v: 0, r: H256::zero(), s: H256::zero() };
+        assert_eq!(block.signing_hash(), signing_hash);
+        assert_ne!(block.hash(), hash);
+
+        block.sign(&SECRET).unwrap();
+        assert_eq!(block.signing_hash(), signing_hash);
+        assert_eq!(block.hash(), hash);
+    }
+
+    #[test]
+    fn test_rlp_round_trip() {
+        let mut block = signed_block();
+        block.parents = vec![H256::repeat_byte(0x01), H256::repeat_byte(0x02)];
+        block.approves = vec![H256::repeat_byte(0x03)];
+        block.gas_used = U256::from(21_000);
+        block.sign(&SECRET).unwrap();
+
+        let decoded: Block = rlp::decode(&block.rlp_bytes()).unwrap();
+        assert_eq!(decoded.from, block.from);
+        assert_eq!(decoded.parents, block.parents);
+        assert_eq!(decoded.links, block.links);
+        assert_eq!(decoded.approves, block.approves);
+        assert_eq!(decoded.exec_timestamp, block.exec_timestamp);
+        assert_eq!(decoded.gas_used, block.gas_used);
+        assert_eq!((decoded.signature.v, decoded.signature.r, decoded.signature.s), (block.signature.v, block.signature.r, block.signature.s));
+        assert_eq!(decoded.hash(), block.hash());
+    }
+
+    #[test]
+    fn test_signature_must_come_from_creator() {
+        let clock = MockClock::new(1_600_000_000);
+        let block = signed_block();
+        assert!(block.signature.v <= 1);
+        assert!(block.validate_with_clock(&clock).is_ok());
+
+        let mut tampered = block.clone();
+        tampered.links.push(H256::repeat_byte(0x0b));
+        assert!(matches!(tampered.validate_with_clock(&clock), Err(OlympusError::InvalidBlock(_))));
+
+        let mut impersonated = block.clone();
+        impersonated.from = Address::repeat_byte(0x01);
+        assert!(matches!(impersonated.validate_with_clock(&clock), Err(OlympusError::InvalidBlock(_))));
+
+        let mut unsigned = block;
+        unsigned.signature = Signature { v: 0, r: H256::zero(), s: H256::zero() };
+        assert!(unsigned.validate_with_clock(&clock).is_err());
+    }
+
+    #[test]
+    fn test_malleated_signature_rejected() {
+        let clock = MockClock::new(1_600_000_000);
+        let block = signed_block();
+
+        // (r, n - s) with the flipped parity recovers the same signer
+        let order: U256 = SECP256K1_HALF_ORDER * 2 + 1;
+        let mut high_s = [0u8; 32];
+        (order - U256::from_big_endian(block.signature.s.as_bytes())).to_big_endian(&mut high_s);
+        let mut malleated = block.clone();
+        malleated.signature = Signature { v: block.signature.v ^ 1, r: block.signature.r, s: H256(high_s) };
+
+        assert!(matches!(
+            malleated.validate_with_clock(&clock),
+            Err(OlympusError::InvalidBlock(message)) if message.contains("upper half")
+        ));
+        assert!(block.validate_with_clock(&clock).is_ok());
+    }
+}
@@ -33,6 +33,70 @@ pub struct NetworkConfig {
     pub max_peers: usize,
     /// Enable UPnP
     pub enable_upnp: bool,
+    /// How gossiped transactions are propagated to peers
+    #[serde(default)]
+    pub transaction_propagation: TransactionPropagation,
+    /// Peer score limits and bans
+    #[serde(default)]
+    pub peer_scoring: PeerScoring,
+    /// Number of recently gossiped block and transaction hashes remembered
+    #[serde(default = "default_seen_cache_capacity")]
+    pub seen_cache_capacity: usize,
+}
+
+/// Default number of gossiped hashes remembered for deduplication
+pub const DEFAULT_SEEN_CACHE_CAPACITY: usize = 16_384;
+
+fn default_seen_cache_capacity() -> usize {
+    DEFAULT_SEEN_CACHE_CAPACITY
+}
+
+/// Peer score limits and bans
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PeerScoring {
+    /// Score peers start with
+    pub initial_score: f64,
+    /// Highest score rewards can raise a peer to
+    pub max_score: f64,
+    /// Score below which a peer is removed and banned
+    pub ban_threshold: f64,
+    /// Seconds a banned peer cannot be re-added
+    pub ban_duration: u64,
+}
+
+impl Default for PeerScoring {
+    fn default() -> Self {
+        Self {
+            initial_score: 1.0,
+            max_score: 10.0,
+            ban_threshold: 0.0,
+            ban_duration: 3600,
+        }
+    }
+}
+
+/// How transactions are propagated to peers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionPropagation {
+    /// Full bodies to every peer
+    #[default]
+    Flood,
+    /// Full bodies to the square root of the peer count, hashes to the rest
+    Sqrt,
+    /// Full bodies to a fixed number of peers, hashes to the rest
+    Fanout(usize),
+}
+
+impl TransactionPropagation {
+    /// Number of peers, out of `peers`, sent full transaction bodies
+    pub fn fanout(&self, peers: usize) -> usize {
+        match self {
+            TransactionPropagation::Flood => peers,
+            TransactionPropagation::Sqrt => peers.isqrt(),
+            TransactionPropagation::Fanout(fanout) => (*fanout).min(peers),
+        }
+    }
 }
 
 /// Database configuration
@@ -44,6 +108,20 @@ pub struct DatabaseConfig {
     pub write_buffer_size: u64,
     /// Enable cache filter
     pub cache_filter: bool,
+    /// How state commits reach the disk
+    #[serde(default)]
+    pub durability: CommitDurability,
+}
+
+/// Durability of state commits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitDurability {
+    /// Every commit is synced to disk before returning
+    #[default]
+    Durable,
+    /// Commits return immediately and are synced by the database in the background
+    Fast,
 }
 
 /// RPC configuration
@@ -59,6 +137,10 @@ pub struct RpcConfig {
     pub enable_websocket: bool,
     /// CORS origins
     pub cors_origins: Vec<String>,
+    /// Maximum number of cached eth_call results (0 disables the cache)
+    pub call_cache_size: usize,
+    /// Time-to-live of a cached eth_call result in seconds
+    pub call_cache_ttl: u64,
 }
 
 /// Consensus configuration
@@ -85,6 +167,20 @@ pub struct LoggingConfig {
     pub file: Option<PathBuf>,
     /// Enable console logging
     pub console: bool,
+    /// Log output format
+    #[serde(default)]
+    pub format: LogFormat,
+}
+
+/// Log output format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log aggregation
+    Json,
 }
 
 impl Default for Config {
@@ -108,6 +204,9 @@ impl Default for NetworkConfig {
             bootstrap_nodes: vec![],
             max_peers: 50,
             enable_upnp: true,
+            transaction_propagation: TransactionPropagation::default(),
+            peer_scoring: PeerScoring::default(),
+            seen_cache_capacity: DEFAULT_SEEN_CACHE_CAPACITY,
         }
     }
 }
@@ -118,6 +217,7 @@ impl Default for DatabaseConfig {
             cache_size: 2048, // 2GB
             write_buffer_size: 256, // 256MB
             cache_filter: true,
+            durability: CommitDurability::Durable,
         }
     }
 }
@@ -130,6 +230,8 @@ impl Default for RpcConfig {
             listen_port: 8765,
             enable_websocket: true,
             cors_origins: vec!["*".to_string()],
+            call_cache_size: 1024,
+            call_cache_ttl: 15,
         }
     }
 }
@@ -152,6 +254,7 @@ impl Default for LoggingConfig {
             level: "info".to_string(),
             file: None,
             console: true,
+            format: LogFormat::Pretty,
         }
     }
 }
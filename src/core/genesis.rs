@@ -0,0 +1,193 @@
+//! Genesis configuration and canonical genesis hash
+
+use crate::core::block::Block;
+use crate::core::types::*;
+use crate::evm::state::State;
+use crate::{Address, H256, U256, Result, OlympusError};
+use rlp::{RlpStream, Encodable};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Genesis configuration shared by every node on the same chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Genesis {
+    /// Chain ID the genesis is bound to
+    pub chain_id: u64,
+    /// Genesis timestamp
+    pub timestamp: u64,
+    /// Initial witness set
+    pub witnesses: Vec<Address>,
+    /// Pre-funded accounts (address -> balance)
+    pub alloc: BTreeMap<Address, U256>,
+}
+
+impl Genesis {
+    /// Create a new genesis configuration
+    pub fn new(chain_id: u64, timestamp: u64, witnesses: Vec<Address>, alloc: BTreeMap<Address, U256>) -> Self {
+        Self {
+            chain_id,
+            timestamp,
+            witnesses,
+            alloc,
+        }
+    }
+
+    /// Calculate the canonical genesis hash
+    ///
+    /// Witnesses are hashed in sorted order and the alloc is already ordered by
+    /// address, so two configs with the same parameters always agree.
+    pub fn hash(&self) -> H256 {
+        let rlp = self.rlp_bytes();
+        crate::common::keccak256(&rlp)
+    }
+
+    /// Get RLP encoded bytes
+    pub fn rlp_bytes(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+        self.rlp_append(&mut stream);
+        stream.out().to_vec()
+    }
+
+    /// Get chain ID
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Load a genesis configuration from a JSON file
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| OlympusError::Database(format!("Failed to read genesis {}: {}", path.display(), e)))?;
+        serde_json::from_str(&json)
+            .map_err(|e| OlympusError::Serialization(format!("Malformed genesis {}: {}", path.display(), e)))
+    }
+
+    /// Seed a state with the pre-funded accounts, each starting at nonce zero
+    pub fn build_state(&self, state: &mut dyn State) {
+        for (address, balance) in &self.alloc {
+            state.set_balance(*address, *balance);
+            state.set_nonce(*address, 0);
+        }
+    }
+
+    /// Build the genesis block
+    ///
+    /// The block has no creator or signature; it carries the genesis hash as
+    /// its last summary, so it differs between chains.
+    pub fn genesis_block(&self) -> Block {
+        Block::new(
+            Address::zero(),
+            H256::zero(),
+            vec![],
+            vec![],
+            vec![],
+            self.hash(),
+            H256::zero(),
+            H256::zero(),
+            self.timestamp,
+            U256::zero(),
+            Signature { v: 0, r: H256::zero(), s: H256::zero() },
+        )
+    }
+}
+
+impl Default for Genesis {
+    fn default() -> Self {
+        Self {
+            chain_id: CHAIN_ID,
+            timestamp: 0,
+            witnesses: vec![],
+            alloc: BTreeMap::new(),
+        }
+    }
+}
+
+impl Encodable for Genesis {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        let mut witnesses = self.witnesses.clone();
+        witnesses.sort();
+        witnesses.dedup();
+
+        s.begin_list(4);
+        s.append(&self.chain_id);
+        s.append(&self.timestamp);
+        s.append_list(&witnesses);
+        s.begin_list(self.alloc.len());
+        for (address, balance) in &self.alloc {
+            s.begin_list(2);
+            s.append(address);
+            s.append(balance);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_genesis() -> Genesis {
+        let mut alloc = BTreeMap::new();
+        alloc.insert(Address::from([0x11; 20]), U256::from(1_000_000u64));
+        alloc.insert(Address::from([0x22; 20]), U256::from(2_000_000u64));
+
+        Genesis::new(
+            CHAIN_ID,
+            1_600_000_000,
+            vec![Address::from([0x01; 20]), Address::from([0x02; 20])],
+            alloc,
+        )
+    }
+
+    #[test]
+    fn test_identical_genesis_hash_equal() {
+        let a = sample_genesis();
+        let b = sample_genesis();
+        assert_eq!(a.hash(), b.hash());
+
+        // Witness listing order does not change the canonical hash
+        let mut c = sample_genesis();
+        c.witnesses.reverse();
+        assert_eq!(a.hash(), c.hash());
+    }
+
+    #[test]
+    fn test_genesis_seeds_state_from_file() {
+        let genesis = sample_genesis();
+        let path = std::env::temp_dir().join(format!("olympus-genesis-{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string_pretty(&genesis).unwrap()).unwrap();
+        let loaded = Genesis::load_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.hash(), genesis.hash());
+
+        let mut state = crate::evm::state::MemoryState::new();
+        loaded.build_state(&mut state);
+        assert_eq!(state.get_balance(Address::from([0x11; 20])), U256::from(1_000_000u64));
+        assert_eq!(state.get_balance(Address::from([0x22; 20])), U256::from(2_000_000u64));
+        assert_eq!(state.get_nonce(Address::from([0x11; 20])), 0);
+
+        let block = loaded.genesis_block();
+        assert_eq!(block.exec_timestamp, 1_600_000_000);
+        assert_eq!(block.last_summary, genesis.hash());
+    }
+
+    #[test]
+    fn test_differing_genesis_hash_differs() {
+        let base = sample_genesis();
+
+        let mut other_chain = sample_genesis();
+        other_chain.chain_id = 1;
+        assert_ne!(base.hash(), other_chain.hash());
+
+        let mut other_alloc = sample_genesis();
+        other_alloc.alloc.insert(Address::from([0x33; 20]), U256::from(1u64));
+        assert_ne!(base.hash(), other_alloc.hash());
+
+        let mut other_time = sample_genesis();
+        other_time.timestamp += 1;
+        assert_ne!(base.hash(), other_time.hash());
+
+        let mut other_witnesses = sample_genesis();
+        other_witnesses.witnesses.push(Address::from([0x03; 20]));
+        assert_ne!(base.hash(), other_witnesses.hash());
+    }
+}
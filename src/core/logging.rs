@@ -0,0 +1,82 @@
+//! Logging setup from configuration
+
+use crate::core::config::{LogFormat, LoggingConfig};
+use crate::{OlympusError, Result};
+use std::fs::OpenOptions;
+use std::sync::Arc;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
+
+/// Build the log subscriber described by a logging configuration
+pub fn build_subscriber(config: &LoggingConfig) -> Result<Box<dyn Subscriber + Send + Sync>> {
+    let filter = EnvFilter::try_new(&config.level)
+        .map_err(|e| OlympusError::Serialization(format!("Invalid log level '{}': {}", config.level, e)))?;
+
+    let mut layers = Vec::new();
+    if config.console {
+        layers.push(format_layer(config.format, std::io::stdout, true));
+    }
+    if let Some(path) = &config.file {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| OlympusError::Database(format!("Failed to open log file {:?}: {}", path, e)))?;
+        layers.push(format_layer(config.format, Arc::new(file), false));
+    }
+
+    Ok(Box::new(Registry::default().with(layers).with(filter)))
+}
+
+/// Install the configured subscriber as the global default
+pub fn init_logging(config: &LoggingConfig) -> Result<()> {
+    tracing::subscriber::set_global_default(build_subscriber(config)?)
+        .map_err(|e| OlympusError::Serialization(e.to_string()))
+}
+
+/// Formatting layer writing to `writer`
+fn format_layer<W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+    match format {
+        LogFormat::Pretty => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::Level;
+
+    #[test]
+    fn test_subscriber_applies_configured_level() {
+        let config = LoggingConfig {
+            level: "warn".to_string(),
+            file: None,
+            console: true,
+            format: LogFormat::Json,
+        };
+
+        let subscriber = build_subscriber(&config).unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(tracing::enabled!(Level::ERROR));
+            assert!(tracing::enabled!(Level::WARN));
+            assert!(!tracing::enabled!(Level::INFO));
+            assert!(!tracing::enabled!(Level::DEBUG));
+        });
+    }
+
+    #[test]
+    fn test_invalid_level_rejected() {
+        let config = LoggingConfig {
+            level: "olympus=loud".to_string(),
+            ..LoggingConfig::default()
+        };
+        assert!(build_subscriber(&config).is_err());
+    }
+}
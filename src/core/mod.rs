@@ -4,10 +4,16 @@ pub mod block;
 pub mod transaction;
 pub mod approve;
 pub mod config;
+pub mod logging;
+pub mod genesis;
+pub mod receipt;
 pub mod types;
 
 pub use block::*;
 pub use transaction::*;
 pub use approve::*;
 pub use config::*;
+pub use logging::*;
+pub use genesis::*;
+pub use receipt::*;
 pub use types::*;
@@ -0,0 +1,55 @@
+//! Transaction receipts and log blooms
+
+use crate::evm::transaction_executor::{TransactionExecutionResult, TransactionLogEntry};
+use crate::{Address, U256};
+use ethereum_types::{Bloom, BloomInput};
+
+/// Receipt of a transaction executed in a block
+#[derive(Debug, Clone)]
+pub struct Receipt {
+    /// Whether the transaction succeeded
+    pub status: bool,
+    /// Gas used by this and all earlier transactions in the block
+    pub cumulative_gas_used: U256,
+    /// 2048-bit bloom of the addresses and topics of the logs
+    pub logs_bloom: [u8; 256],
+    /// Logs emitted
+    pub logs: Vec<TransactionLogEntry>,
+    /// Contract address (for contract creation)
+    pub contract_address: Option<Address>,
+}
+
+impl Receipt {
+    /// Create the receipt of an execution result
+    pub fn new(result: &TransactionExecutionResult, cumulative_gas_used: U256) -> Self {
+        Self {
+            status: result.success,
+            cumulative_gas_used,
+            logs_bloom: logs_bloom(&result.logs),
+            logs: result.logs.clone(),
+            contract_address: result.contract_address,
+        }
+    }
+
+    /// Check if the bloom may contain a log address or topic
+    ///
+    /// False positives are possible, false negatives are not.
+    pub fn bloom_contains(&self, input: &[u8]) -> bool {
+        Bloom(self.logs_bloom).contains_input(BloomInput::Raw(input))
+    }
+}
+
+/// Compute the bloom of the addresses and topics of `logs`
+///
+/// Each address and topic sets three bits, chosen by the first three 11-bit
+/// values of its keccak256.
+pub fn logs_bloom(logs: &[TransactionLogEntry]) -> [u8; 256] {
+    let mut bloom = Bloom::zero();
+    for log in logs {
+        bloom.accrue(BloomInput::Raw(log.address.as_bytes()));
+        for topic in &log.topics {
+            bloom.accrue(BloomInput::Raw(topic.as_bytes()));
+        }
+    }
+    bloom.0
+}
@@ -24,6 +24,40 @@ pub enum IncludeSignature {
     WithSignature = 1,
 }
 
+/// Transaction envelope type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionType {
+    /// Untyped transaction paying a single gas price
+    Legacy,
+    /// EIP-2930 transaction with an access list
+    Eip2930,
+    /// EIP-1559 dynamic fee transaction
+    Eip1559,
+}
+
+impl TransactionType {
+    /// Envelope type byte, zero for legacy transactions
+    pub fn type_byte(&self) -> u8 {
+        match self {
+            TransactionType::Legacy => 0x00,
+            TransactionType::Eip2930 => EIP2930_TRANSACTION_TYPE,
+            TransactionType::Eip1559 => EIP1559_TRANSACTION_TYPE,
+        }
+    }
+}
+
+/// Type byte of the EIP-2930 typed transaction envelope
+pub const EIP2930_TRANSACTION_TYPE: u8 = 0x01;
+
+/// Type byte of the EIP-1559 typed transaction envelope
+pub const EIP1559_TRANSACTION_TYPE: u8 = 0x02;
+
+/// Half the secp256k1 curve order; signatures with a larger `s` are malleable (EIP-2)
+pub const SECP256K1_HALF_ORDER: U256 = U256([0xdfe92f46681b20a0, 0x5d576e7357a4501d, 0xffffffffffffffff, 0x7fffffffffffffff]);
+
+/// Accounts and storage slots a transaction declares it will touch
+pub type AccessList = Vec<(Address, Vec<H256>)>;
+
 /// Transaction validation level
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CheckTransaction {
@@ -41,7 +75,7 @@ pub struct Transaction {
     pub value: U256,
     /// Receiving address (zero for contract creation)
     pub receive_address: Address,
-    /// Gas price in wei
+    /// Gas price in wei; the max fee per gas of an EIP-1559 transaction
     pub gas_price: U256,
     /// Gas limit
     pub gas: U256,
@@ -51,6 +85,20 @@ pub struct Transaction {
     pub signature: Option<Signature>,
     /// Chain ID for replay protection
     pub chain_id: Option<u64>,
+    /// Last block number the transaction may be included in
+    #[serde(default)]
+    pub valid_until: Option<u64>,
+    /// Max priority fee per gas, set only on EIP-1559 transactions
+    #[serde(default)]
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Accounts and storage slots warmed before execution; a legacy
+    /// transaction with an access list is sent as an EIP-2930 transaction
+    #[serde(default)]
+    pub access_list: AccessList,
+    /// Envelope type the transaction was decoded from; built transactions
+    /// leave it unset and take the type their fields imply
+    #[serde(default)]
+    pub tx_type: Option<TransactionType>,
 }
 
 impl Transaction {
@@ -72,6 +120,26 @@ impl Transaction {
             data,
             signature: None,
             chain_id: Some(crate::core::types::CHAIN_ID),
+            valid_until: None,
+            max_priority_fee_per_gas: None,
+            access_list: Vec::new(),
+            tx_type: None,
+        }
+    }
+
+    /// Create a new unsigned EIP-1559 message call transaction
+    pub fn new_eip1559(
+        value: U256,
+        max_fee_per_gas: U256,
+        max_priority_fee_per_gas: U256,
+        gas: U256,
+        dest: Address,
+        data: Vec<u8>,
+        nonce: U256,
+    ) -> Self {
+        Self {
+            max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+            ..Self::new(value, max_fee_per_gas, gas, dest, data, nonce)
         }
     }
 
@@ -92,6 +160,10 @@ impl Transaction {
             data,
             signature: None,
             chain_id: Some(crate::core::types::CHAIN_ID),
+            valid_until: None,
+            max_priority_fee_per_gas: None,
+            access_list: Vec::new(),
+            tx_type: None,
         }
     }
 
@@ -106,6 +178,10 @@ impl Transaction {
             data: skeleton.data,
             signature: None,
             chain_id: Some(crate::core::types::CHAIN_ID),
+            valid_until: None,
+            max_priority_fee_per_gas: None,
+            access_list: Vec::new(),
+            tx_type: None,
         };
 
         if let Some(secret_bytes) = secret {
@@ -115,6 +191,11 @@ impl Transaction {
         Ok(tx)
     }
 
+    /// Decode a raw transaction, legacy RLP or a typed envelope
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(rlp::decode(bytes)?)
+    }
+
     /// Get transaction sender address
     pub fn sender(&self) -> Result<Address> {
         match &self.signature {
@@ -125,42 +206,82 @@ impl Transaction {
         }
     }
 
+    /// Recover sender address and transaction hash from raw signed RLP
+    ///
+    /// Only the signature is decoded; the payload fields are copied as raw RLP
+    /// into the signing hash, so no `Transaction` is built.
+    pub fn sender_from_raw(raw: &[u8]) -> Result<(Address, TransactionHash)> {
+        if matches!(raw.first(), Some(&EIP2930_TRANSACTION_TYPE | &EIP1559_TRANSACTION_TYPE)) {
+            let transaction = decode_typed(raw)?;
+            return Ok((transaction.sender()?, crate::common::keccak256(raw)));
+        }
+
+        let rlp = Rlp::new(raw);
+        let item_count = rlp.item_count()?;
+        if item_count != 9 && item_count != 10 {
+            return Err(OlympusError::RlpDecoding(rlp::DecoderError::RlpIncorrectListLen));
+        }
+
+        let (v, r, s) = decode_signature_fields(&rlp, 6)?;
+        if r.is_zero() && s.is_zero() {
+            return Err(OlympusError::InvalidTransaction("Transaction is unsigned".to_string()));
+        }
+        // Pre-EIP-155 signatures cover only the payload, as `signing_hash` does
+        let chain_id = chain_id_from_v(v);
+
+        let mut stream = RlpStream::new_list(item_count - 3 * chain_id.is_none() as usize);
+        for index in 0..6 {
+            stream.append_raw(rlp.at(index)?.as_raw(), 1);
+        }
+        if let Some(chain_id) = chain_id {
+            stream.append(&chain_id);
+            stream.append(&0u8);
+            stream.append(&0u8);
+        }
+        if item_count == 10 {
+            stream.append_raw(rlp.at(9)?.as_raw(), 1);
+        }
+        let signing_hash = crate::common::keccak256(&stream.out());
+
+        let signature = Signature { v, r, s };
+        let sender = recover_address(signing_hash, &signature, recovery_id_from_v(v, chain_id)?)?;
+        Ok((sender, crate::common::keccak256(raw)))
+    }
+
     /// Recover sender address from signature
     fn recover_sender_from_signature(&self, sig: &Signature) -> Result<Address> {
-        use secp256k1::{Secp256k1, Message};
-        use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
-        
-        let secp = Secp256k1::new();
-        
-        // Create message hash
-        let message_hash = self.hash();
-        let message = Message::from_digest_slice(&message_hash.as_bytes())
-            .map_err(|_| OlympusError::InvalidTransaction("Invalid message hash".to_string()))?;
-        
-        // Calculate recovery ID from v value
-        let chain_id = self.chain_id.unwrap_or(1);
-        let recovery_id_value = sig.v as i32 - 27 - (chain_id * 2 + 35) as i32;
-        let recovery_id = RecoveryId::from_i32(recovery_id_value)
-            .map_err(|_| OlympusError::InvalidTransaction("Invalid recovery ID".to_string()))?;
-        
-        // Reconstruct signature
-        let mut signature_bytes = [0u8; 64];
-        signature_bytes[0..32].copy_from_slice(sig.r.as_bytes());
-        signature_bytes[32..64].copy_from_slice(sig.s.as_bytes());
-        
-        let recoverable_sig = RecoverableSignature::from_compact(&signature_bytes, recovery_id)
-            .map_err(|_| OlympusError::InvalidTransaction("Invalid signature".to_string()))?;
-        
-        // Recover public key
-        let public_key = secp.recover_ecdsa(&message, &recoverable_sig)
-            .map_err(|_| OlympusError::InvalidTransaction("Signature recovery failed".to_string()))?;
-        
-        // Convert public key to address (last 20 bytes of keccak256 hash)
-        let public_key_bytes = public_key.serialize_uncompressed();
-        let hash = crate::common::keccak256(&public_key_bytes[1..]); // Skip the 0x04 prefix
-        let address_bytes = &hash[12..]; // Take last 20 bytes
-        
-        Ok(Address::from_slice(address_bytes))
+        let recovery_id = match self.transaction_type() {
+            TransactionType::Legacy => recovery_id_from_v(sig.v, self.chain_id)?,
+            TransactionType::Eip2930 | TransactionType::Eip1559 if sig.v <= 1 => sig.v as i32,
+            TransactionType::Eip2930 | TransactionType::Eip1559 => {
+                return Err(OlympusError::InvalidTransaction(format!("Signature y parity {} out of range", sig.v)));
+            }
+        };
+        recover_address(self.signing_hash(), sig, recovery_id)
+    }
+
+    /// Get the hash signed by the sender
+    ///
+    /// This is the transaction encoded with the chain ID in place of the
+    /// signature, so it does not change once the signature is attached.
+    /// Typed transactions sign their envelope without the signature fields.
+    pub fn signing_hash(&self) -> H256 {
+        if self.transaction_type() != TransactionType::Legacy {
+            return crate::common::keccak256(&self.typed_envelope(IncludeSignature::WithoutSignature));
+        }
+
+        // Pre-EIP-155 transactions sign the bare payload, without a chain ID
+        let mut stream = RlpStream::new_list(6 + 3 * self.chain_id.is_some() as usize + self.valid_until.is_some() as usize);
+        self.rlp_append_payload(&mut stream);
+        if let Some(chain_id) = self.chain_id {
+            stream.append(&chain_id);
+            stream.append(&0u8);
+            stream.append(&0u8);
+        }
+        if let Some(valid_until) = self.valid_until {
+            stream.append(&valid_until);
+        }
+        crate::common::keccak256(&stream.out())
     }
 
     /// Get transaction sender address without throwing
@@ -168,6 +289,38 @@ impl Transaction {
         self.sender().unwrap_or(Address::zero())
     }
 
+    /// Get the transaction envelope type
+    ///
+    /// Decoded transactions keep the type they were received with, so an
+    /// EIP-2930 transaction with an empty access list re-encodes, hashes and
+    /// recovers as EIP-2930.
+    pub fn transaction_type(&self) -> TransactionType {
+        if let Some(tx_type) = self.tx_type {
+            return tx_type;
+        }
+        match self.max_priority_fee_per_gas {
+            Some(_) => TransactionType::Eip1559,
+            None if !self.access_list.is_empty() => TransactionType::Eip2930,
+            None => TransactionType::Legacy,
+        }
+    }
+
+    /// Get the most the sender pays per gas, base fee included
+    pub fn max_fee_per_gas(&self) -> U256 {
+        self.gas_price
+    }
+
+    /// Get the gas price paid under `base_fee`
+    ///
+    /// Legacy transactions pay their gas price; EIP-1559 transactions pay the
+    /// base fee plus their priority fee, capped at their max fee.
+    pub fn effective_gas_price(&self, base_fee: U256) -> U256 {
+        match self.max_priority_fee_per_gas {
+            Some(priority_fee) => self.gas_price.min(base_fee.saturating_add(priority_fee)),
+            None => self.gas_price,
+        }
+    }
+
     /// Check if transaction has signature
     pub fn has_signature(&self) -> bool {
         self.signature.is_some()
@@ -187,8 +340,18 @@ impl Transaction {
         self.chain_id
     }
 
+    /// Set the last block number the transaction may be included in
+    pub fn set_valid_until(&mut self, valid_until: Option<u64>) {
+        self.valid_until = valid_until;
+    }
+
+    /// Check if the transaction can no longer be included at `block_number`
+    pub fn is_expired(&self, block_number: u64) -> bool {
+        self.valid_until.is_some_and(|valid_until| valid_until < block_number)
+    }
+
     /// Set signature
-    pub fn set_signature(&mut self, r: H256, s: H256, v: u8) {
+    pub fn set_signature(&mut self, r: H256, s: H256, v: u64) {
         self.signature = Some(Signature { v, r, s });
     }
 
@@ -214,8 +377,12 @@ impl Transaction {
         crate::common::keccak256(&rlp)
     }
 
-    /// Get RLP encoded bytes
+    /// Get RLP encoded bytes, or the envelope of a typed transaction
     pub fn rlp_bytes(&self, include_sig: IncludeSignature) -> Vec<u8> {
+        if self.transaction_type() != TransactionType::Legacy {
+            return self.typed_envelope(include_sig);
+        }
+
         let mut stream = RlpStream::new();
         self.rlp_append_with_signature(&mut stream, include_sig);
         stream.out().to_vec()
@@ -234,8 +401,8 @@ impl Transaction {
             .map_err(|_| OlympusError::InvalidTransaction("Invalid private key".to_string()))?;
         
         // Create message hash for signing
-        let message_hash = self.hash();
-        let message = Message::from_digest_slice(&message_hash.as_bytes())
+        let message_hash = self.signing_hash();
+        let message = Message::from_digest_slice(message_hash.as_bytes())
             .map_err(|_| OlympusError::InvalidTransaction("Invalid message hash".to_string()))?;
         
         // Sign the message
@@ -248,9 +415,15 @@ impl Transaction {
         r_bytes.copy_from_slice(&signature_bytes[0..32]);
         s_bytes.copy_from_slice(&signature_bytes[32..64]);
         
-        // Calculate v value with chain ID
-        let chain_id = self.chain_id.unwrap_or(1);
-        let v = recovery_id.to_i32() as u8 + 27 + (chain_id * 2 + 35) as u8;
+        // Typed transactions carry the bare y parity, legacy ones fold in the chain ID
+        let v = match self.transaction_type() {
+            TransactionType::Eip2930 | TransactionType::Eip1559 => recovery_id.to_i32() as u64,
+            // EIP-155: v = recovery_id + chain_id * 2 + 35, or 27 + recovery_id without a chain ID
+            TransactionType::Legacy => match self.chain_id {
+                Some(chain_id) => recovery_id.to_i32() as u64 + chain_id * 2 + 35,
+                None => recovery_id.to_i32() as u64 + 27,
+            },
+        };
         
         self.signature = Some(Signature {
             v,
@@ -261,8 +434,13 @@ impl Transaction {
         Ok(())
     }
 
-    /// Validate transaction
+    /// Validate transaction against the gas schedule of the latest hardfork
     pub fn validate(&self, check_level: CheckTransaction) -> Result<()> {
+        self.validate_with_schedule(check_level, &crate::evm::gas_schedule::GasSchedule::default())
+    }
+
+    /// Validate transaction, pricing its intrinsic gas with `schedule`
+    pub fn validate_with_schedule(&self, check_level: CheckTransaction, schedule: &crate::evm::gas_schedule::GasSchedule) -> Result<()> {
         match check_level {
             CheckTransaction::None => Ok(()),
             CheckTransaction::Cheap => {
@@ -270,43 +448,55 @@ impl Transaction {
                 if self.gas == U256::zero() {
                     return Err(OlympusError::InvalidTransaction("Gas cannot be zero".to_string()));
                 }
+                if self.gas < U256::from(self.intrinsic_gas(schedule)) {
+                    return Err(OlympusError::InvalidTransaction("intrinsic gas too low".to_string()));
+                }
                 if self.gas_price == U256::zero() {
                     return Err(OlympusError::InvalidTransaction("Gas price cannot be zero".to_string()));
                 }
+                if self.max_priority_fee_per_gas.is_some_and(|priority_fee| priority_fee > self.gas_price) {
+                    return Err(OlympusError::InvalidTransaction("Max priority fee exceeds max fee".to_string()));
+                }
                 Ok(())
             }
             CheckTransaction::Everything => {
                 // Full validation including signature verification
-                self.validate(CheckTransaction::Cheap)?;
+                self.validate_with_schedule(CheckTransaction::Cheap, schedule)?;
                 
-                if self.signature.is_none() {
+                let Some(signature) = &self.signature else {
                     return Err(OlympusError::InvalidTransaction("Transaction must be signed".to_string()));
+                };
+                // Only pre-EIP-155 legacy transactions, which decoding accepts, sign without a chain ID
+                let missing_chain_id = match self.chain_id {
+                    Some(chain_id) => chain_id == 0,
+                    None => self.transaction_type() != TransactionType::Legacy,
+                };
+                if missing_chain_id {
+                    return Err(OlympusError::InvalidTransaction("Signed transaction must have a chain ID".to_string()));
+                }
+                if U256::from_big_endian(signature.s.as_bytes()) > SECP256K1_HALF_ORDER {
+                    return Err(OlympusError::InvalidTransaction("Signature s is in the upper half of the curve order".to_string()));
+                }
+                if self.sender()?.is_zero() {
+                    return Err(OlympusError::InvalidTransaction("Signature recovers to the zero address".to_string()));
                 }
-                
-                // Additional validation would go here
                 Ok(())
             }
         }
     }
 
-    /// Calculate base gas required
+    /// Calculate base gas required under the latest hardfork
     pub fn base_gas_required(&self) -> u64 {
-        let mut gas = 21000; // Base transaction cost
-        
-        if self.is_creation() {
-            gas += 32000; // Contract creation cost
-        }
-        
-        // Add cost for data (68 gas per non-zero byte, 4 gas per zero byte)
-        for byte in &self.data {
-            if *byte == 0 {
-                gas += 4;
-            } else {
-                gas += 68;
-            }
-        }
-        
-        gas
+        self.intrinsic_gas(&crate::evm::gas_schedule::GasSchedule::default())
+    }
+
+    /// Calculate intrinsic gas under a gas schedule, including the access list charge
+    ///
+    /// Where the schedule sets a calldata floor (EIP-7623) the gas limit must
+    /// also cover it, so the larger of the two is returned.
+    pub fn intrinsic_gas(&self, schedule: &crate::evm::gas_schedule::GasSchedule) -> u64 {
+        let intrinsic = schedule.intrinsic_gas(&self.data, self.is_creation()) + schedule.access_list_gas(&self.access_list);
+        intrinsic.max(schedule.floor_gas(&self.data))
     }
 
     /// Get transaction value
@@ -342,7 +532,13 @@ impl Transaction {
 
 impl Encodable for Transaction {
     fn rlp_append(&self, s: &mut RlpStream) {
-        self.rlp_append_with_signature(s, IncludeSignature::WithSignature);
+        match self.transaction_type() {
+            TransactionType::Legacy => self.rlp_append_with_signature(s, IncludeSignature::WithSignature),
+            // Typed envelopes nest as a byte string, as in block bodies
+            TransactionType::Eip2930 | TransactionType::Eip1559 => {
+                s.append(&self.typed_envelope(IncludeSignature::WithSignature));
+            }
+        }
     }
 }
 
@@ -350,33 +546,250 @@ impl Transaction {
     fn rlp_append_with_signature(&self, s: &mut RlpStream, include_sig: IncludeSignature) {
         match include_sig {
             IncludeSignature::WithSignature => {
-                s.begin_list(9);
-                s.append(&self.nonce);
-                s.append(&self.gas_price);
-                s.append(&self.gas);
-                s.append(&self.receive_address);
-                s.append(&self.value);
-                s.append(&self.data);
-                s.append(&self.chain_id.unwrap_or(0));
-                s.append(&0u8); // r
-                s.append(&0u8); // s
-                s.append(&0u8); // v
+                // The optional deadline is a trailing tenth item
+                s.begin_list(9 + self.valid_until.is_some() as usize);
+                self.rlp_append_payload(s);
+                match &self.signature {
+                    Some(sig) => {
+                        // r and s are scalars, encoded without leading zeros
+                        s.append(&sig.v);
+                        s.append(&U256::from_big_endian(sig.r.as_bytes()));
+                        s.append(&U256::from_big_endian(sig.s.as_bytes()));
+                    }
+                    None => {
+                        s.append(&self.chain_id.unwrap_or(0));
+                        s.append(&0u8); // r
+                        s.append(&0u8); // s
+                    }
+                }
+                if let Some(valid_until) = self.valid_until {
+                    s.append(&valid_until);
+                }
             }
             IncludeSignature::WithoutSignature => {
                 s.begin_list(6);
-                s.append(&self.nonce);
-                s.append(&self.gas_price);
-                s.append(&self.gas);
-                s.append(&self.receive_address);
-                s.append(&self.value);
-                s.append(&self.data);
+                self.rlp_append_payload(s);
             }
         }
     }
+
+    /// Encode the envelope `type || rlp([chain_id, nonce, fees, gas, to, value, data, access_list, ...])`
+    ///
+    /// EIP-2930 transactions carry the gas price as their only fee, EIP-1559
+    /// ones the priority fee then the max fee. The signed form appends y
+    /// parity, r and s; either form may end with the optional deadline.
+    fn typed_envelope(&self, include_sig: IncludeSignature) -> Vec<u8> {
+        let tx_type = self.transaction_type();
+        let fees = match tx_type {
+            TransactionType::Eip1559 => vec![self.max_priority_fee_per_gas.unwrap_or_default(), self.gas_price],
+            TransactionType::Legacy | TransactionType::Eip2930 => vec![self.gas_price],
+        };
+        let signature_items = match include_sig {
+            IncludeSignature::WithSignature => 3,
+            IncludeSignature::WithoutSignature => 0,
+        };
+
+        let mut stream = RlpStream::new_list(7 + fees.len() + signature_items + self.valid_until.is_some() as usize);
+        stream.append(&self.chain_id.unwrap_or(CHAIN_ID));
+        stream.append(&self.nonce);
+        for fee in &fees {
+            stream.append(fee);
+        }
+        stream.append(&self.gas);
+        self.append_recipient(&mut stream);
+        stream.append(&self.value);
+        stream.append(&self.data);
+        stream.begin_list(self.access_list.len());
+        for (address, keys) in &self.access_list {
+            stream.begin_list(2);
+            stream.append(address);
+            stream.append_list(keys);
+        }
+        if include_sig == IncludeSignature::WithSignature {
+            match &self.signature {
+                Some(sig) => {
+                    stream.append(&sig.v);
+                    stream.append(&U256::from_big_endian(sig.r.as_bytes()));
+                    stream.append(&U256::from_big_endian(sig.s.as_bytes()));
+                }
+                None => {
+                    stream.append(&0u8);
+                    stream.append(&0u8);
+                    stream.append(&0u8);
+                }
+            }
+        }
+        if let Some(valid_until) = self.valid_until {
+            stream.append(&valid_until);
+        }
+
+        let mut envelope = vec![tx_type.type_byte()];
+        envelope.extend_from_slice(&stream.out());
+        envelope
+    }
+
+    /// Append the six payload fields shared by every encoding
+    fn rlp_append_payload(&self, s: &mut RlpStream) {
+        s.append(&self.nonce);
+        s.append(&self.gas_price);
+        s.append(&self.gas);
+        self.append_recipient(s);
+        s.append(&self.value);
+        s.append(&self.data);
+    }
+
+    /// Append the recipient, an empty string for contract creation
+    fn append_recipient(&self, s: &mut RlpStream) {
+        if self.is_creation() {
+            s.append_empty_data();
+        } else {
+            s.append(&self.receive_address);
+        }
+    }
+}
+
+/// Decode the recipient at item `index`, empty for contract creation
+fn decode_recipient(rlp: &Rlp, index: usize) -> std::result::Result<Address, rlp::DecoderError> {
+    let item = rlp.at(index)?;
+    if item.is_empty() {
+        Ok(Address::zero())
+    } else {
+        item.as_val()
+    }
+}
+
+/// Decode the v, r, s fields of a transaction list, starting at item `first`
+///
+/// Unsigned transactions carry empty r and s, which decode as zero.
+fn decode_signature_fields(rlp: &Rlp, first: usize) -> std::result::Result<(u64, H256, H256), rlp::DecoderError> {
+    let v: u64 = rlp.val_at(first)?;
+    let word = |index: usize| -> std::result::Result<H256, rlp::DecoderError> {
+        let data = rlp.at(index)?.data()?;
+        if data.len() > 32 {
+            return Err(rlp::DecoderError::RlpIsTooBig);
+        }
+        let mut bytes = [0u8; 32];
+        bytes[32 - data.len()..].copy_from_slice(data);
+        Ok(H256::from(bytes))
+    };
+    Ok((v, word(first + 1)?, word(first + 2)?))
+}
+
+/// Decode an EIP-2930 or EIP-1559 envelope
+fn decode_typed(envelope: &[u8]) -> std::result::Result<Transaction, rlp::DecoderError> {
+    let (tx_type, fee_items, payload) = match envelope.split_first() {
+        Some((&EIP2930_TRANSACTION_TYPE, payload)) => (TransactionType::Eip2930, 1, payload),
+        Some((&EIP1559_TRANSACTION_TYPE, payload)) => (TransactionType::Eip1559, 2, payload),
+        _ => return Err(rlp::DecoderError::Custom("Unsupported transaction type")),
+    };
+    let rlp = Rlp::new(payload);
+    let item_count = rlp.item_count()?;
+    let signed_items = 10 + fee_items;
+    if item_count != signed_items && item_count != signed_items + 1 {
+        return Err(rlp::DecoderError::RlpIncorrectListLen);
+    }
+
+    let access_list = rlp.at(6 + fee_items)?.iter()
+        .map(|item| {
+            if item.item_count()? != 2 {
+                return Err(rlp::DecoderError::RlpIncorrectListLen);
+            }
+            Ok((item.val_at(0)?, item.list_at(1)?))
+        })
+        .collect::<std::result::Result<AccessList, _>>()?;
+
+    let (v, r, s) = decode_signature_fields(&rlp, 7 + fee_items)?;
+    let signature = if r.is_zero() && s.is_zero() {
+        None
+    } else {
+        Some(Signature { v, r, s })
+    };
+
+    Ok(Transaction {
+        chain_id: Some(rlp.val_at(0)?),
+        nonce: rlp.val_at(1)?,
+        max_priority_fee_per_gas: if fee_items == 2 { Some(rlp.val_at(2)?) } else { None },
+        gas_price: rlp.val_at(1 + fee_items)?,
+        gas: rlp.val_at(2 + fee_items)?,
+        receive_address: decode_recipient(&rlp, 3 + fee_items)?,
+        value: rlp.val_at(4 + fee_items)?,
+        data: rlp.val_at(5 + fee_items)?,
+        access_list,
+        signature,
+        valid_until: if item_count > signed_items { Some(rlp.val_at(signed_items)?) } else { None },
+        tx_type: Some(tx_type),
+    })
+}
+
+/// Derive the chain ID from an EIP-155 signature's v
+///
+/// Pre-EIP-155 v (27/28) carries no chain ID.
+fn chain_id_from_v(v: u64) -> Option<u64> {
+    match v {
+        35.. => Some((v - 35) / 2),
+        _ => None,
+    }
+}
+
+/// Get the recovery ID encoded in a signature's v
+///
+/// Accepts pre-EIP-155 v (27/28) only without a chain ID, and otherwise only
+/// the two EIP-155 values for the chain; anything else is rejected before any
+/// arithmetic on it.
+fn recovery_id_from_v(v: u64, chain_id: Option<u64>) -> Result<i32> {
+    let Some(chain_id) = chain_id else {
+        return match v {
+            27 | 28 => Ok((v - 27) as i32),
+            _ => Err(OlympusError::InvalidTransaction(format!("Signature v {} out of range without a chain ID", v))),
+        };
+    };
+    match chain_id.checked_mul(2).and_then(|doubled| doubled.checked_add(35)) {
+        Some(base) if v == base || v == base + 1 => Ok((v - base) as i32),
+        _ => Err(OlympusError::InvalidTransaction(format!(
+            "Signature v {} out of range for chain {}", v, chain_id
+        ))),
+    }
+}
+
+/// Recover the signer address of a message hash
+fn recover_address(message_hash: H256, sig: &Signature, recovery_id: i32) -> Result<Address> {
+    use secp256k1::{Secp256k1, Message};
+    use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+
+    let secp = Secp256k1::new();
+    let message = Message::from_digest_slice(message_hash.as_bytes())
+        .map_err(|_| OlympusError::InvalidTransaction("Invalid message hash".to_string()))?;
+
+    let recovery_id = RecoveryId::from_i32(recovery_id)
+        .map_err(|_| OlympusError::InvalidTransaction("Invalid recovery ID".to_string()))?;
+
+    // Reconstruct signature
+    let mut signature_bytes = [0u8; 64];
+    signature_bytes[0..32].copy_from_slice(sig.r.as_bytes());
+    signature_bytes[32..64].copy_from_slice(sig.s.as_bytes());
+
+    let recoverable_sig = RecoverableSignature::from_compact(&signature_bytes, recovery_id)
+        .map_err(|_| OlympusError::InvalidTransaction("Invalid signature".to_string()))?;
+
+    // Recover public key
+    let public_key = secp.recover_ecdsa(&message, &recoverable_sig)
+        .map_err(|_| OlympusError::InvalidTransaction("Signature recovery failed".to_string()))?;
+
+    Ok(crate::common::public_key_to_address(&public_key))
 }
 
 impl Decodable for Transaction {
     fn decode(rlp: &Rlp) -> std::result::Result<Self, rlp::DecoderError> {
+        if rlp.is_data() {
+            // A typed envelope, either raw or nested as a byte string
+            let raw = rlp.as_raw();
+            let envelope = match raw.first() {
+                Some(&EIP2930_TRANSACTION_TYPE | &EIP1559_TRANSACTION_TYPE) => raw,
+                _ => rlp.data()?,
+            };
+            return decode_typed(envelope);
+        }
+
         let item_count = rlp.item_count()?;
         
         if item_count == 6 {
@@ -385,27 +798,38 @@ impl Decodable for Transaction {
                 nonce: rlp.val_at(0)?,
                 gas_price: rlp.val_at(1)?,
                 gas: rlp.val_at(2)?,
-                receive_address: rlp.val_at(3)?,
+                receive_address: decode_recipient(rlp, 3)?,
                 value: rlp.val_at(4)?,
                 data: rlp.val_at(5)?,
                 signature: None,
                 chain_id: None,
+                valid_until: None,
+                max_priority_fee_per_gas: None,
+                access_list: Vec::new(),
+                tx_type: Some(TransactionType::Legacy),
             })
-        } else if item_count == 9 {
-            // Signed transaction
+        } else if item_count == 9 || item_count == 10 {
+            // Signed transaction, or unsigned with the chain ID in place of v
+            let (v, r, s) = decode_signature_fields(rlp, 6)?;
+            let (signature, chain_id) = if r.is_zero() && s.is_zero() {
+                (None, if v == 0 { None } else { Some(v) })
+            } else {
+                (Some(Signature { v, r, s }), chain_id_from_v(v))
+            };
+
             Ok(Transaction {
                 nonce: rlp.val_at(0)?,
                 gas_price: rlp.val_at(1)?,
                 gas: rlp.val_at(2)?,
-                receive_address: rlp.val_at(3)?,
+                receive_address: decode_recipient(rlp, 3)?,
                 value: rlp.val_at(4)?,
                 data: rlp.val_at(5)?,
-                chain_id: Some(rlp.val_at(6)?),
-                signature: Some(Signature {
-                    v: rlp.val_at(9)?,
-                    r: rlp.val_at(7)?,
-                    s: rlp.val_at(8)?,
-                }),
+                chain_id,
+                signature,
+                valid_until: if item_count == 10 { Some(rlp.val_at(9)?) } else { None },
+                max_priority_fee_per_gas: None,
+                access_list: Vec::new(),
+                tx_type: Some(TransactionType::Legacy),
             })
         } else {
             Err(rlp::DecoderError::RlpIncorrectListLen)
@@ -460,3 +884,319 @@ impl LocalizedTransaction {
 
 /// Collection of transactions
 pub type Transactions = Vec<Transaction>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_sender_from_raw_matches_full_decode() {
+        let secret = [0x11u8; 32];
+        let mut tx = Transaction::new(
+            U256::from(1000),
+            U256::from(20_000_000_000u64),
+            U256::from(21000),
+            Address::from([0x42; 20]),
+            vec![0xca, 0xfe],
+            U256::from(7),
+        );
+        tx.sign_with_secret(&secret).unwrap();
+
+        let raw = tx.rlp_bytes(IncludeSignature::WithSignature);
+        let decoded: Transaction = rlp::decode(&raw).unwrap();
+        let (sender, hash) = Transaction::sender_from_raw(&raw).unwrap();
+
+        assert_eq!(sender, decoded.sender().unwrap());
+        assert_eq!(hash, decoded.hash());
+        assert_eq!(hash, tx.hash());
+
+        let secp = secp256k1::Secp256k1::new();
+        let key = secp256k1::SecretKey::from_slice(&secret).unwrap();
+        let expected = crate::common::public_key_to_address(&secp256k1::PublicKey::from_secret_key(&secp, &key));
+        assert_eq!(sender, expected);
+    }
+
+    #[test]
+    fn test_valid_until_is_signed_and_round_trips() {
+        let mut tx = Transaction::new(U256::zero(), U256::one(), U256::from(21000), Address::from([0x42; 20]), vec![], U256::zero());
+        let unbounded_hash = tx.signing_hash();
+        tx.set_valid_until(Some(100));
+        assert_ne!(tx.signing_hash(), unbounded_hash);
+
+        tx.sign_with_secret(&[0x22u8; 32]).unwrap();
+        let raw = tx.rlp_bytes(IncludeSignature::WithSignature);
+        let decoded: Transaction = rlp::decode(&raw).unwrap();
+        assert_eq!(decoded.valid_until, Some(100));
+        assert_eq!(Transaction::sender_from_raw(&raw).unwrap().0, tx.sender().unwrap());
+        assert!(!decoded.is_expired(100));
+        assert!(decoded.is_expired(101));
+    }
+
+    #[test]
+    fn test_sender_from_raw_rejects_unsigned() {
+        let tx = Transaction::new(U256::zero(), U256::one(), U256::from(21000), Address::zero(), vec![], U256::zero());
+        let raw = tx.rlp_bytes(IncludeSignature::WithSignature);
+        assert!(Transaction::sender_from_raw(&raw).is_err());
+    }
+
+    fn secret_address(secret: &[u8; 32]) -> Address {
+        let secp = secp256k1::Secp256k1::new();
+        let key = secp256k1::SecretKey::from_slice(secret).unwrap();
+        crate::common::public_key_to_address(&secp256k1::PublicKey::from_secret_key(&secp, &key))
+    }
+
+    #[test]
+    fn test_sender_rejects_legacy_v_with_chain_id() {
+        let secret = [0x33u8; 32];
+        let mut tx = Transaction::new(U256::zero(), U256::one(), U256::from(21000), Address::from([0x42; 20]), vec![], U256::zero());
+        tx.chain_id = Some(1);
+
+        let secp = secp256k1::Secp256k1::new();
+        let key = secp256k1::SecretKey::from_slice(&secret).unwrap();
+        let message = secp256k1::Message::from_digest_slice(tx.signing_hash().as_bytes()).unwrap();
+        let (recovery_id, compact) = secp.sign_ecdsa_recoverable(&message, &key).serialize_compact();
+        tx.set_signature(H256::from_slice(&compact[..32]), H256::from_slice(&compact[32..]), 27 + recovery_id.to_i32() as u64);
+
+        // A chain ID makes the signature EIP-155, so v must encode it
+        assert!(tx.sender().is_err());
+        assert!(tx.validate(CheckTransaction::Everything).is_err());
+    }
+
+    #[test]
+    fn test_sender_accepts_chain_specific_v() {
+        let secret = [0x44u8; 32];
+        let mut tx = Transaction::new(U256::zero(), U256::one(), U256::from(21000), Address::from([0x42; 20]), vec![], U256::zero());
+        tx.sign_with_secret(&secret).unwrap();
+        assert_eq!(tx.sender().unwrap(), secret_address(&secret));
+    }
+
+    #[test]
+    fn test_signature_v_follows_eip155() {
+        let secret = [0x46u8; 32];
+        let mut tx = Transaction::new(U256::exp10(18), U256::from(20_000_000_000u64), U256::from(21000), Address::from([0x35; 20]), vec![], U256::from(9));
+
+        // Example transaction from EIP-155
+        tx.chain_id = Some(1);
+        tx.sign_with_secret(&secret).unwrap();
+        assert_eq!(
+            hex::encode(tx.rlp_bytes(IncludeSignature::WithSignature)),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+
+        tx.chain_id = Some(970);
+        tx.sign_with_secret(&secret).unwrap();
+        let signature = tx.signature.clone().unwrap();
+        assert_eq!(signature.v, 1976);
+        assert_eq!(signature.r, H256::from_str("1d8e4000a41f008aaba39a4a352ffcae20d36c0d447099f38281ec649e1bf98c").unwrap());
+        assert_eq!(signature.s, H256::from_str("5f230b7fcef05dabe0cc974f46414d619409e68f8104898b1369cad6fffb34bf").unwrap());
+        assert_eq!(tx.sender().unwrap(), secret_address(&secret));
+    }
+
+    #[test]
+    fn test_validation_rejects_gas_below_intrinsic_cost() {
+        let data = vec![0xffu8; 1024];
+        let mut tx = Transaction::new(U256::zero(), U256::one(), U256::from(21000), Address::from([0x42; 20]), data, U256::zero());
+        assert!(tx.base_gas_required() > 21000);
+        assert!(matches!(
+            tx.validate(CheckTransaction::Cheap),
+            Err(OlympusError::InvalidTransaction(message)) if message == "intrinsic gas too low"
+        ));
+
+        tx.gas = U256::from(tx.base_gas_required());
+        assert!(tx.validate(CheckTransaction::Cheap).is_ok());
+    }
+
+    #[test]
+    fn test_full_validation_verifies_signature() {
+        let mut tx = Transaction::new(U256::zero(), U256::one(), U256::from(21000), Address::from([0x42; 20]), vec![], U256::zero());
+        tx.sign_with_secret(&[0x77u8; 32]).unwrap();
+        assert!(tx.validate(CheckTransaction::Everything).is_ok());
+        let signature = tx.signature.clone().unwrap();
+
+        // r past the curve order cannot be recovered
+        let mut tampered = tx.clone();
+        tampered.set_signature(H256::repeat_byte(0xff), signature.s, signature.v);
+        assert!(matches!(tampered.validate(CheckTransaction::Everything), Err(OlympusError::InvalidTransaction(_))));
+
+        // The mirrored s with flipped parity recovers the same sender but is malleable
+        let mut malleable = tx.clone();
+        let order: U256 = SECP256K1_HALF_ORDER * 2 + 1;
+        let mut high_s = [0u8; 32];
+        (order - U256::from_big_endian(signature.s.as_bytes())).to_big_endian(&mut high_s);
+        let flipped_v = if signature.v % 2 == 1 { signature.v + 1 } else { signature.v - 1 };
+        malleable.set_signature(signature.r, H256(high_s), flipped_v);
+        assert_eq!(malleable.sender().unwrap(), tx.sender().unwrap());
+        assert!(matches!(malleable.validate(CheckTransaction::Everything), Err(OlympusError::InvalidTransaction(_))));
+    }
+
+    #[test]
+    fn test_sender_rejects_out_of_range_v() {
+        let mut tx = Transaction::new(U256::zero(), U256::one(), U256::from(21000), Address::from([0x42; 20]), vec![], U256::zero());
+        tx.chain_id = Some(1);
+        tx.sign_with_secret(&[0x55u8; 32]).unwrap();
+        let sig = tx.signature.clone().unwrap();
+
+        for v in [0u64, 26, 29, 36, 39, 63, 66, u64::MAX] {
+            tx.set_signature(sig.r, sig.s, v);
+            assert!(matches!(tx.sender(), Err(OlympusError::InvalidTransaction(_))), "v = {}", v);
+        }
+
+        // A chain ID too large for any v must not overflow
+        tx.chain_id = Some(u64::MAX);
+        tx.set_signature(sig.r, sig.s, sig.v);
+        assert!(matches!(tx.sender(), Err(OlympusError::InvalidTransaction(_))));
+    }
+
+    #[test]
+    fn test_signed_transaction_round_trips_signature() {
+        let mut tx = Transaction::new(U256::from(5), U256::one(), U256::from(21000), Address::from([0x42; 20]), vec![0x01], U256::from(3));
+        tx.sign_with_secret(&[0x66u8; 32]).unwrap();
+
+        let raw = tx.rlp_bytes(IncludeSignature::WithSignature);
+        let signature = tx.signature.clone().unwrap();
+        assert_eq!(Rlp::new(&raw).val_at::<u64>(6).unwrap(), signature.v);
+        assert_eq!(Rlp::new(&raw).val_at::<U256>(7).unwrap(), U256::from_big_endian(signature.r.as_bytes()));
+
+        let decoded: Transaction = rlp::decode(&raw).unwrap();
+        assert_eq!(decoded.signature, Some(signature));
+        assert_eq!(decoded.chain_id, tx.chain_id);
+        assert_eq!(decoded.hash(), tx.hash());
+        assert_eq!(decoded.sender().unwrap(), tx.sender().unwrap());
+    }
+
+    #[test]
+    fn test_legacy_signed_transaction_round_trips() {
+        let secret = [0x88u8; 32];
+        let mut tx = Transaction::new(U256::from(5), U256::one(), U256::from(21000), Address::from([0x42; 20]), vec![0x01], U256::from(3));
+        tx.chain_id = None;
+
+        let secp = secp256k1::Secp256k1::new();
+        let key = secp256k1::SecretKey::from_slice(&secret).unwrap();
+        let message = secp256k1::Message::from_digest_slice(tx.signing_hash().as_bytes()).unwrap();
+        let (recovery_id, compact) = secp.sign_ecdsa_recoverable(&message, &key).serialize_compact();
+        tx.set_signature(H256::from_slice(&compact[..32]), H256::from_slice(&compact[32..]), 27 + recovery_id.to_i32() as u64);
+
+        let raw = tx.rlp_bytes(IncludeSignature::WithSignature);
+        assert_eq!(Rlp::new(&raw).item_count().unwrap(), 9);
+        let decoded: Transaction = rlp::decode(&raw).unwrap();
+        assert_eq!(decoded.signature, tx.signature);
+        assert_eq!(decoded.chain_id, None);
+        assert_eq!(decoded.hash(), tx.hash());
+        assert_eq!(decoded.sender().unwrap(), secret_address(&secret));
+        assert_eq!(Transaction::sender_from_raw(&raw).unwrap().0, secret_address(&secret));
+    }
+
+    #[test]
+    fn test_typed_envelope_defaults_to_olympus_chain_id() {
+        let tx = Transaction::new_eip1559(U256::zero(), U256::from(2), U256::one(), U256::from(21000), Address::from([0x42; 20]), vec![], U256::zero());
+        let mut without_chain_id = tx.clone();
+        without_chain_id.chain_id = None;
+        assert_eq!(without_chain_id.signing_hash(), tx.signing_hash());
+    }
+
+    #[test]
+    fn test_eip1559_transaction_round_trips() {
+        let secret = [0x99u8; 32];
+        let gwei = U256::from(1_000_000_000u64);
+        let mut tx = Transaction::new_eip1559(U256::from(7), gwei * 30, gwei * 2, U256::from(21000), Address::from([0x42; 20]), vec![0xab], U256::from(4));
+        tx.sign_with_secret(&secret).unwrap();
+        assert_eq!(tx.transaction_type(), TransactionType::Eip1559);
+        assert!(tx.signature.as_ref().unwrap().v <= 1);
+
+        let raw = tx.rlp_bytes(IncludeSignature::WithSignature);
+        assert_eq!(raw[0], EIP1559_TRANSACTION_TYPE);
+        assert_eq!(Rlp::new(&raw[1..]).item_count().unwrap(), 12);
+        assert_eq!(tx.hash(), crate::common::keccak256(&raw));
+
+        // Both the raw envelope and the envelope nested as a byte string decode
+        for encoded in [raw.clone(), rlp::encode(&tx).to_vec()] {
+            let decoded: Transaction = rlp::decode(&encoded).unwrap();
+            assert_eq!(decoded.max_priority_fee_per_gas, Some(gwei * 2));
+            assert_eq!(decoded.max_fee_per_gas(), gwei * 30);
+            assert_eq!(decoded.chain_id, tx.chain_id);
+            assert_eq!(decoded.signature, tx.signature);
+            assert_eq!(decoded.hash(), tx.hash());
+            assert_eq!(decoded.sender().unwrap(), secret_address(&secret));
+        }
+        assert_eq!(Transaction::sender_from_raw(&raw).unwrap(), (secret_address(&secret), tx.hash()));
+
+        // The tip is capped by the max fee
+        assert_eq!(tx.effective_gas_price(gwei * 10), gwei * 12);
+        assert_eq!(tx.effective_gas_price(gwei * 29), gwei * 30);
+    }
+
+    #[test]
+    fn test_eip2930_transaction_round_trips() {
+        let secret = [0xaau8; 32];
+        let mut tx = Transaction::new(U256::from(7), U256::from(3), U256::from(50_000), Address::from([0x42; 20]), vec![0xab], U256::from(2));
+        tx.access_list = vec![
+            (Address::from([0x11; 20]), vec![H256::repeat_byte(0x01), H256::repeat_byte(0x02)]),
+            (Address::from([0x22; 20]), vec![]),
+        ];
+        tx.sign_with_secret(&secret).unwrap();
+        assert_eq!(tx.transaction_type(), TransactionType::Eip2930);
+
+        let raw = tx.rlp_bytes(IncludeSignature::WithSignature);
+        assert_eq!(raw[0], EIP2930_TRANSACTION_TYPE);
+        assert_eq!(Rlp::new(&raw[1..]).item_count().unwrap(), 11);
+        let decoded: Transaction = rlp::decode(&raw).unwrap();
+        assert_eq!(decoded.access_list, tx.access_list);
+        assert_eq!(decoded.gas_price(), tx.gas_price());
+        assert_eq!(decoded.max_priority_fee_per_gas, None);
+        assert_eq!(decoded.hash(), tx.hash());
+        assert_eq!(decoded.sender().unwrap(), secret_address(&secret));
+
+        // Each account and slot listed is charged up front under Berlin
+        let berlin = crate::evm::gas_schedule::GasSchedule::berlin();
+        let plain = Transaction { access_list: vec![], ..tx.clone() };
+        assert_eq!(tx.intrinsic_gas(&berlin), plain.intrinsic_gas(&berlin) + 2 * 2400 + 2 * 1900);
+    }
+
+    #[test]
+    fn test_pre_eip155_mainnet_transaction() {
+        // Keyless deployment of the deterministic deployment proxy, signed without a chain ID (v = 27)
+        let raw = hex::decode(concat!(
+            "f8a58085174876e800830186a08080b853604580600e600039806000f350fe7fffffffffffffffffffffffffffffffffffffffffffffff",
+            "ffffffffffffffffe03601600081602082378035828234f58015156039578182fd5b8082525050506014600cf31ba02222222222222222",
+            "222222222222222222222222222222222222222222222222a0222222222222222222222222222222222222222222222222222222222222",
+            "2222",
+        )).unwrap();
+
+        let sender = Address::from_str("3fab184622dc19b6109349b94811493bf2a45362").unwrap();
+        let tx = Transaction::from_bytes(&raw).unwrap();
+        assert_eq!(tx.chain_id, None);
+        assert_eq!(tx.hash(), crate::common::keccak256(&raw));
+        assert_eq!(tx.sender().unwrap(), sender);
+        assert_eq!(Transaction::sender_from_raw(&raw).unwrap(), (sender, tx.hash()));
+        assert_eq!(
+            crate::common::contract_address_from_sender(sender, tx.nonce()),
+            Address::from_str("4e59b44847b379578588920ca78fbf26c0b4956c").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_eip2930_with_empty_access_list_round_trips() {
+        let secret = [0x44u8; 32];
+        let mut tx = Transaction::new(U256::from(5), U256::from(1_000_000_000u64), U256::from(21000), Address::from([0x42; 20]), vec![], U256::zero());
+        tx.tx_type = Some(TransactionType::Eip2930);
+        tx.sign_with_secret(&secret).unwrap();
+        assert!(tx.access_list.is_empty());
+
+        let raw = tx.rlp_bytes(IncludeSignature::WithSignature);
+        assert_eq!(raw[0], EIP2930_TRANSACTION_TYPE);
+        let decoded = Transaction::from_bytes(&raw).unwrap();
+        assert_eq!(decoded.transaction_type(), TransactionType::Eip2930);
+        assert_eq!(decoded.rlp_bytes(IncludeSignature::WithSignature), raw);
+        assert_eq!(decoded.hash(), crate::common::keccak256(&raw));
+        assert_eq!(decoded.sender().unwrap(), secret_address(&secret));
+        assert_eq!(Transaction::sender_from_raw(&raw).unwrap(), (secret_address(&secret), decoded.hash()));
+    }
+
+    #[test]
+    fn test_eip1559_priority_fee_above_max_fee_rejected() {
+        let gwei = U256::from(1_000_000_000u64);
+        let tx = Transaction::new_eip1559(U256::zero(), gwei, gwei * 2, U256::from(21000), Address::from([0x42; 20]), vec![], U256::zero());
+        assert!(tx.validate(CheckTransaction::Cheap).is_err());
+    }
+}
@@ -112,9 +112,9 @@ pub struct MinWlResult {
 }
 
 /// Block signature structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Signature {
-    pub v: u8,
+    pub v: u64,
     pub r: H256,
     pub s: H256,
 }
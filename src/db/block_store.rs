@@ -0,0 +1,155 @@
+//! Block storage and canonical chain index
+
+use crate::core::block::Block;
+use crate::core::transaction::{IncludeSignature, Transaction};
+use crate::db::SledDatabase;
+use crate::rpc::methods::BlockProvider;
+use crate::{H256, Result, OlympusError};
+
+/// Tree of RLP-encoded blocks by hash
+const BLOCKS_TREE: &str = "blocks";
+/// Tree of canonical block hashes by height
+const BLOCK_NUMBERS_TREE: &str = "block_numbers";
+/// Tree of RLP-encoded transactions by hash
+const TRANSACTIONS_TREE: &str = "transactions";
+/// Tree of the state root after each block, by block hash
+const STATE_ROOTS_TREE: &str = "state_roots";
+/// Tree of chain metadata
+const META_TREE: &str = "meta";
+/// Key of the head block hash in the metadata tree
+const HEAD_KEY: &[u8] = b"head";
+/// Key of the genesis hash in the metadata tree
+const GENESIS_KEY: &[u8] = b"genesis";
+
+/// Typed block storage on top of a sled database
+pub struct BlockStore {
+    db: SledDatabase,
+}
+
+impl BlockStore {
+    /// Create a block store using `db`
+    pub fn new(db: SledDatabase) -> Self {
+        Self { db }
+    }
+
+    /// Store a block under its hash
+    pub fn put_block(&self, block: &Block) -> Result<()> {
+        self.db.insert(BLOCKS_TREE, block.hash().as_bytes(), &block.rlp_bytes())
+    }
+
+    /// Get a block by hash
+    pub fn get_block(&self, hash: H256) -> Result<Option<Block>> {
+        self.db.get(BLOCKS_TREE, hash.as_bytes())?
+            .map(|bytes| rlp::decode(&bytes).map_err(OlympusError::from))
+            .transpose()
+    }
+
+    /// Record `hash` as the canonical block at height `number`
+    pub fn put_block_number(&self, number: u64, hash: H256) -> Result<()> {
+        self.db.insert(BLOCK_NUMBERS_TREE, &number.to_be_bytes(), &encode_hash(hash)?)
+    }
+
+    /// Get the hash of the canonical block at a height
+    pub fn get_block_hash(&self, number: u64) -> Result<Option<H256>> {
+        self.db.get(BLOCK_NUMBERS_TREE, &number.to_be_bytes())?
+            .map(|bytes| decode_hash(&bytes))
+            .transpose()
+    }
+
+    /// Drop the canonical block at a height, e.g. past the head after a reorg
+    pub fn remove_block_number(&self, number: u64) -> Result<()> {
+        self.db.delete(BLOCK_NUMBERS_TREE, &number.to_be_bytes())
+    }
+
+    /// Record the state root produced by applying a block
+    pub fn put_state_root(&self, hash: H256, state_root: H256) -> Result<()> {
+        self.db.insert(STATE_ROOTS_TREE, hash.as_bytes(), &encode_hash(state_root)?)
+    }
+
+    /// Get the state root produced by applying a block
+    pub fn get_state_root(&self, hash: H256) -> Result<Option<H256>> {
+        self.db.get(STATE_ROOTS_TREE, hash.as_bytes())?
+            .map(|bytes| decode_hash(&bytes))
+            .transpose()
+    }
+
+    /// Get the canonical block at a height
+    pub fn get_block_by_number(&self, number: u64) -> Result<Option<Block>> {
+        match self.get_block_hash(number)? {
+            Some(hash) => self.get_block(hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a signed transaction under its hash
+    pub fn put_transaction(&self, transaction: &Transaction) -> Result<()> {
+        self.db.insert(TRANSACTIONS_TREE, transaction.hash().as_bytes(), &transaction.rlp_bytes(IncludeSignature::WithSignature))
+    }
+
+    /// Get a transaction by hash
+    pub fn get_transaction(&self, hash: H256) -> Result<Option<Transaction>> {
+        self.db.get(TRANSACTIONS_TREE, hash.as_bytes())?
+            .map(|bytes| Transaction::from_bytes(&bytes))
+            .transpose()
+    }
+
+    /// Get the hash of the head block
+    pub fn get_head(&self) -> Result<Option<H256>> {
+        self.db.get(META_TREE, HEAD_KEY)?
+            .map(|bytes| decode_hash(&bytes))
+            .transpose()
+    }
+
+    /// Set the head block
+    pub fn set_head(&self, hash: H256) -> Result<()> {
+        self.db.insert(META_TREE, HEAD_KEY, &encode_hash(hash)?)
+    }
+
+    /// Clear the head block, leaving the chain empty
+    pub fn remove_head(&self) -> Result<()> {
+        self.db.delete(META_TREE, HEAD_KEY)
+    }
+
+    /// Bind the store to the chain of a genesis hash
+    ///
+    /// An empty store records the hash; a store already holding another
+    /// chain's blocks is refused.
+    pub fn check_genesis(&self, genesis_hash: H256) -> Result<()> {
+        match self.db.get(META_TREE, GENESIS_KEY)? {
+            None => self.db.insert(META_TREE, GENESIS_KEY, &encode_hash(genesis_hash)?),
+            Some(bytes) => {
+                let stored = decode_hash(&bytes)?;
+                if stored != genesis_hash {
+                    return Err(OlympusError::Database(format!(
+                        "Block store holds the chain of genesis {:?}, not {:?}", stored, genesis_hash
+                    )));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl BlockProvider for BlockStore {
+    fn block(&self, hash: H256) -> Result<Option<Block>> {
+        self.get_block(hash)
+    }
+
+    fn block_hash(&self, number: u64) -> Result<Option<H256>> {
+        self.get_block_hash(number)
+    }
+
+    fn transaction(&self, hash: H256) -> Result<Option<Transaction>> {
+        self.get_transaction(hash)
+    }
+}
+
+/// Encode an index entry
+fn encode_hash(hash: H256) -> Result<Vec<u8>> {
+    bincode::serialize(&hash).map_err(|e| OlympusError::Serialization(e.to_string()))
+}
+
+/// Decode an index entry
+fn decode_hash(bytes: &[u8]) -> Result<H256> {
+    bincode::deserialize(bytes).map_err(|e| OlympusError::Serialization(e.to_string()))
+}
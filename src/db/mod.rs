@@ -1,5 +1,9 @@
 //! Database layer
 
+pub mod block_store;
 pub mod sled_db;
+pub mod tx_index;
 
+pub use block_store::*;
 pub use sled_db::*;
+pub use tx_index::*;
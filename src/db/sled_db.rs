@@ -1,9 +1,32 @@
 //! Sled database implementation
 
 use crate::{Result, OlympusError};
+use sled::transaction::{ConflictableTransactionError, TransactionError, Transactional};
 use sled::{Db, Tree};
+use std::collections::BTreeMap;
 
-/// Sled database wrapper
+/// Write to a named tree, applied as part of a batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOp {
+    /// Insert a key-value pair
+    Insert { tree: String, key: Vec<u8>, value: Vec<u8> },
+    /// Delete a key
+    Delete { tree: String, key: Vec<u8> },
+    /// Abort the batch unless the key currently holds `value`, or is absent for `None`
+    Expect { tree: String, key: Vec<u8>, value: Option<Vec<u8>> },
+}
+
+impl BatchOp {
+    /// Name of the tree the operation targets
+    fn tree(&self) -> &str {
+        match self {
+            BatchOp::Insert { tree, .. } | BatchOp::Delete { tree, .. } | BatchOp::Expect { tree, .. } => tree,
+        }
+    }
+}
+
+/// Sled database wrapper, sharing the underlying database when cloned
+#[derive(Clone)]
 pub struct SledDatabase {
     db: Db,
 }
@@ -17,6 +40,16 @@ impl SledDatabase {
         Ok(Self { db })
     }
 
+    /// Create a database deleted when dropped
+    pub fn temporary() -> Result<Self> {
+        let db = sled::Config::new()
+            .temporary(true)
+            .open()
+            .map_err(|e| OlympusError::Database(e.to_string()))?;
+
+        Ok(Self { db })
+    }
+
     /// Get tree by name
     pub fn tree(&self, name: &str) -> Result<Tree> {
         self.db.open_tree(name)
@@ -39,4 +72,75 @@ impl SledDatabase {
         
         Ok(result.map(|v| v.to_vec()))
     }
+
+    /// Delete a key, doing nothing if it is absent
+    pub fn delete(&self, tree: &str, key: &[u8]) -> Result<()> {
+        let tree = self.tree(tree)?;
+        tree.remove(key)
+            .map_err(|e| OlympusError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Get all key-value pairs whose key starts with `prefix`, in key order
+    pub fn scan_prefix(&self, tree: &str, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        collect_entries(self.tree(tree)?.scan_prefix(prefix))
+    }
+
+    /// Get all key-value pairs with keys from `start` up to but excluding `end`, in key order
+    pub fn range(&self, tree: &str, start: &[u8], end: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        collect_entries(self.tree(tree)?.range(start..end))
+    }
+
+    /// Apply operations across trees atomically, in order
+    ///
+    /// Either every operation takes effect or, on failure, none does.
+    pub fn apply_batch(&self, ops: Vec<BatchOp>) -> Result<()> {
+        let mut tree_index = BTreeMap::new();
+        for op in &ops {
+            let next = tree_index.len();
+            tree_index.entry(op.tree().to_string()).or_insert(next);
+        }
+        let mut trees = vec![None; tree_index.len()];
+        for (name, index) in &tree_index {
+            trees[*index] = Some(self.tree(name)?);
+        }
+        let trees: Vec<Tree> = trees.into_iter().flatten().collect();
+
+        let result: std::result::Result<(), TransactionError<String>> = trees.as_slice().transaction(|views| {
+            for op in &ops {
+                let view = &views[tree_index[op.tree()]];
+                match op {
+                    BatchOp::Insert { key, value, .. } => {
+                        view.insert(key.as_slice(), value.as_slice())?;
+                    }
+                    BatchOp::Delete { key, .. } => {
+                        view.remove(key.as_slice())?;
+                    }
+                    BatchOp::Expect { tree, key, value } => {
+                        let current = view.get(key.as_slice())?;
+                        if current.as_deref() != value.as_deref() {
+                            return Err(ConflictableTransactionError::Abort(
+                                format!("Unexpected value for key {} in tree {}", hex::encode(key), tree)
+                            ));
+                        }
+                    }
+                }
+            }
+            Ok(())
+        });
+
+        result.map_err(|e| match e {
+            TransactionError::Abort(message) => OlympusError::Database(message),
+            TransactionError::Storage(e) => OlympusError::Database(e.to_string()),
+        })
+    }
+}
+
+/// Collect the entries of a tree iterator
+fn collect_entries(entries: sled::Iter) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    entries
+        .map(|entry| entry
+            .map(|(key, value)| (key.to_vec(), value.to_vec()))
+            .map_err(|e| OlympusError::Database(e.to_string())))
+        .collect()
 }
@@ -0,0 +1,70 @@
+//! Transaction lookup by hash
+
+use crate::core::transaction::{IncludeSignature, LocalizedTransaction, Transaction};
+use crate::db::SledDatabase;
+use crate::{H256, Result, OlympusError};
+use serde::{Deserialize, Serialize};
+
+/// Tree of index entries by transaction hash
+const TX_INDEX_TREE: &str = "tx_index";
+
+/// Stored location and encoding of a transaction
+#[derive(Serialize, Deserialize)]
+struct IndexEntry {
+    /// Block containing the transaction
+    block_hash: H256,
+    /// Height of the block
+    block_number: u64,
+    /// Position of the transaction in the block
+    transaction_index: u32,
+    /// Signed RLP of the transaction
+    transaction: Vec<u8>,
+}
+
+/// Index from transaction hash to the block including it
+pub struct TxIndex {
+    db: SledDatabase,
+}
+
+impl TxIndex {
+    /// Create a transaction index using `db`
+    pub fn new(db: SledDatabase) -> Self {
+        Self { db }
+    }
+
+    /// Index the transactions of a block
+    ///
+    /// Entries are keyed by transaction hash, so indexing a block again
+    /// overwrites its entries with the same values.
+    pub fn index_block(&self, block_hash: H256, block_number: u64, txs: &[Transaction]) -> Result<()> {
+        let mut batch = sled::Batch::default();
+        for (index, transaction) in txs.iter().enumerate() {
+            let entry = IndexEntry {
+                block_hash,
+                block_number,
+                transaction_index: index as u32,
+                transaction: transaction.rlp_bytes(IncludeSignature::WithSignature),
+            };
+            let value = bincode::serialize(&entry).map_err(|e| OlympusError::Serialization(e.to_string()))?;
+            batch.insert(transaction.hash().as_bytes(), value);
+        }
+
+        self.db.tree(TX_INDEX_TREE)?
+            .apply_batch(batch)
+            .map_err(|e| OlympusError::Database(e.to_string()))
+    }
+
+    /// Get an indexed transaction with its block location
+    pub fn get(&self, tx_hash: H256) -> Result<Option<LocalizedTransaction>> {
+        let Some(bytes) = self.db.get(TX_INDEX_TREE, tx_hash.as_bytes())? else {
+            return Ok(None);
+        };
+        let entry: IndexEntry = bincode::deserialize(&bytes).map_err(|e| OlympusError::Serialization(e.to_string()))?;
+        Ok(Some(LocalizedTransaction::new(
+            Transaction::from_bytes(&entry.transaction)?,
+            entry.block_hash,
+            entry.transaction_index,
+            entry.block_number,
+        )))
+    }
+}
@@ -5,7 +5,7 @@ use rand::rngs::StdRng;
 use crate::{Address, H256, U256};
 use crate::core::transaction::Transaction;
 use crate::core::block::Block;
-use crate::evm::{Executive, create_precompiled_registry};
+use crate::evm::{Executive, MemoryState, ParallelExecutor, State, TransactionExecutionContext, TransactionExecutor, create_precompiled_registry};
 use crate::core::types::Signature;
 
 #[derive(Debug, Clone)]
@@ -71,6 +71,12 @@ impl DynamicTestGenerator {
         }
     }
 
+    fn random_h256(&mut self) -> H256 {
+        let mut bytes = [0u8; 32];
+        self.rng.fill(&mut bytes);
+        H256::from(bytes)
+    }
+
     pub fn generate_addresses(&mut self, count: usize) -> Vec<Address> {
         let mut addresses = Vec::new();
         for _ in 0..count {
@@ -122,8 +128,8 @@ impl DynamicTestGenerator {
         for _ in 0..count {
             let size = self.rng.gen_range(self.config.data_size_range.0..=self.config.data_size_range.1);
             let mut payload = vec![0u8; size];
-            for i in 0..size {
-                payload[i] = self.rng.gen();
+            for byte in payload.iter_mut() {
+                *byte = self.rng.gen();
             }
             payloads.push(payload);
         }
@@ -158,19 +164,19 @@ impl DynamicTestGenerator {
         for i in 0..self.config.block_count {
             let block = Block::new(
                 addresses[self.config.transaction_count + i],
-                H256::random(),
-                vec![H256::random()],
-                vec![H256::random()],
-                vec![H256::random()],
-                H256::random(),
-                H256::random(),
-                H256::random(),
+                self.random_h256(),
+                vec![self.random_h256()],
+                vec![self.random_h256()],
+                vec![self.random_h256()],
+                self.random_h256(),
+                self.random_h256(),
+                self.random_h256(),
                 timestamps[i],
                 U256::from(21000),
                 Signature { 
                     v: 27, 
-                    r: H256::random(), 
-                    s: H256::random() 
+                    r: self.random_h256(), 
+                    s: self.random_h256() 
                 },
             );
             blocks.push(block);
@@ -189,22 +195,48 @@ impl DynamicTestGenerator {
     }
 }
 
+/// Benchmarks run by the suite; each one draws from its own RNG stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchmarkId {
+    TransactionCreation = 1,
+    BlockHashing = 2,
+    PrecompiledContracts = 3,
+    EvmExecution = 4,
+    MemoryUsage = 5,
+    SignatureVerification = 6,
+    Consensus = 7,
+    ParallelExecution = 8,
+}
+
 pub struct DynamicBenchmarkSuite {
     config: TestConfig,
-    generator: DynamicTestGenerator,
+    base_seed: u64,
 }
 
 impl DynamicBenchmarkSuite {
     pub fn new(config: TestConfig) -> Self {
-        let generator = DynamicTestGenerator::new(config.clone());
-        Self { config, generator }
+        let base_seed = config.random_seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        });
+        Self { config, base_seed }
+    }
+
+    /// Build the generator for a benchmark, seeded with `base_seed ^ benchmark_id`
+    /// so its data does not depend on which other benchmarks ran before it
+    pub fn sub_generator(&self, benchmark: BenchmarkId) -> DynamicTestGenerator {
+        let mut config = self.config.clone();
+        config.random_seed = Some(self.base_seed ^ benchmark as u64);
+        DynamicTestGenerator::new(config)
     }
 
     pub fn run_transaction_creation_benchmark(&mut self) -> HashMap<String, f64> {
         let mut results = HashMap::new();
         let start = std::time::Instant::now();
         
-        let test_data = self.generator.generate_test_data();
+        let test_data = self.sub_generator(BenchmarkId::TransactionCreation).generate_test_data();
         let transactions = test_data.transactions;
         
         let duration = start.elapsed();
@@ -221,7 +253,7 @@ impl DynamicBenchmarkSuite {
         let mut results = HashMap::new();
         let start = std::time::Instant::now();
         
-        let test_data = self.generator.generate_test_data();
+        let test_data = self.sub_generator(BenchmarkId::BlockHashing).generate_test_data();
         let blocks = test_data.blocks;
         
         // Calculate hashes
@@ -249,7 +281,7 @@ impl DynamicBenchmarkSuite {
         let sha256_addr = Address::from([0x02; 20]);
         let sha256_contract = registry.get(&sha256_addr).unwrap();
         
-        let test_data = self.generator.generate_test_data();
+        let test_data = self.sub_generator(BenchmarkId::PrecompiledContracts).generate_test_data();
         let mut success_count = 0;
         
         for payload in test_data.data_payloads.iter().take(100) {
@@ -273,14 +305,14 @@ impl DynamicBenchmarkSuite {
         let start = std::time::Instant::now();
         
         let mut executive = Executive::new();
-        let test_data = self.generator.generate_test_data();
+        let test_data = self.sub_generator(BenchmarkId::EvmExecution).generate_test_data();
         let mut success_count = 0;
         
         for tx in test_data.transactions.iter().take(100) {
-            if executive.initialize(tx, U256::from(1), U256::from(test_data.timestamps[0])).is_ok() {
-                if executive.execute(tx).is_ok() {
-                    success_count += 1;
-                }
+            if executive.initialize(tx, U256::from(1), U256::from(test_data.timestamps[0])).is_ok()
+                && executive.execute(tx).is_ok()
+            {
+                success_count += 1;
             }
         }
         
@@ -299,7 +331,7 @@ impl DynamicBenchmarkSuite {
         let mut results = HashMap::new();
         let start = std::time::Instant::now();
         
-        let test_data = self.generator.generate_test_data();
+        let test_data = self.sub_generator(BenchmarkId::MemoryUsage).generate_test_data();
         let transactions = test_data.transactions;
         let blocks = test_data.blocks;
         
@@ -324,7 +356,7 @@ impl DynamicBenchmarkSuite {
         let mut results = HashMap::new();
         let start = std::time::Instant::now();
         
-        let test_data = self.generator.generate_test_data();
+        let test_data = self.sub_generator(BenchmarkId::SignatureVerification).generate_test_data();
         let mut valid_count = 0;
         
         for tx in test_data.transactions.iter().take(100) {
@@ -350,7 +382,7 @@ impl DynamicBenchmarkSuite {
         let mut results = HashMap::new();
         let start = std::time::Instant::now();
         
-        let test_data = self.generator.generate_test_data();
+        let test_data = self.sub_generator(BenchmarkId::Consensus).generate_test_data();
         let blocks = test_data.blocks;
         
         // Simulate consensus validation
@@ -369,6 +401,72 @@ impl DynamicBenchmarkSuite {
         results
     }
 
+    pub fn run_parallel_execution_benchmark(&mut self) -> HashMap<String, f64> {
+        let mut results = HashMap::new();
+        
+        let test_data = self.sub_generator(BenchmarkId::ParallelExecution).generate_test_data();
+        let mut transactions = Vec::new();
+        let mut state = MemoryState::new();
+        
+        // Independent senders paying distinct recipients, so the block parallelises fully
+        for (i, (recipient, payload)) in test_data.addresses.iter().zip(&test_data.data_payloads).take(100).enumerate() {
+            let mut secret = [0x01; 32];
+            secret[24..].copy_from_slice(&(i as u64).to_be_bytes());
+            let mut tx = Transaction::new(
+                U256::zero(),
+                U256::from(2_000_000_000u64),
+                U256::from(100_000),
+                *recipient,
+                payload.clone(),
+                U256::zero(),
+            );
+            if tx.sign_with_secret(&secret).is_ok() {
+                state.set_balance(tx.from(), U256::from(10u64).pow(U256::from(20)));
+                transactions.push(tx);
+            }
+        }
+        
+        let coinbase = Address::repeat_byte(0xcb);
+        let context = TransactionExecutionContext {
+            coinbase,
+            ..Default::default()
+        };
+        let mut sequential_state = MemoryState::new();
+        for tx in &transactions {
+            sequential_state.set_balance(tx.from(), state.get_balance(tx.from()));
+        }
+        let start = std::time::Instant::now();
+        let mut sequential = TransactionExecutor::new(Box::new(sequential_state), context.clone());
+        let sequential_results = sequential.execute_block_transactions(transactions.clone()).unwrap_or_default();
+        let sequential_duration = start.elapsed();
+        
+        let start = std::time::Instant::now();
+        let parallel = ParallelExecutor::new_default(context)
+            .execute_block(&mut state, &transactions);
+        let parallel_duration = start.elapsed();
+        
+        let success_count = parallel.results.iter().filter(|r| r.success).count();
+        let matching = parallel.results.iter().zip(&sequential_results)
+            .filter(|(a, b)| a.success == b.success && a.gas_used == b.gas_used)
+            .count();
+        let coinbase_balance = state.get_balance(coinbase);
+        let coinbase_matches = !coinbase_balance.is_zero() && coinbase_balance == sequential.state().get_balance(coinbase);
+        
+        results.insert("transaction_count".to_string(), transactions.len() as f64);
+        results.insert("success_count".to_string(), success_count as f64);
+        results.insert("matching_results".to_string(), matching as f64);
+        results.insert("reexecuted_count".to_string(), parallel.reexecuted.len() as f64);
+        results.insert("coinbase_matches".to_string(), if coinbase_matches { 1.0 } else { 0.0 });
+        results.insert("sequential_time_ms".to_string(), sequential_duration.as_millis() as f64);
+        results.insert("execution_time_ms".to_string(), parallel_duration.as_millis() as f64);
+        results.insert("speedup".to_string(),
+                      sequential_duration.as_secs_f64() / parallel_duration.as_secs_f64().max(f64::EPSILON));
+        results.insert("transactions_per_second".to_string(),
+                      transactions.len() as f64 / parallel_duration.as_secs_f64().max(f64::EPSILON));
+        
+        results
+    }
+
     pub fn run_all_benchmarks(&mut self) -> HashMap<String, HashMap<String, f64>> {
         let mut all_results = HashMap::new();
         
@@ -379,6 +477,7 @@ impl DynamicBenchmarkSuite {
         all_results.insert("memory_usage".to_string(), self.run_memory_usage_benchmark());
         all_results.insert("signature_verification".to_string(), self.run_signature_verification_benchmark());
         all_results.insert("consensus".to_string(), self.run_consensus_benchmark());
+        all_results.insert("parallel_execution".to_string(), self.run_parallel_execution_benchmark());
         
         all_results
     }
@@ -424,5 +523,55 @@ mod tests {
         assert!(results.contains_key("memory_usage"));
         assert!(results.contains_key("signature_verification"));
         assert!(results.contains_key("consensus"));
+        assert!(results.contains_key("parallel_execution"));
+    }
+
+    #[test]
+    fn test_parallel_execution_benchmark_matches_sequential() {
+        let config = TestConfig {
+            transaction_count: 50,
+            block_count: 5,
+            random_seed: Some(42),
+            ..Default::default()
+        };
+
+        let mut suite = DynamicBenchmarkSuite::new(config);
+        let results = suite.run_parallel_execution_benchmark();
+
+        assert_eq!(results["transaction_count"], 50.0);
+        assert_eq!(results["success_count"], 50.0);
+        assert_eq!(results["matching_results"], 50.0);
+        assert_eq!(results["reexecuted_count"], 0.0);
+        assert_eq!(results["coinbase_matches"], 1.0);
+    }
+
+    #[test]
+    fn test_benchmark_data_independent_of_run_order() {
+        let config = TestConfig {
+            transaction_count: 20,
+            block_count: 5,
+            random_seed: Some(42),
+            ..Default::default()
+        };
+
+        let mut alone = DynamicBenchmarkSuite::new(config.clone());
+        let alone_data = alone.sub_generator(BenchmarkId::EvmExecution).generate_test_data();
+        let alone_results = alone.run_evm_execution_benchmark();
+
+        let mut all = DynamicBenchmarkSuite::new(config);
+        let all_results = all.run_all_benchmarks();
+        let all_data = all.sub_generator(BenchmarkId::EvmExecution).generate_test_data();
+
+        let hashes = |data: &DynamicTestData| -> Vec<H256> {
+            data.transactions.iter().map(|tx| tx.hash())
+                .chain(data.blocks.iter().map(|block| block.hash()))
+                .collect()
+        };
+        assert_eq!(hashes(&alone_data), hashes(&all_data));
+        assert_eq!(alone_results["success_count"], all_results["evm_execution"]["success_count"]);
+
+        // Different benchmarks draw different data
+        let other_data = all.sub_generator(BenchmarkId::Consensus).generate_test_data();
+        assert_ne!(hashes(&alone_data), hashes(&other_data));
     }
 }
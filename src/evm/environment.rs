@@ -1,8 +1,9 @@
 //! Enhanced EVM execution environment
 
 use crate::{Address, H256, U256, Result, OlympusError};
+use crate::evm::gas_schedule::{GasSchedule, Hardfork};
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// EVM execution environment information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,10 +22,56 @@ pub struct EvmEnv {
     pub coinbase: Address,
     /// Difficulty (for PoW chains)
     pub difficulty: U256,
+    /// Block randomness (PREVRANDAO)
+    pub prevrandao: H256,
     /// Chain ID
     pub chain_id: u64,
+    /// Hardfork activation heights, in ascending order
+    #[serde(default = "default_hardforks")]
+    pub hardforks: Vec<(u64, Hardfork)>,
 }
 
+impl EvmEnv {
+    /// Hardfork in effect at block `number`
+    ///
+    /// Blocks before the first activation run under Frontier.
+    pub fn fork_for_block(&self, number: u64) -> Hardfork {
+        self.hardforks.iter()
+            .filter(|(height, _)| *height <= number)
+            .max_by_key(|(height, _)| *height)
+            .map_or(Hardfork::Frontier, |(_, fork)| *fork)
+    }
+
+    /// Replace the hardfork activation table
+    ///
+    /// The table is sorted by height; two forks at the same height, or a fork
+    /// activating after a later fork, are rejected.
+    pub fn set_hardforks(&mut self, mut hardforks: Vec<(u64, Hardfork)>) -> Result<()> {
+        hardforks.sort();
+        for pair in hardforks.windows(2) {
+            let ((height, fork), (next_height, next_fork)) = (pair[0], pair[1]);
+            if height == next_height {
+                return Err(OlympusError::EvmExecution(format!("Duplicate hardfork activation height {}", height)));
+            }
+            if fork >= next_fork {
+                return Err(OlympusError::EvmExecution(format!(
+                    "Hardfork {:?} at height {} activates after {:?} at height {}", next_fork, next_height, fork, height
+                )));
+            }
+        }
+        self.hardforks = hardforks;
+        Ok(())
+    }
+}
+
+/// Hardfork schedule activating the latest fork from genesis
+fn default_hardforks() -> Vec<(u64, Hardfork)> {
+    vec![(0, Hardfork::Prague)]
+}
+
+/// Largest share of gas used that refunds may cover, as a divisor (EIP-3529)
+pub const MAX_REFUND_QUOTIENT: u64 = 5;
+
 /// Gas management for EVM execution
 #[derive(Debug, Clone)]
 pub struct GasManager {
@@ -36,19 +83,28 @@ pub struct GasManager {
     pub gas_refunded: U256,
     /// Gas price
     pub gas_price: U256,
+    /// Largest share of gas used that refunds may cover, as a divisor
+    pub refund_quotient: u64,
 }
 
 impl GasManager {
-    /// Create new gas manager
+    /// Create new gas manager, capping refunds per EIP-3529
     pub fn new(gas_limit: U256, gas_price: U256) -> Self {
         Self {
             gas_limit,
             gas_used: U256::zero(),
             gas_refunded: U256::zero(),
             gas_price,
+            refund_quotient: MAX_REFUND_QUOTIENT,
         }
     }
 
+    /// Cap refunds at `gas_used / refund_quotient` instead; a quotient of 0 disables refunds
+    pub fn with_refund_quotient(mut self, refund_quotient: u64) -> Self {
+        self.refund_quotient = refund_quotient;
+        self
+    }
+
     /// Consume gas
     pub fn consume_gas(&mut self, amount: U256) -> Result<()> {
         if self.gas_used + amount > self.gas_limit {
@@ -63,6 +119,16 @@ impl GasManager {
         self.gas_refunded += amount;
     }
 
+    /// Gas charged once refunds are applied
+    ///
+    /// Refunds are capped at a share of the gas used: half before London and
+    /// a fifth after, per EIP-3529. A quotient of 0 allows no refund.
+    pub fn final_gas_used(&self) -> U256 {
+        let cap = self.gas_used.checked_div(U256::from(self.refund_quotient)).unwrap_or_default();
+        let refund = self.gas_refunded.min(cap);
+        self.gas_used - refund
+    }
+
     /// Get remaining gas
     pub fn remaining_gas(&self) -> U256 {
         if self.gas_used > self.gas_limit {
@@ -93,6 +159,14 @@ pub struct ExecutionContext {
     pub call_stack: Vec<CallFrame>,
     /// Current call frame
     pub current_frame: Option<CallFrame>,
+    /// Addresses accessed in the current transaction
+    pub accessed_addresses: HashSet<Address>,
+    /// Storage slots accessed in the current transaction
+    pub accessed_storage_keys: HashSet<(Address, H256)>,
+    /// Hardfork of the block being executed
+    pub hardfork: Hardfork,
+    /// Gas schedule in effect
+    pub gas_schedule: GasSchedule,
 }
 
 /// Call frame for EVM execution
@@ -117,6 +191,7 @@ pub struct CallFrame {
 impl ExecutionContext {
     /// Create new execution context
     pub fn new(env: EvmEnv, gas_limit: U256, gas_price: U256) -> Self {
+        let hardfork = env.fork_for_block(env.block_number.low_u64());
         Self {
             depth: 0,
             max_depth: 1024, // Ethereum's max call depth
@@ -124,9 +199,45 @@ impl ExecutionContext {
             gas_manager: GasManager::new(gas_limit, gas_price),
             call_stack: Vec::new(),
             current_frame: None,
+            accessed_addresses: HashSet::new(),
+            accessed_storage_keys: HashSet::new(),
+            hardfork,
+            gas_schedule: GasSchedule::new(hardfork),
         }
     }
 
+    /// Set the gas schedule in effect
+    pub fn set_gas_schedule(&mut self, gas_schedule: GasSchedule) {
+        self.gas_schedule = gas_schedule;
+    }
+
+    /// Mark an address as warm
+    pub fn warm_address(&mut self, address: Address) {
+        self.accessed_addresses.insert(address);
+    }
+
+    /// Mark a storage slot (and its account) as warm
+    pub fn warm_storage_key(&mut self, address: Address, key: H256) {
+        self.accessed_addresses.insert(address);
+        self.accessed_storage_keys.insert((address, key));
+    }
+
+    /// Check if an address is warm
+    pub fn is_warm_address(&self, address: Address) -> bool {
+        self.accessed_addresses.contains(&address)
+    }
+
+    /// Check if a storage slot is warm
+    pub fn is_warm_storage_key(&self, address: Address, key: H256) -> bool {
+        self.accessed_storage_keys.contains(&(address, key))
+    }
+
+    /// Clear the access set at the start of a transaction
+    pub fn clear_access_set(&mut self) {
+        self.accessed_addresses.clear();
+        self.accessed_storage_keys.clear();
+    }
+
     /// Push new call frame
     pub fn push_call_frame(&mut self, frame: CallFrame) -> Result<()> {
         if self.depth >= self.max_depth {
@@ -155,58 +266,19 @@ impl ExecutionContext {
         self.current_frame.as_ref()
     }
 
-    /// Update environment
+    /// Update environment, switching to the hardfork of its block and its gas schedule
     pub fn update_env(&mut self, env: EvmEnv) {
+        self.hardfork = env.fork_for_block(env.block_number.low_u64());
+        self.gas_schedule = GasSchedule::new(self.hardfork);
         self.env = env;
     }
 
-    /// Get gas cost for operation
+    /// Get the static gas cost for operation
+    ///
+    /// Execution is metered by REVM under the spec of the active hardfork;
+    /// this is the schedule's listed price, ignoring access warmth.
     pub fn get_gas_cost(&self, operation: &str) -> U256 {
-        match operation {
-            "ADD" | "SUB" | "MUL" | "DIV" | "MOD" | "ADDMOD" | "MULMOD" => U256::from(3),
-            "LT" | "GT" | "SLT" | "SGT" | "EQ" => U256::from(3),
-            "AND" | "OR" | "XOR" => U256::from(3),
-            "NOT" | "BYTE" => U256::from(3),
-            "SHA3" => U256::from(30),
-            "SLOAD" => U256::from(200),
-            "SSTORE" => U256::from(20000),
-            "BALANCE" => U256::from(400),
-            "BLOCKHASH" => U256::from(20),
-            "COINBASE" | "TIMESTAMP" | "NUMBER" | "DIFFICULTY" | "GASLIMIT" => U256::from(2),
-            "POP" => U256::from(2),
-            "MLOAD" => U256::from(3),
-            "MSTORE" => U256::from(3),
-            "MSTORE8" => U256::from(3),
-            "JUMP" => U256::from(8),
-            "JUMPI" => U256::from(10),
-            "PC" => U256::from(2),
-            "MSIZE" => U256::from(2),
-            "GAS" => U256::from(2),
-            "JUMPDEST" => U256::from(1),
-            "PUSH1" | "PUSH2" | "PUSH3" | "PUSH4" | "PUSH5" | "PUSH6" | "PUSH7" | "PUSH8" => U256::from(3),
-            "PUSH9" | "PUSH10" | "PUSH11" | "PUSH12" | "PUSH13" | "PUSH14" | "PUSH15" | "PUSH16" => U256::from(3),
-            "PUSH17" | "PUSH18" | "PUSH19" | "PUSH20" | "PUSH21" | "PUSH22" | "PUSH23" | "PUSH24" => U256::from(3),
-            "PUSH25" | "PUSH26" | "PUSH27" | "PUSH28" | "PUSH29" | "PUSH30" | "PUSH31" | "PUSH32" => U256::from(3),
-            "DUP1" | "DUP2" | "DUP3" | "DUP4" | "DUP5" | "DUP6" | "DUP7" | "DUP8" => U256::from(3),
-            "DUP9" | "DUP10" | "DUP11" | "DUP12" | "DUP13" | "DUP14" | "DUP15" | "DUP16" => U256::from(3),
-            "SWAP1" | "SWAP2" | "SWAP3" | "SWAP4" | "SWAP5" | "SWAP6" | "SWAP7" | "SWAP8" => U256::from(3),
-            "SWAP9" | "SWAP10" | "SWAP11" | "SWAP12" | "SWAP13" | "SWAP14" | "SWAP15" | "SWAP16" => U256::from(3),
-            "LOG0" => U256::from(375),
-            "LOG1" => U256::from(750),
-            "LOG2" => U256::from(1125),
-            "LOG3" => U256::from(1500),
-            "LOG4" => U256::from(1875),
-            "CREATE" => U256::from(32000),
-            "CALL" => U256::from(700),
-            "CALLCODE" => U256::from(700),
-            "RETURN" => U256::from(0),
-            "DELEGATECALL" => U256::from(700),
-            "CREATE2" => U256::from(32000),
-            "STATICCALL" => U256::from(700),
-            "REVERT" => U256::from(0),
-            "SELFDESTRUCT" => U256::from(5000),
-            _ => U256::from(1), // Default gas cost
-        }
+        U256::from(self.gas_schedule.opcode_cost(operation))
     }
 }
 
@@ -220,7 +292,9 @@ impl Default for EvmEnv {
             base_fee: U256::from(1_000_000_000), // 1 gwei
             coinbase: Address::zero(),
             difficulty: U256::zero(),
-            chain_id: 1,
+            prevrandao: H256::zero(),
+            chain_id: crate::core::types::CHAIN_ID,
+            hardforks: default_hardforks(),
         }
     }
 }
@@ -273,3 +347,60 @@ pub struct TraceEntry {
     /// Storage changes
     pub storage: HashMap<H256, H256>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fork_for_block_follows_activation_table() {
+        let env = EvmEnv {
+            hardforks: vec![(0, Hardfork::Berlin), (100, Hardfork::London), (200, Hardfork::Shanghai)],
+            ..EvmEnv::default()
+        };
+        assert_eq!(env.fork_for_block(0), Hardfork::Berlin);
+        assert_eq!(env.fork_for_block(99), Hardfork::Berlin);
+        assert_eq!(env.fork_for_block(100), Hardfork::London);
+        assert_eq!(env.fork_for_block(1_000), Hardfork::Shanghai);
+
+        let late = EvmEnv { hardforks: vec![(10, Hardfork::Cancun)], ..EvmEnv::default() };
+        assert_eq!(late.fork_for_block(9), Hardfork::Frontier);
+    }
+
+    #[test]
+    fn test_set_hardforks_sorts_and_rejects_inconsistent_tables() {
+        let mut env = EvmEnv::default();
+        env.set_hardforks(vec![(200, Hardfork::Shanghai), (0, Hardfork::Berlin), (100, Hardfork::London)]).unwrap();
+        assert_eq!(env.hardforks, vec![(0, Hardfork::Berlin), (100, Hardfork::London), (200, Hardfork::Shanghai)]);
+
+        assert!(env.set_hardforks(vec![(0, Hardfork::Berlin), (0, Hardfork::London)]).is_err());
+        assert!(env.set_hardforks(vec![(0, Hardfork::London), (100, Hardfork::Berlin)]).is_err());
+        assert_eq!(env.fork_for_block(150), Hardfork::London);
+
+        let mut context = ExecutionContext::new(EvmEnv { block_number: U256::from(150), ..env }, U256::from(100_000), U256::one());
+        assert_eq!(context.hardfork, Hardfork::London);
+        assert_eq!(context.gas_schedule, GasSchedule::london());
+        context.update_env(EvmEnv { block_number: U256::from(200), ..context.env.clone() });
+        assert_eq!(context.hardfork, Hardfork::Shanghai);
+        assert_eq!(context.gas_schedule, GasSchedule::shanghai());
+    }
+
+    #[test]
+    fn test_final_gas_used_caps_refunds() {
+        let mut gas = GasManager::new(U256::from(100_000), U256::one());
+        gas.consume_gas(U256::from(50_000)).unwrap();
+        gas.refund_gas(U256::from(4_000));
+        assert_eq!(gas.final_gas_used(), U256::from(46_000));
+
+        // Refunds beyond a fifth of the gas used are dropped
+        gas.refund_gas(U256::from(20_000));
+        assert_eq!(gas.final_gas_used(), U256::from(40_000));
+
+        // Before London refunds may cover half
+        let pre_london = gas.clone().with_refund_quotient(GasSchedule::berlin().max_refund_quotient);
+        assert_eq!(pre_london.final_gas_used(), U256::from(26_000));
+
+        // A zero quotient from a hand-written schedule refunds nothing rather than panicking
+        assert_eq!(gas.with_refund_quotient(0).final_gas_used(), U256::from(50_000));
+    }
+}
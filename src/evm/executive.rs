@@ -1,19 +1,300 @@
 //! EVM Executive for transaction execution
 
-use crate::core::transaction::Transaction;
+use crate::core::transaction::{Transaction, TransactionType};
+use crate::core::types::CHAIN_ID;
 use crate::{Address, H256, U256, Result, OlympusError};
-use crate::evm::precompiled::{create_precompiled_registry, PrecompiledContract};
-use crate::evm::environment::{ExecutionContext, EvmEnv, GasManager, EnvironmentLogEntry};
+use crate::evm::environment::{ExecutionContext, EvmEnv, GasManager, EnvironmentLogEntry, TraceEntry};
+use crate::evm::gas_schedule::Hardfork;
+use crate::evm::state_override::StateOverride;
+use crate::evm::state::{MemoryState, State};
+use crate::evm::revm_database::{commit_to_state, BlockHashes, StateDatabase};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use revm::{
-    handler::{MainBuilder, MainContext, ExecuteEvm},
-    primitives::{U256 as RevmU256, Address as RevmAddress, TxKind, Bytes},
-    context::{Context, TxEnv, BlockEnv, CfgEnv, result::{ExecResultAndState, ExecutionResult}},
-    database::EmptyDB,
-    state::EvmState,
+    handler::{post_execution, EthFrame, EvmTr, EvmTrError, FrameResult, Handler, MainBuilder, MainContext, ExecuteEvm},
+    primitives::{U256 as RevmU256, Address as RevmAddress, B256, TxKind, Bytes, hardfork::SpecId},
+    context::{Context, ContextSetters, TxEnv, BlockEnv, CfgEnv, result::{EVMError, ExecResultAndState, ExecutionResult, HaltReason}},
+    context_interface::{transaction::{AccessList, AccessListItem}, ContextTr, JournalTr},
+    database::{CacheDB, DatabaseRef, EmptyDB, WrapDatabaseRef},
+    inspector::{Inspector, InspectorEvmTr, InspectorHandler},
+    bytecode::{opcode, OpCode},
+    interpreter::{interpreter::EthInterpreter, interpreter_types::Jumps, Interpreter, InstructionResult},
+    state::{AccountInfo, Bytecode, EvmState},
+    Database,
 };
 
+/// Most executions a gas estimation runs before settling on its upper bound
+pub const MAX_ESTIMATION_ITERATIONS: usize = 32;
+
+/// Interpreter steps a trace may take when no step limit is set
+pub const DEFAULT_TRACE_MAX_STEPS: u64 = 1_000_000;
+
+/// Most stack words a trace may capture across all of its entries
+pub const MAX_TRACE_STACK_WORDS: usize = 1 << 21;
+
+/// Selector of Solidity's `Error(string)` revert payload
+pub const REVERT_REASON_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+
+/// Decode the message of an `Error(string)` revert payload
+///
+/// Returns `None` for other payloads, such as custom error selectors.
+pub fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    let data = output.strip_prefix(&REVERT_REASON_SELECTOR)?;
+    let word = |at: usize| {
+        let value = U256::from_big_endian(data.get(at..at.checked_add(32)?)?);
+        (value <= U256::from(u32::MAX)).then(|| value.as_usize())
+    };
+    let offset = word(0)?;
+    let length = word(offset)?;
+    let start = offset + 32;
+    String::from_utf8(data.get(start..start + length)?.to_vec()).ok()
+}
+
+/// Outcome of an EVM execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExecutionStatus {
+    /// Executed successfully
+    Success,
+    /// Reverted by the contract
+    Reverted,
+    /// Ran out of gas
+    OutOfGas,
+    /// Halted by an exceptional condition other than gas
+    Halted,
+    /// Stopped by the step or time limit
+    TimedOut,
+    /// Emitted more logs than allowed
+    LogLimitExceeded,
+    /// Rejected before execution
+    Rejected,
+    /// Left out of the block because it would exceed the block gas limit
+    Skipped,
+}
+
+impl ExecutionStatus {
+    /// Error message of an unsuccessful status
+    pub fn error_message(&self) -> Option<&'static str> {
+        match self {
+            ExecutionStatus::Success => None,
+            ExecutionStatus::Reverted => Some("Execution reverted"),
+            ExecutionStatus::OutOfGas => Some("Out of gas"),
+            ExecutionStatus::Halted => Some("Execution halted"),
+            ExecutionStatus::TimedOut => Some("Execution timed out"),
+            ExecutionStatus::LogLimitExceeded => Some("Log limit exceeded"),
+            ExecutionStatus::Rejected => Some("Transaction rejected"),
+            ExecutionStatus::Skipped => Some("Block gas limit reached"),
+        }
+    }
+
+    /// Whether the status consumes all gas provided to the transaction
+    pub fn consumes_all_gas(&self) -> bool {
+        matches!(
+            self,
+            ExecutionStatus::OutOfGas | ExecutionStatus::Halted | ExecutionStatus::TimedOut | ExecutionStatus::LogLimitExceeded
+        )
+    }
+}
+
+/// Limits bounding a single EVM execution
+///
+/// Step and time limits are for RPC executions: `eth_call`, gas estimation
+/// and tracing. Block execution sets only the log limit and is otherwise
+/// bounded by gas, since a wall-clock limit would make nodes disagree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutionLimits {
+    /// Maximum number of interpreter steps, across all call frames
+    pub max_steps: Option<u64>,
+    /// Maximum wall-clock execution time
+    pub timeout: Option<Duration>,
+    /// Maximum number of LOG instructions, including ones in reverted frames
+    pub max_logs: Option<usize>,
+}
+
+impl ExecutionLimits {
+    /// Check if any limit is set
+    pub fn is_limited(&self) -> bool {
+        self.max_steps.is_some() || self.timeout.is_some() || self.max_logs.is_some()
+    }
+}
+
+/// Inspector halting execution once a step, time or log limit is exceeded
+struct StepLimiter {
+    /// Remaining interpreter steps
+    remaining_steps: Option<u64>,
+    /// Wall-clock deadline
+    deadline: Option<Instant>,
+    /// Remaining LOG instructions
+    remaining_logs: Option<usize>,
+    /// Status forced by the limit that was hit
+    exceeded: Option<ExecutionStatus>,
+}
+
+impl StepLimiter {
+    /// Create a limiter starting now
+    fn new(limits: ExecutionLimits) -> Self {
+        Self {
+            remaining_steps: limits.max_steps,
+            deadline: limits.timeout.map(|timeout| Instant::now() + timeout),
+            remaining_logs: limits.max_logs,
+            exceeded: None,
+        }
+    }
+}
+
+impl<CTX> Inspector<CTX> for StepLimiter {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        let steps_exhausted = match self.remaining_steps.as_mut() {
+            Some(0) => true,
+            Some(remaining) => {
+                *remaining -= 1;
+                false
+            }
+            None => false,
+        };
+
+        let logs_exhausted = match self.remaining_logs.as_mut() {
+            Some(remaining) if (opcode::LOG0..=opcode::LOG4).contains(&interp.bytecode.opcode()) => {
+                let exhausted = *remaining == 0;
+                *remaining = remaining.saturating_sub(1);
+                exhausted
+            }
+            _ => false,
+        };
+
+        if self.exceeded.is_none() {
+            if logs_exhausted {
+                self.exceeded = Some(ExecutionStatus::LogLimitExceeded);
+            } else if steps_exhausted || self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                self.exceeded = Some(ExecutionStatus::TimedOut);
+            }
+        }
+        if self.exceeded.is_some() {
+            // Halting like out-of-gas consumes all gas of the frame and reverts its changes
+            interp.halt(InstructionResult::OutOfGas);
+        }
+    }
+}
+
+/// Inspector recording a trace entry per interpreter step, within the execution limits
+///
+/// Steps default to `DEFAULT_TRACE_MAX_STEPS` and the captured stacks are
+/// capped at `MAX_TRACE_STACK_WORDS`; hitting either times the execution out.
+struct Tracer {
+    /// Limits enforced while tracing
+    limiter: StepLimiter,
+    /// Completed steps
+    entries: Vec<TraceEntry>,
+    /// Stack words captured so far
+    stack_words: usize,
+    /// Gas remaining before the step in progress
+    gas_before: u64,
+}
+
+impl Tracer {
+    /// Create a tracer enforcing `limits`
+    fn new(limits: ExecutionLimits) -> Self {
+        let limits = ExecutionLimits { max_steps: limits.max_steps.or(Some(DEFAULT_TRACE_MAX_STEPS)), ..limits };
+        Self {
+            limiter: StepLimiter::new(limits),
+            entries: Vec::new(),
+            stack_words: 0,
+            gas_before: 0,
+        }
+    }
+}
+
+impl<CTX> Inspector<CTX> for Tracer {
+    fn step(&mut self, interp: &mut Interpreter, context: &mut CTX) {
+        self.stack_words += interp.stack.len();
+        if self.stack_words > MAX_TRACE_STACK_WORDS {
+            self.limiter.exceeded.get_or_insert(ExecutionStatus::TimedOut);
+            interp.halt(InstructionResult::OutOfGas);
+            return;
+        }
+
+        let op = interp.bytecode.opcode();
+        let stack: Vec<U256> = interp.stack.data().iter().map(|value| U256::from_big_endian(&value.to_be_bytes::<32>())).collect();
+
+        // SSTORE takes the slot from the top of the stack and the value below it
+        let mut storage = HashMap::new();
+        if op == opcode::SSTORE && stack.len() >= 2 {
+            let word = |value: U256| {
+                let mut bytes = [0u8; 32];
+                value.to_big_endian(&mut bytes);
+                H256(bytes)
+            };
+            storage.insert(word(stack[stack.len() - 1]), word(stack[stack.len() - 2]));
+        }
+
+        self.gas_before = interp.gas.remaining();
+        self.entries.push(TraceEntry {
+            pc: interp.bytecode.pc(),
+            opcode: OpCode::name_by_op(op).to_string(),
+            gas_cost: U256::zero(),
+            stack,
+            memory: Vec::new(),
+            storage,
+        });
+        self.limiter.step(interp, context);
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut CTX) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.gas_cost = U256::from(self.gas_before.saturating_sub(interp.gas.remaining()));
+        }
+    }
+}
+
+/// Mainnet transaction handler that can leave the coinbase unpaid
+///
+/// With the reward deferred the coinbase is only loaded if the transaction
+/// itself touches it, so its fee can be credited later as a plain delta.
+struct FeeHandler<EVM, ERROR> {
+    /// Credit the coinbase with the priority fee
+    pay_coinbase: bool,
+    _phantom: PhantomData<(EVM, ERROR)>,
+}
+
+impl<EVM, ERROR> FeeHandler<EVM, ERROR> {
+    /// Create a handler paying the coinbase only if `pay_coinbase` is set
+    fn new(pay_coinbase: bool) -> Self {
+        Self { pay_coinbase, _phantom: PhantomData }
+    }
+}
+
+impl<EVM, ERROR> Handler for FeeHandler<EVM, ERROR>
+where
+    EVM: EvmTr<Context: ContextTr<Journal: JournalTr<State = EvmState>>, Frame = EthFrame<EthInterpreter>>,
+    ERROR: EvmTrError<EVM>,
+{
+    type Evm = EVM;
+    type Error = ERROR;
+    type HaltReason = HaltReason;
+
+    fn reward_beneficiary(&self, evm: &mut EVM, exec_result: &mut FrameResult) -> std::result::Result<(), ERROR> {
+        if !self.pay_coinbase {
+            return Ok(());
+        }
+        post_execution::reward_beneficiary(evm.ctx(), exec_result.gas()).map_err(From::from)
+    }
+}
+
+impl<EVM, ERROR> InspectorHandler for FeeHandler<EVM, ERROR>
+where
+    EVM: InspectorEvmTr<
+        Context: ContextTr<Journal: JournalTr<State = EvmState>>,
+        Frame = EthFrame<EthInterpreter>,
+        Inspector: Inspector<<EVM as EvmTr>::Context, EthInterpreter>,
+    >,
+    ERROR: EvmTrError<EVM>,
+{
+    type IT = EthInterpreter;
+}
+
 /// EVM execution result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EvmExecutionResult {
@@ -25,6 +306,8 @@ pub struct EvmExecutionResult {
     pub output: Vec<u8>,
     /// Success status
     pub success: bool,
+    /// Execution outcome
+    pub status: ExecutionStatus,
     /// Logs emitted
     pub logs: Vec<EnvironmentLogEntry>,
     /// Contract address (for contract creation)
@@ -37,15 +320,26 @@ pub struct EvmExecutionResult {
 pub struct Executive {
     /// Execution context
     context: ExecutionContext,
-    /// Precompiled contracts registry
-    precompiled_registry: HashMap<Address, Box<dyn PrecompiledContract>>,
     /// REVM context
     revm_context: Context<BlockEnv, TxEnv, CfgEnv, EmptyDB>,
+    /// Step and time limits
+    limits: ExecutionLimits,
+    /// Leave priority fees out of the state for the caller to credit
+    defer_coinbase_fee: bool,
+    /// State read and written by executions
+    state: Box<dyn State>,
+    /// Canonical block hashes served to BLOCKHASH
+    block_hashes: Option<Arc<dyn BlockHashes>>,
 }
 
 impl Executive {
-    /// Create a new EVM executive
+    /// Create a new EVM executive over an empty in-memory state
     pub fn new() -> Self {
+        Self::new_with_state(Box::new(MemoryState::new()))
+    }
+
+    /// Create a new EVM executive reading and writing `state`
+    pub fn new_with_state(state: Box<dyn State>) -> Self {
         let env = EvmEnv::default();
         let context = ExecutionContext::new(env, U256::from(30_000_000), U256::from(1_000_000_000));
         
@@ -54,11 +348,40 @@ impl Executive {
         
         Self {
             context,
-            precompiled_registry: create_precompiled_registry(),
             revm_context,
+            limits: ExecutionLimits::default(),
+            defer_coinbase_fee: false,
+            state,
+            block_hashes: None,
         }
     }
 
+    /// Set the canonical block hashes BLOCKHASH reads; without them it returns zero
+    pub fn set_block_hashes(&mut self, block_hashes: Arc<dyn BlockHashes>) {
+        self.block_hashes = Some(block_hashes);
+    }
+
+    /// View of the state as a REVM database for the current block
+    fn database(&self) -> StateDatabase<'_> {
+        StateDatabase::new(self.state.as_ref())
+            .with_block_hashes(self.block_hashes.as_deref(), self.context.env.block_number.low_u64())
+    }
+
+    /// Replace the state executions run against, returning the previous one
+    pub fn set_state(&mut self, state: Box<dyn State>) -> Box<dyn State> {
+        std::mem::replace(&mut self.state, state)
+    }
+
+    /// Get the state executions run against
+    pub fn state(&self) -> &dyn State {
+        self.state.as_ref()
+    }
+
+    /// Get the mutable state executions run against
+    pub fn state_mut(&mut self) -> &mut dyn State {
+        self.state.as_mut()
+    }
+
     /// Initialize executive with transaction and environment
     pub fn initialize(&mut self, transaction: &Transaction, block_number: U256, timestamp: U256) -> Result<()> {
         let env = EvmEnv {
@@ -66,158 +389,323 @@ impl Executive {
             timestamp,
             block_hash: H256::zero(),
             block_gas_limit: U256::from(30_000_000),
-            base_fee: U256::from(1_000_000_000),
-            coinbase: Address::zero(),
+            base_fee: self.context.env.base_fee,
+            coinbase: self.context.env.coinbase,
             difficulty: U256::zero(),
-            chain_id: 1,
+            prevrandao: self.context.env.prevrandao,
+            chain_id: CHAIN_ID,
+            hardforks: self.context.env.hardforks.clone(),
         };
         
         self.context.update_env(env);
-        self.context.gas_manager = GasManager::new(transaction.gas(), transaction.gas_price());
+        self.context.gas_manager = GasManager::new(transaction.gas(), transaction.gas_price())
+            .with_refund_quotient(self.context.gas_schedule.max_refund_quotient);
+        self.context.clear_access_set();
         Ok(())
     }
 
-    /// Execute a transaction
-    pub fn execute(&mut self, transaction: &Transaction) -> Result<EvmExecutionResult> {
-        // Check if this is a precompiled contract call
-        if self.precompiled_registry.contains_key(&transaction.receive_address) {
-            return self.execute_precompiled_contract(transaction);
-        }
+    /// Set the step and time limits of subsequent executions
+    pub fn set_execution_limits(&mut self, limits: ExecutionLimits) {
+        self.limits = limits;
+    }
 
-        // Execute regular transaction using REVM
-        self.execute_with_revm(transaction)
+    /// Set whether executions leave the coinbase's priority fee uncredited
+    pub fn set_defer_coinbase_fee(&mut self, defer: bool) {
+        self.defer_coinbase_fee = defer;
     }
 
-    /// Execute precompiled contract
-    fn execute_precompiled_contract(&mut self, transaction: &Transaction) -> Result<EvmExecutionResult> {
-        let gas_cost = self.precompiled_registry.get(&transaction.receive_address)
-            .unwrap()
-            .gas_cost(&transaction.data);
-        
-        // Check if we have enough gas
-        if self.context.gas_manager.remaining_gas() < gas_cost {
-            return Ok(EvmExecutionResult {
-                gas_used: self.context.gas_manager.gas_used,
-                gas_refunded: self.context.gas_manager.gas_refunded,
-                output: vec![],
-                success: false,
-                logs: vec![],
-                contract_address: None,
-                error: Some("Out of gas".to_string()),
-            });
+    /// Set the block randomness exposed to contracts as PREVRANDAO
+    pub fn set_prevrandao(&mut self, prevrandao: H256) {
+        self.context.env.prevrandao = prevrandao;
+    }
+
+    /// Set the account credited with the block's priority fees
+    pub fn set_coinbase(&mut self, coinbase: Address) {
+        self.context.env.coinbase = coinbase;
+    }
+
+    /// Set the base fee per gas of the block being executed
+    pub fn set_base_fee(&mut self, base_fee: U256) {
+        self.context.env.base_fee = base_fee;
+    }
+
+    /// Set the hardfork activation heights
+    ///
+    /// Fails, leaving the schedule unchanged, on duplicate heights or forks
+    /// out of order. The hardfork and gas schedule of the current block are
+    /// switched to match.
+    pub fn set_hardforks(&mut self, hardforks: Vec<(u64, Hardfork)>) -> Result<()> {
+        let mut env = self.context.env.clone();
+        env.set_hardforks(hardforks)?;
+        self.context.update_env(env);
+        Ok(())
+    }
+
+    /// REVM spec of the block being executed, matching the gas schedule in effect
+    fn spec_id(&self) -> SpecId {
+        match self.context.hardfork {
+            Hardfork::Frontier => SpecId::FRONTIER,
+            Hardfork::Homestead => SpecId::HOMESTEAD,
+            Hardfork::Byzantium => SpecId::BYZANTIUM,
+            Hardfork::Petersburg => SpecId::PETERSBURG,
+            Hardfork::Istanbul => SpecId::ISTANBUL,
+            Hardfork::Berlin => SpecId::BERLIN,
+            Hardfork::London => SpecId::LONDON,
+            Hardfork::Paris => SpecId::MERGE,
+            Hardfork::Shanghai => SpecId::SHANGHAI,
+            Hardfork::Cancun => SpecId::CANCUN,
+            Hardfork::Prague => SpecId::PRAGUE,
         }
+    }
 
-        // Consume gas
-        self.context.gas_manager.consume_gas(gas_cost)?;
+    /// Pre-warm accounts and storage slots for the next execution
+    pub fn prewarm(&mut self, warm: &[(Address, Vec<H256>)]) {
+        for (address, keys) in warm {
+            self.context.warm_address(*address);
+            for key in keys {
+                self.context.warm_storage_key(*address, *key);
+            }
+        }
+    }
 
-        // Execute precompiled contract
-        let output = self.precompiled_registry.get(&transaction.receive_address)
-            .unwrap()
-            .execute(&transaction.data)?;
+    /// Execute a transaction, committing its state changes
+    pub fn execute(&mut self, transaction: &Transaction) -> Result<EvmExecutionResult> {
+        self.run(transaction, true)
+    }
 
-        Ok(EvmExecutionResult {
-            gas_used: self.context.gas_manager.gas_used,
-            gas_refunded: self.context.gas_manager.gas_refunded,
-            output,
-            success: true,
-            logs: vec![],
-            contract_address: None,
-            error: None,
-        })
+    /// Execute a transaction, recording an opcode-level trace
+    ///
+    /// Each entry carries the program counter, opcode, gas charged and the
+    /// stack before the step; SSTORE steps also record the slot written.
+    /// Memory is not captured. The state changes are discarded, so tracing
+    /// never alters the state.
+    pub fn execute_with_trace(&mut self, transaction: &Transaction) -> Result<(EvmExecutionResult, Vec<TraceEntry>)> {
+        let mut trace = Vec::new();
+        let result = self.execute_with_revm(transaction, false, Some(&mut trace))?;
+        Ok((result, trace))
+    }
+
+    /// Execute a transaction, optionally committing its state changes
+    ///
+    /// Precompiled contracts run inside REVM, so calls to them pay the
+    /// intrinsic gas like any other transaction.
+    fn run(&mut self, transaction: &Transaction, commit: bool) -> Result<EvmExecutionResult> {
+        self.execute_with_revm(transaction, commit, None)
     }
 
     /// Execute transaction using REVM
-    fn execute_with_revm(&mut self, transaction: &Transaction) -> Result<EvmExecutionResult> {
+    ///
+    /// A panic inside REVM or the conversion to its types is turned into an
+    /// error so a single malformed transaction cannot take down the node.
+    fn execute_with_revm(&mut self, transaction: &Transaction, commit: bool, trace: Option<&mut Vec<TraceEntry>>) -> Result<EvmExecutionResult> {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.execute_with_revm_unguarded(transaction, commit, trace)))
+            .unwrap_or_else(|panic| {
+                let message = panic.downcast_ref::<&str>().map(|m| m.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                Err(OlympusError::EvmExecution(format!("REVM execution panicked: {}", message)))
+            })
+    }
+
+    /// Execute transaction using REVM without panic protection
+    fn execute_with_revm_unguarded(&mut self, transaction: &Transaction, commit: bool, trace: Option<&mut Vec<TraceEntry>>) -> Result<EvmExecutionResult> {
         // Convert transaction to REVM format
-        let tx_env = self.convert_transaction_to_tx_env(transaction);
+        let tx_env = self.convert_transaction_to_tx_env(transaction)?;
         
         // Update REVM context
         self.revm_context.tx = tx_env.clone();
         self.revm_context.block.number = RevmU256::from(self.context.env.block_number.as_u64());
         self.revm_context.block.timestamp = RevmU256::from(self.context.env.timestamp.as_u64());
-        self.revm_context.block.beneficiary = RevmAddress::from_slice(&self.context.env.coinbase.as_bytes());
+        self.revm_context.block.beneficiary = RevmAddress::from_slice(self.context.env.coinbase.as_bytes());
         self.revm_context.block.gas_limit = self.context.env.block_gas_limit.as_u64();
         self.revm_context.block.basefee = self.context.env.base_fee.as_u64();
+        self.revm_context.block.prevrandao = Some(B256::from_slice(self.context.env.prevrandao.as_bytes()));
+        self.revm_context.cfg.spec = self.spec_id();
+        self.revm_context.cfg.chain_id = self.context.env.chain_id;
         
-        // Build EVM instance
-        let mut evm = self.revm_context.clone().build_mainnet();
-        
-        // Execute transaction
-        let result = evm.transact(tx_env).map_err(|e| {
-            OlympusError::EvmExecution(format!("REVM execution failed: {:?}", e))
-        })?;
+        // Execute transaction against the state
+        let db = WrapDatabaseRef(self.database());
+        let mut context = self.revm_context.clone().with_db(db);
+        self.warm_access_set(&mut context)?;
+        let (result, exceeded) = self.transact(context, tx_env, trace)?;
+        if commit {
+            commit_to_state(self.state.as_mut(), &result.state);
+        }
         
         // Convert result
-        self.convert_revm_result(result)
+        self.convert_revm_result(result, exceeded)
+    }
+
+    /// Load the accounts and slots marked warm by `prewarm` into the REVM journal
+    ///
+    /// Loaded before the transaction starts, they are charged the warm access
+    /// price on first use, without the up-front charge of an access list.
+    fn warm_access_set<DB: Database>(&self, context: &mut Context<BlockEnv, TxEnv, CfgEnv, DB>) -> Result<()> {
+        let mut warm: HashMap<Address, Vec<RevmU256>> = self.context.accessed_addresses.iter()
+            .map(|address| (*address, Vec::new()))
+            .collect();
+        for (address, key) in &self.context.accessed_storage_keys {
+            warm.entry(*address).or_default().push(RevmU256::from_be_bytes(key.0));
+        }
+
+        for (address, keys) in warm {
+            context.journaled_state.warm_account_and_storage(RevmAddress::from_slice(address.as_bytes()), keys)
+                .map_err(|e| OlympusError::EvmExecution(format!("Pre-warming {:?} failed: {:?}", address, e)))?;
+        }
+        Ok(())
+    }
+
+    /// Run a transaction on a REVM context, enforcing the execution limits
+    ///
+    /// Returns the result and the status forced by the limit that stopped
+    /// the execution, if any. Steps are appended to `trace` when given.
+    fn transact<DB: Database>(&self, mut context: Context<BlockEnv, TxEnv, CfgEnv, DB>, tx_env: TxEnv, trace: Option<&mut Vec<TraceEntry>>) -> Result<(ExecResultAndState<ExecutionResult, EvmState>, Option<ExecutionStatus>)> {
+        let map_error = |e: EVMError<DB::Error>| OlympusError::EvmExecution(format!("REVM execution failed: {:?}", e));
+        let pay_coinbase = !self.defer_coinbase_fee;
+        context.set_tx(tx_env);
+
+        if let Some(trace) = trace {
+            let mut tracer = Tracer::new(self.limits);
+            let mut evm = context.build_mainnet_with_inspector(&mut tracer);
+            let result = FeeHandler::new(pay_coinbase).inspect_run(&mut evm);
+            let state = evm.finalize();
+            let result = ExecResultAndState::new(result.map_err(map_error)?, state);
+            trace.append(&mut tracer.entries);
+            return Ok((result, tracer.limiter.exceeded));
+        }
+
+        if !self.limits.is_limited() {
+            let mut evm = context.build_mainnet();
+            let result = FeeHandler::new(pay_coinbase).run(&mut evm);
+            let state = evm.finalize();
+            return Ok((ExecResultAndState::new(result.map_err(map_error)?, state), None));
+        }
+
+        let mut limiter = StepLimiter::new(self.limits);
+        let mut evm = context.build_mainnet_with_inspector(&mut limiter);
+        let result = FeeHandler::new(pay_coinbase).inspect_run(&mut evm);
+        let state = evm.finalize();
+        Ok((ExecResultAndState::new(result.map_err(map_error)?, state), limiter.exceeded))
     }
 
     /// Convert transaction to REVM TxEnv
-    fn convert_transaction_to_tx_env(&self, transaction: &Transaction) -> TxEnv {
-        TxEnv {
-            tx_type: 0, // Legacy transaction
-            caller: RevmAddress::from_slice(&transaction.from().as_bytes()),
+    fn convert_transaction_to_tx_env(&self, transaction: &Transaction) -> Result<TxEnv> {
+        if transaction.gas() > U256::from(u64::MAX) || transaction.nonce() > U256::from(u64::MAX) {
+            return Err(OlympusError::EvmExecution("Transaction field exceeds 64 bits".to_string()));
+        }
+        let to_u128 = |fee: U256| u128::try_from(fee)
+            .map_err(|_| OlympusError::EvmExecution("Transaction fee exceeds 128 bits".to_string()));
+
+        // EIP-1559 transactions pass their max fee as the gas price
+        let tx_type = transaction.transaction_type();
+        let gas_priority_fee = match tx_type {
+            TransactionType::Eip1559 => Some(to_u128(transaction.max_priority_fee_per_gas.unwrap_or_default())?),
+            TransactionType::Legacy | TransactionType::Eip2930 => None,
+        };
+
+        Ok(TxEnv {
+            tx_type: tx_type.type_byte(),
+            caller: RevmAddress::from_slice(transaction.from().as_bytes()),
             gas_limit: transaction.gas().as_u64(),
-            gas_price: transaction.gas_price().as_u64() as u128,
-            gas_priority_fee: None,
+            gas_price: to_u128(transaction.gas_price())?,
+            gas_priority_fee,
             kind: if transaction.is_creation() {
                 TxKind::Create
             } else {
-                TxKind::Call(RevmAddress::from_slice(&transaction.receive_address.as_bytes()))
+                TxKind::Call(RevmAddress::from_slice(transaction.receive_address.as_bytes()))
             },
-            value: RevmU256::from(transaction.value().as_u64()),
+            value: RevmU256::from_limbs(transaction.value().0),
             data: Bytes::from(transaction.data().to_vec()),
             nonce: transaction.nonce().as_u64(),
             chain_id: transaction.chain_id(),
-            access_list: Default::default(),
+            access_list: AccessList(transaction.access_list.iter().map(|(address, keys)| AccessListItem {
+                address: RevmAddress::from_slice(address.as_bytes()),
+                storage_keys: keys.iter().map(|key| B256::from_slice(key.as_bytes())).collect(),
+            }).collect()),
             blob_hashes: vec![],
             max_fee_per_blob_gas: 0,
             authorization_list: vec![],
-        }
+        })
     }
 
     /// Convert REVM result to our format
-    fn convert_revm_result(&mut self, result: ExecResultAndState<ExecutionResult, EvmState>) -> Result<EvmExecutionResult> {
+    fn convert_revm_result(&mut self, result: ExecResultAndState<ExecutionResult, EvmState>, exceeded: Option<ExecutionStatus>) -> Result<EvmExecutionResult> {
         let execution_result = result.result;
+        let status = match (&execution_result, exceeded) {
+            (ExecutionResult::Success { .. }, _) => ExecutionStatus::Success,
+            (ExecutionResult::Revert { .. }, _) => ExecutionStatus::Reverted,
+            (ExecutionResult::Halt { .. }, Some(exceeded)) => exceeded,
+            (ExecutionResult::Halt { reason: HaltReason::OutOfGas(_), .. }, None) => ExecutionStatus::OutOfGas,
+            (ExecutionResult::Halt { .. }, None) => ExecutionStatus::Halted,
+        };
+
+        // REVM refunds cleared storage slots, already capped by its spec and netted out of
+        // gas used; the gas manager tracks the gross gas and the refund alongside
+        let gas_refunded = match &execution_result {
+            ExecutionResult::Success { gas_refunded, .. } => U256::from(*gas_refunded),
+            _ => U256::zero(),
+        };
+        self.context.gas_manager.gas_used = U256::from(execution_result.gas_used()) + gas_refunded;
+        self.context.gas_manager.refund_gas(gas_refunded);
+        
+        // Revert payloads are kept as output, and a standard reason is appended to the error
+        let output = execution_result.output().unwrap_or(&Bytes::new()).to_vec();
+        let error = match (status.error_message(), decode_revert_reason(&output)) {
+            (Some(message), Some(reason)) if status == ExecutionStatus::Reverted => Some(format!("{}: {}", message, reason)),
+            (message, _) => message.map(str::to_string),
+        };
         
         Ok(EvmExecutionResult {
             gas_used: U256::from(execution_result.gas_used()),
-            gas_refunded: U256::zero(),
-            output: execution_result.output().unwrap_or(&Bytes::new()).to_vec(),
+            gas_refunded,
+            output,
             success: execution_result.is_success(),
-            logs: vec![], // TODO: Extract logs from execution result
-            contract_address: execution_result.created_address().map(|addr| Address::from_slice(&addr.as_slice())),
-            error: if execution_result.is_success() {
-                None
-            } else {
-                Some(format!("Execution failed"))
-            },
+            status,
+            logs: execution_result.logs().iter().map(|log| EnvironmentLogEntry {
+                address: Address::from_slice(log.address.as_slice()),
+                topics: log.topics().iter().map(|topic| H256::from_slice(topic.as_slice())).collect(),
+                data: log.data.data.to_vec(),
+            }).collect(),
+            contract_address: execution_result.created_address().map(|addr| Address::from_slice(addr.as_slice())),
+            error,
         })
     }
 
-
-    /// Calculate contract address for contract creation
-    fn calculate_contract_address(&self, transaction: &Transaction) -> Address {
-        // Simple contract address calculation based on sender and nonce
-        // In a full implementation, this would use CREATE2 or proper CREATE logic
-        let mut data = Vec::new();
-        data.extend_from_slice(transaction.from().as_bytes());
-        data.extend_from_slice(&transaction.nonce().as_u64().to_be_bytes());
-        crate::common::keccak256(&data).into()
-    }
-
     /// Estimate gas for transaction
+    ///
+    /// Binary searches for the lowest gas limit, between the intrinsic gas and
+    /// the block gas limit, at which the transaction succeeds. This can exceed
+    /// the gas used by a single run, which is net of refunds and of gas
+    /// withheld from calls.
     pub fn estimate_gas(&mut self, transaction: &Transaction) -> Result<U256> {
-        // Initialize with high gas limit for estimation
-        self.context.gas_manager = GasManager::new(U256::from(100_000_000), transaction.gas_price());
-        
-        // Execute transaction
-        let result = self.execute(transaction)?;
-        
-        if result.success {
-            Ok(result.gas_used)
-        } else {
-            Err(OlympusError::EvmExecution(result.error.unwrap_or("Execution failed".to_string())))
+        // Every limit up to `low` fails, and `high` must succeed
+        let mut low = U256::from(transaction.intrinsic_gas(&self.context.gas_schedule)).saturating_sub(U256::one());
+        let mut high = self.context.env.block_gas_limit;
+
+        let mut transaction = transaction.clone();
+        let mut succeeds = |executive: &mut Self, gas: U256| {
+            transaction.gas = gas;
+            executive.context.gas_manager = GasManager::new(gas, transaction.gas_price())
+                .with_refund_quotient(executive.context.gas_schedule.max_refund_quotient);
+            executive.run(&transaction, false)
+        };
+
+        let result = succeeds(self, high)?;
+        if !result.success {
+            return Err(OlympusError::EvmExecution(result.error.unwrap_or("Execution failed".to_string())));
+        }
+
+        for _ in 0..MAX_ESTIMATION_ITERATIONS {
+            if high - low <= U256::one() {
+                break;
+            }
+            let middle = low + (high - low) / 2;
+            match succeeds(self, middle) {
+                Ok(result) if result.success => high = middle,
+                _ => low = middle,
+            }
         }
+        Ok(high)
     }
 
     /// Call contract method (read-only)
@@ -232,8 +720,8 @@ impl Executive {
             U256::zero(), // Nonce not important for calls
         );
 
-        // Execute the call
-        let result = self.execute(&call_transaction)?;
+        // Execute the call without keeping its effects
+        let result = self.run(&call_transaction, false)?;
         
         if result.success {
             Ok(result.output)
@@ -242,6 +730,64 @@ impl Executive {
         }
     }
 
+    /// Call contract method (read-only) with per-account state overrides
+    ///
+    /// The overrides are written to a throwaway cache layered over the
+    /// state, so they are discarded when the call returns.
+    pub fn call_with_overrides(&mut self, from: Address, to: Address, data: Vec<u8>, overrides: &StateOverride) -> Result<Vec<u8>> {
+        let mut db = CacheDB::new(self.database());
+        for (address, account) in overrides {
+            let address = RevmAddress::from_slice(address.as_bytes());
+            let db_error = |e| OlympusError::EvmExecution(format!("State override failed: {:?}", e));
+
+            let mut info = db.basic_ref(address).map_err(db_error)?.unwrap_or_default();
+            if let Some(balance) = account.balance {
+                let mut bytes = [0u8; 32];
+                balance.to_big_endian(&mut bytes);
+                info.balance = RevmU256::from_be_bytes(bytes);
+            }
+            if let Some(nonce) = account.nonce {
+                info.nonce = nonce;
+            }
+            if let Some(code) = &account.code {
+                let bytecode = Bytecode::new_raw(Bytes::from(code.clone()));
+                info = AccountInfo { code_hash: bytecode.hash_slow(), code: Some(bytecode), ..info };
+            }
+            db.insert_account_info(address, info);
+
+            if let Some(state) = &account.state {
+                let storage = state.iter()
+                    .map(|(slot, value)| (RevmU256::from_be_bytes(slot.0), RevmU256::from_be_bytes(value.0)))
+                    .collect();
+                db.replace_account_storage(address, storage).map_err(db_error)?;
+            }
+            for (slot, value) in &account.state_diff {
+                db.insert_account_storage(address, RevmU256::from_be_bytes(slot.0), RevmU256::from_be_bytes(value.0))
+                    .map_err(db_error)?;
+            }
+        }
+
+        // Calls are free and unsigned: no fee, no nonce or chain checks
+        let call_transaction = Transaction::new(U256::zero(), U256::zero(), U256::from(100_000), to, data, U256::zero());
+        let mut tx_env = self.convert_transaction_to_tx_env(&call_transaction)?;
+        tx_env.caller = RevmAddress::from_slice(from.as_bytes());
+        tx_env.chain_id = None;
+
+        let mut context = self.revm_context.clone();
+        context.block.basefee = 0;
+        context.cfg.spec = self.spec_id();
+        context.cfg.chain_id = self.context.env.chain_id;
+        context.cfg.disable_nonce_check = true;
+        let (result, exceeded) = self.transact(context.with_db(db), tx_env, None)?;
+        let result = self.convert_revm_result(result, exceeded)?;
+
+        if result.success {
+            Ok(result.output)
+        } else {
+            Err(OlympusError::EvmExecution(result.error.unwrap_or("Call failed".to_string())))
+        }
+    }
+
     /// Get current gas usage
     pub fn gas_used(&self) -> U256 {
         self.context.gas_manager.gas_used
@@ -261,4 +807,388 @@ impl Executive {
     pub fn context_mut(&mut self) -> &mut ExecutionContext {
         &mut self.context
     }
-}
\ No newline at end of file
+}
+
+impl Default for Executive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prevrandao_visible_to_contracts() {
+        // PREVRANDAO PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let init_code = vec![0x44, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+        let tx = Transaction::new_contract_creation(U256::zero(), U256::zero(), U256::from(100_000), init_code, U256::zero());
+
+        let prevrandao = H256::repeat_byte(0x5a);
+        let mut executive = Executive::new();
+        executive.set_prevrandao(prevrandao);
+        executive.initialize(&tx, U256::one(), U256::from(1_600_000_000u64)).unwrap();
+        executive.context_mut().env.base_fee = U256::zero();
+
+        let result = executive.execute(&tx).unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, prevrandao.as_bytes().to_vec());
+    }
+
+    #[test]
+    fn test_malformed_transaction_returns_error() {
+        // A gas limit above u64::MAX used to panic converting to REVM's transaction env
+        let tx = Transaction::new(U256::zero(), U256::from(1_000_000_000u64), U256::MAX, Address::from([0x42; 20]), vec![], U256::zero());
+        let mut executive = Executive::new();
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+
+        assert!(matches!(executive.execute(&tx), Err(OlympusError::EvmExecution(_))));
+    }
+
+    #[test]
+    fn test_access_list_prewarms_listed_account() {
+        // PUSH20 target BALANCE POP STOP
+        let target = Address::repeat_byte(0x77);
+        let init_code = [vec![0x73], target.as_bytes().to_vec(), vec![0x31, 0x50, 0x00]].concat();
+        let run = |access_list: Vec<(Address, Vec<H256>)>| {
+            let mut tx = Transaction::new_contract_creation(U256::zero(), U256::zero(), U256::from(100_000), init_code.clone(), U256::zero());
+            tx.access_list = access_list;
+            let mut executive = Executive::new();
+            executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+            executive.context_mut().env.base_fee = U256::zero();
+            let result = executive.execute(&tx).unwrap();
+            assert!(result.success);
+            (tx.intrinsic_gas(&crate::evm::gas_schedule::GasSchedule::berlin()), result.gas_used)
+        };
+
+        let (cold_intrinsic, cold_used) = run(vec![]);
+        let (warm_intrinsic, warm_used) = run(vec![(target, vec![])]);
+        // Listing costs 2400 up front but saves the 2500 cold access surcharge
+        assert_eq!(warm_intrinsic, cold_intrinsic + 2400);
+        assert_eq!(warm_used + U256::from(100), cold_used);
+    }
+
+    #[test]
+    fn test_value_transfer_reads_and_writes_state() {
+        let recipient = Address::repeat_byte(0x42);
+        let mut state = MemoryState::new();
+        state.set_balance(Address::zero(), U256::from(1_000_000));
+        state.set_balance(recipient, U256::from(7));
+
+        let tx = Transaction::new(U256::from(500), U256::zero(), U256::from(21_000), recipient, vec![], U256::zero());
+        let mut executive = Executive::new_with_state(Box::new(state));
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+        executive.set_base_fee(U256::zero());
+
+        let result = executive.execute(&tx).unwrap();
+        assert!(result.success);
+        assert_eq!(executive.state().get_balance(recipient), U256::from(507));
+        assert_eq!(executive.state().get_balance(Address::zero()), U256::from(999_500));
+        assert_eq!(executive.state().get_nonce(Address::zero()), 1);
+    }
+
+    #[test]
+    fn test_transfer_to_empty_account_deletes_it() {
+        let recipient = Address::repeat_byte(0x42);
+        let mut state = MemoryState::new();
+        state.set_balance(Address::zero(), U256::from(1_000_000));
+        state.create_account(recipient);
+
+        let tx = Transaction::new(U256::zero(), U256::zero(), U256::from(21_000), recipient, vec![], U256::zero());
+        let mut executive = Executive::new_with_state(Box::new(state));
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+        executive.set_base_fee(U256::zero());
+
+        assert!(executive.execute(&tx).unwrap().success);
+        // Touched and left empty, so removed under EIP-161
+        assert!(!executive.state().exists(recipient));
+        assert!(executive.state().exists(Address::zero()));
+    }
+
+    #[test]
+    fn test_blockhash_reads_recent_canonical_hashes() {
+        // PUSH2 number BLOCKHASH PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let block_hash = |number: u16| {
+            let [high, low] = number.to_be_bytes();
+            let init_code = vec![0x61, high, low, 0x40, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+            let tx = Transaction::new_contract_creation(U256::zero(), U256::zero(), U256::from(100_000), init_code, U256::zero());
+            let mut executive = Executive::new();
+            executive.set_block_hashes(Arc::new(std::collections::BTreeMap::from([
+                (10u64, H256::repeat_byte(0x10)),
+                (299, H256::repeat_byte(0x99)),
+            ])));
+            executive.initialize(&tx, U256::from(300), U256::zero()).unwrap();
+            executive.set_base_fee(U256::zero());
+            let result = executive.execute(&tx).unwrap();
+            assert!(result.success);
+            H256::from_slice(&result.output)
+        };
+
+        assert_eq!(block_hash(299), H256::repeat_byte(0x99));
+        // Older than 256 blocks, the current block and future blocks read as zero
+        assert_eq!(block_hash(10), H256::zero());
+        assert_eq!(block_hash(300), H256::zero());
+        assert_eq!(block_hash(301), H256::zero());
+    }
+
+    #[test]
+    fn test_chainid_opcode_returns_olympus_chain_id() {
+        // CHAINID PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let init_code = vec![0x46, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+        let tx = Transaction::new_contract_creation(U256::zero(), U256::zero(), U256::from(100_000), init_code, U256::zero());
+        let mut executive = Executive::new();
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+        executive.set_base_fee(U256::zero());
+
+        let result = executive.execute(&tx).unwrap();
+        assert!(result.success);
+        assert_eq!(U256::from_big_endian(&result.output), U256::from(CHAIN_ID));
+    }
+
+    #[test]
+    fn test_transfer_above_64_bits_of_wei() {
+        let recipient = Address::repeat_byte(0x42);
+        let ether = U256::from(10u64).pow(U256::from(18));
+        let mut state = MemoryState::new();
+        state.set_balance(Address::zero(), ether * 1000);
+
+        // 100 ETH is more than u64::MAX wei
+        let value = ether * 100;
+        assert!(value > U256::from(u64::MAX));
+        let tx = Transaction::new(value, U256::from(1_000_000_000u64), U256::from(21_000), recipient, vec![], U256::zero());
+        let mut executive = Executive::new_with_state(Box::new(state));
+        executive.set_coinbase(Address::repeat_byte(0xcb));
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+        executive.set_base_fee(U256::zero());
+
+        let result = executive.execute(&tx).unwrap();
+        assert!(result.success);
+        assert_eq!(executive.state().get_balance(recipient), value);
+        assert_eq!(executive.state().get_balance(Address::zero()), ether * 900 - U256::from(21_000u64 * 1_000_000_000));
+
+        let overpriced = Transaction::new(U256::zero(), U256::from(u128::MAX) + 1, U256::from(21_000), recipient, vec![], U256::zero());
+        assert!(matches!(executive.execute(&overpriced), Err(OlympusError::EvmExecution(_))));
+    }
+
+    #[test]
+    fn test_clearing_storage_refunds_capped_gas() {
+        // PUSH1 0 CALLDATALOAD DUP1 PUSH1 0 SSTORE PUSH1 1 SSTORE STOP
+        let contract = Address::repeat_byte(0x5c);
+        let mut state = MemoryState::new();
+        state.set_code(contract, vec![0x60, 0x00, 0x35, 0x80, 0x60, 0x00, 0x55, 0x60, 0x01, 0x55, 0x00]);
+        let mut executive = Executive::new_with_state(Box::new(state));
+        let mut execute = |data: Vec<u8>, nonce: u64| {
+            let tx = Transaction::new(U256::zero(), U256::zero(), U256::from(100_000), contract, data, U256::from(nonce));
+            executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+            executive.set_base_fee(U256::zero());
+            let result = executive.execute(&tx).unwrap();
+            assert!(result.success);
+            (result, executive.context().gas_manager.gas_refunded)
+        };
+
+        // Set both slots to one
+        let (set, _) = execute(H256::from_low_u64_be(1).as_bytes().to_vec(), 0);
+        assert!(set.gas_refunded.is_zero());
+
+        // Clearing both earns 2 * 4800, capped at a fifth of the 21000 + 10015 gas spent
+        let (clear, tracked) = execute(vec![], 1);
+        let gross = 21_000 + 10_015;
+        assert_eq!(clear.gas_refunded, U256::from(gross / 5));
+        assert_eq!(tracked, clear.gas_refunded);
+        assert_eq!(clear.gas_used, U256::from(gross - gross / 5));
+    }
+
+    #[test]
+    fn test_refunds_capped_at_half_before_london() {
+        // PUSH1 0 PUSH1 0 SSTORE STOP, clearing slot 0
+        let contract = Address::repeat_byte(0x5c);
+        let mut state = MemoryState::new();
+        state.set_code(contract, vec![0x60, 0x00, 0x60, 0x00, 0x55, 0x00]);
+        state.set_storage(contract, H256::zero(), H256::from_low_u64_be(1));
+        let mut executive = Executive::new_with_state(Box::new(state));
+        executive.set_hardforks(vec![(0, Hardfork::Berlin)]).unwrap();
+
+        let tx = Transaction::new(U256::zero(), U256::zero(), U256::from(100_000), contract, vec![], U256::zero());
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+        executive.set_base_fee(U256::zero());
+        let result = executive.execute(&tx).unwrap();
+        assert!(result.success);
+
+        // The 15000 clear refund exceeds half the gross gas, so half is refunded
+        let gross = result.gas_used + result.gas_refunded;
+        assert_eq!(result.gas_refunded, gross / 2);
+        assert_eq!(executive.context().gas_manager.final_gas_used(), result.gas_used);
+    }
+
+    #[test]
+    fn test_hardfork_schedule_selects_spec() {
+        // PUSH0 STOP, only valid from Shanghai
+        let contract = Address::repeat_byte(0x5d);
+        let mut state = MemoryState::new();
+        state.set_code(contract, vec![0x5f, 0x00]);
+        let mut executive = Executive::new_with_state(Box::new(state));
+        executive.set_hardforks(vec![(0, Hardfork::London), (10, Hardfork::Shanghai)]).unwrap();
+        let mut execute = |block: u64, nonce: u64| {
+            let tx = Transaction::new(U256::zero(), U256::zero(), U256::from(100_000), contract, vec![], U256::from(nonce));
+            executive.initialize(&tx, U256::from(block), U256::zero()).unwrap();
+            executive.set_base_fee(U256::zero());
+            executive.execute(&tx).unwrap()
+        };
+
+        assert_eq!(execute(9, 0).status, ExecutionStatus::Halted);
+        assert!(execute(10, 1).success);
+    }
+
+    #[test]
+    fn test_execute_with_trace_records_each_step() {
+        // PUSH1 2 PUSH1 3 ADD PUSH1 0 SSTORE STOP
+        let contract = Address::repeat_byte(0x5e);
+        let mut state = MemoryState::new();
+        state.set_code(contract, vec![0x60, 0x02, 0x60, 0x03, 0x01, 0x60, 0x00, 0x55, 0x00]);
+        let mut executive = Executive::new_with_state(Box::new(state));
+        let tx = Transaction::new(U256::zero(), U256::zero(), U256::from(100_000), contract, vec![], U256::zero());
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+        executive.set_base_fee(U256::zero());
+
+        let (result, trace) = executive.execute_with_trace(&tx).unwrap();
+        assert!(result.success);
+        let opcodes: Vec<&str> = trace.iter().map(|entry| entry.opcode.as_str()).collect();
+        assert_eq!(opcodes, ["PUSH1", "PUSH1", "ADD", "PUSH1", "SSTORE", "STOP"]);
+        assert_eq!(trace.iter().map(|entry| entry.pc).collect::<Vec<_>>(), [0, 2, 4, 5, 7, 8]);
+        assert_eq!(trace[4].stack, [U256::from(5), U256::zero()]);
+        assert_eq!(trace[4].storage.get(&H256::zero()), Some(&H256::from_low_u64_be(5)));
+
+        let traced: U256 = trace.iter().map(|entry| entry.gas_cost).fold(U256::zero(), |sum, cost| sum + cost);
+        assert_eq!(traced + U256::from(21_000), result.gas_used);
+
+        // Tracing leaves the state as it was
+        assert_eq!(executive.state().get_storage(contract, H256::zero()), None);
+        assert_eq!(executive.state().get_nonce(Address::zero()), 0);
+    }
+
+    #[test]
+    fn test_trace_stack_capture_bounded() {
+        // 1000 x PUSH0, then loop forever: JUMPDEST PUSH2 1000 JUMP
+        let code = [vec![0x5f; 1000], vec![0x5b, 0x61, 0x03, 0xe8, 0x56]].concat();
+        let mut executive = Executive::new();
+        let tx = Transaction::new(U256::zero(), U256::zero(), U256::from(10_000_000), Address::zero(), code, U256::zero());
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+        executive.set_base_fee(U256::zero());
+
+        let (result, trace) = executive.execute_with_trace(&tx).unwrap();
+        assert_eq!(result.status, ExecutionStatus::TimedOut);
+        let captured: usize = trace.iter().map(|entry| entry.stack.len()).sum();
+        assert!(captured <= MAX_TRACE_STACK_WORDS);
+        assert!(captured + 1001 > MAX_TRACE_STACK_WORDS);
+    }
+
+    #[test]
+    fn test_timed_out_trace_distinct_and_charged_all_gas() {
+        // Init code looping forever: JUMPDEST PUSH1 0x00 JUMP
+        let gas = U256::from(1_000_000);
+        let run = |limits: ExecutionLimits| {
+            let mut executive = Executive::new();
+            let tx = Transaction::new(U256::zero(), U256::zero(), gas, Address::zero(), vec![0x5b, 0x60, 0x00, 0x56], U256::zero());
+            executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+            executive.set_base_fee(U256::zero());
+            executive.set_execution_limits(limits);
+            executive.execute_with_trace(&tx).unwrap().0
+        };
+
+        let out_of_gas = run(ExecutionLimits::default());
+        assert_eq!(out_of_gas.status, ExecutionStatus::OutOfGas);
+        assert_eq!(out_of_gas.gas_used, gas);
+
+        let timed_out = run(ExecutionLimits { max_steps: Some(1_000), ..Default::default() });
+        assert!(!timed_out.success);
+        assert_eq!(timed_out.status, ExecutionStatus::TimedOut);
+        assert_eq!(timed_out.error.as_deref(), Some("Execution timed out"));
+        assert_eq!(timed_out.gas_used, gas);
+    }
+
+    #[test]
+    fn test_revert_reason_surfaces_in_error() {
+        // Error("nope"), as emitted by require(false, "nope")
+        let mut payload = REVERT_REASON_SELECTOR.to_vec();
+        payload.extend_from_slice(H256::from_low_u64_be(32).as_bytes());
+        payload.extend_from_slice(H256::from_low_u64_be(4).as_bytes());
+        payload.extend_from_slice(&[b"nope".as_slice(), &[0u8; 28]].concat());
+
+        // PUSH32 word PUSH1 offset MSTORE for each word, then PUSH1 len PUSH1 0 REVERT
+        let mut code = Vec::new();
+        for (index, chunk) in payload.chunks(32).enumerate() {
+            let mut word = chunk.to_vec();
+            word.resize(32, 0);
+            code.push(0x7f);
+            code.extend(word);
+            code.extend([0x60, (index * 32) as u8, 0x52]);
+        }
+        code.extend([0x60, payload.len() as u8, 0x60, 0x00, 0xfd]);
+
+        let contract = Address::repeat_byte(0x5d);
+        let mut state = MemoryState::new();
+        state.set_code(contract, code);
+        let tx = Transaction::new(U256::zero(), U256::zero(), U256::from(100_000), contract, vec![], U256::zero());
+        let mut executive = Executive::new_with_state(Box::new(state));
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+        executive.set_base_fee(U256::zero());
+
+        let result = executive.execute(&tx).unwrap();
+        assert_eq!(result.status, ExecutionStatus::Reverted);
+        assert_eq!(result.output, payload);
+        assert_eq!(result.error.as_deref(), Some("Execution reverted: nope"));
+        assert_eq!(decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef]), None);
+    }
+
+    #[test]
+    fn test_estimate_covers_gas_refunded_at_the_end() {
+        // PUSH1 0 CALLDATALOAD DUP1 PUSH1 0 SSTORE PUSH1 1 SSTORE STOP, clearing two set slots
+        let contract = Address::repeat_byte(0x5c);
+        let mut state = MemoryState::new();
+        state.set_code(contract, vec![0x60, 0x00, 0x35, 0x80, 0x60, 0x00, 0x55, 0x60, 0x01, 0x55, 0x00]);
+        state.set_storage(contract, H256::zero(), H256::from_low_u64_be(1));
+        state.set_storage(contract, H256::from_low_u64_be(1), H256::from_low_u64_be(1));
+
+        let tx = Transaction::new(U256::zero(), U256::zero(), U256::from(100_000), contract, vec![], U256::zero());
+        let mut executive = Executive::new_with_state(Box::new(state));
+        executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+        executive.set_base_fee(U256::zero());
+
+        // The slots are cleared before the refund is credited, so the full cost must be supplied
+        let estimate = executive.estimate_gas(&tx).unwrap();
+        assert_eq!(estimate, U256::from(21_000 + 10_015));
+
+        let single_run = executive.execute(&tx).unwrap();
+        assert!(single_run.success);
+        assert!(single_run.gas_used < estimate);
+    }
+
+    #[test]
+    fn test_identity_charges_intrinsic_and_ceiling_words() {
+        let identity = Address::from_low_u64_be(4);
+        let input = vec![0xab; 33];
+        let mut executive = Executive::new();
+        // Before Prague, so the calldata floor does not apply
+        executive.set_hardforks(vec![(0, Hardfork::Berlin)]).unwrap();
+        let mut execute = |gas: u64, nonce: u64| {
+            let tx = Transaction::new(U256::zero(), U256::zero(), U256::from(gas), identity, input.clone(), U256::from(nonce));
+            executive.initialize(&tx, U256::one(), U256::zero()).unwrap();
+            executive.set_base_fee(U256::zero());
+            executive.execute(&tx).unwrap()
+        };
+
+        // 21000 plus 16 per calldata byte, then 15 base + 3 per started word: two words for 33 bytes
+        let gas = 21_000 + 33 * 16 + 21;
+        let result = execute(gas, 0);
+        assert!(result.success);
+        assert_eq!(result.gas_used, U256::from(gas));
+        assert_eq!(result.output, input);
+
+        // One gas short runs out of gas without copying the input
+        let result = execute(gas - 1, 1);
+        assert_eq!(result.status, ExecutionStatus::OutOfGas);
+        assert_eq!(result.gas_used, U256::from(gas - 1));
+        assert!(result.output.is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+//! Gas schedule: per-opcode and intrinsic gas costs
+
+use serde::{Deserialize, Serialize};
+
+/// Hardforks, in activation order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Hardfork {
+    /// Frontier
+    Frontier,
+    /// Homestead
+    Homestead,
+    /// Byzantium
+    Byzantium,
+    /// Petersburg (Constantinople without EIP-1283)
+    Petersburg,
+    /// Istanbul (EIP-1884, EIP-2028)
+    Istanbul,
+    /// Berlin (EIP-2929 cold/warm access)
+    Berlin,
+    /// London (EIP-1559, EIP-3529)
+    London,
+    /// Paris, the merge (PREVRANDAO)
+    Paris,
+    /// Shanghai (PUSH0)
+    Shanghai,
+    /// Cancun (transient storage, blobs)
+    Cancun,
+    /// Prague
+    Prague,
+}
+
+/// Gas costs consulted by the environment and intrinsic-gas code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasSchedule {
+    /// Base cost of every transaction
+    pub tx_gas: u64,
+    /// Extra cost of a contract creation transaction
+    pub tx_create_gas: u64,
+    /// Cost per zero byte of transaction data
+    pub tx_data_zero_gas: u64,
+    /// Cost per non-zero byte of transaction data
+    pub tx_data_non_zero_gas: u64,
+    /// Cost of the cheapest opcodes (JUMPDEST)
+    pub jumpdest_gas: u64,
+    /// Cost of base-tier opcodes (POP, PC, block context)
+    pub base_gas: u64,
+    /// Cost of very-low-tier opcodes (arithmetic, memory, stack)
+    pub very_low_gas: u64,
+    /// Cost of JUMP
+    pub mid_gas: u64,
+    /// Cost of JUMPI
+    pub high_gas: u64,
+    /// Cost of SHA3
+    pub sha3_gas: u64,
+    /// Cost of SLOAD
+    pub sload_gas: u64,
+    /// Cost of SSTORE setting a zero slot
+    pub sstore_set_gas: u64,
+    /// Refund for clearing a storage slot
+    pub sstore_clear_refund: u64,
+    /// Largest share of gas used that refunds may cover, as a divisor
+    #[serde(default = "default_max_refund_quotient")]
+    pub max_refund_quotient: u64,
+    /// Cost of BALANCE
+    pub balance_gas: u64,
+    /// Cost of BLOCKHASH
+    pub blockhash_gas: u64,
+    /// Base cost of LOG opcodes
+    pub log_gas: u64,
+    /// Cost per LOG topic
+    pub log_topic_gas: u64,
+    /// Cost of CREATE and CREATE2
+    pub create_gas: u64,
+    /// Cost of the CALL family
+    pub call_gas: u64,
+    /// Cost of SELFDESTRUCT
+    pub selfdestruct_gas: u64,
+    /// Cost of the first storage read of a slot in a transaction
+    pub cold_sload_gas: u64,
+    /// Cost of a repeated storage or account access
+    pub warm_storage_read_gas: u64,
+    /// Cost of the first access of an account in a transaction
+    pub cold_account_access_gas: u64,
+    /// Cost of opcodes not listed in the schedule
+    pub default_gas: u64,
+    /// Cost per account in a transaction's access list
+    #[serde(default)]
+    pub access_list_address_gas: u64,
+    /// Cost per storage key in a transaction's access list
+    #[serde(default)]
+    pub access_list_storage_key_gas: u64,
+    /// Cost per 32-byte word of contract creation code (EIP-3860)
+    #[serde(default)]
+    pub initcode_word_gas: u64,
+    /// Floor cost per calldata token (EIP-7623), zero before Prague
+    #[serde(default)]
+    pub tx_data_floor_gas: u64,
+}
+
+impl GasSchedule {
+    /// Create the gas schedule of a hardfork
+    ///
+    /// Forks before Istanbul share the Byzantium costs; later forks take the
+    /// schedule of the last fork before them that changed a cost.
+    pub fn new(hardfork: Hardfork) -> Self {
+        match hardfork {
+            Hardfork::Frontier | Hardfork::Homestead | Hardfork::Byzantium | Hardfork::Petersburg => Self::byzantium(),
+            Hardfork::Istanbul => Self::istanbul(),
+            Hardfork::Berlin => Self::berlin(),
+            Hardfork::London | Hardfork::Paris => Self::london(),
+            Hardfork::Shanghai | Hardfork::Cancun => Self::shanghai(),
+            Hardfork::Prague => Self::prague(),
+        }
+    }
+
+    /// Byzantium gas schedule
+    pub fn byzantium() -> Self {
+        Self {
+            tx_gas: 21000,
+            tx_create_gas: 32000,
+            tx_data_zero_gas: 4,
+            tx_data_non_zero_gas: 68,
+            jumpdest_gas: 1,
+            base_gas: 2,
+            very_low_gas: 3,
+            mid_gas: 8,
+            high_gas: 10,
+            sha3_gas: 30,
+            sload_gas: 200,
+            sstore_set_gas: 20000,
+            sstore_clear_refund: 15000,
+            max_refund_quotient: 2,
+            balance_gas: 400,
+            blockhash_gas: 20,
+            log_gas: 375,
+            log_topic_gas: 375,
+            create_gas: 32000,
+            call_gas: 700,
+            selfdestruct_gas: 5000,
+            cold_sload_gas: 200,
+            warm_storage_read_gas: 200,
+            cold_account_access_gas: 400,
+            default_gas: 1,
+            access_list_address_gas: 0,
+            access_list_storage_key_gas: 0,
+            initcode_word_gas: 0,
+            tx_data_floor_gas: 0,
+        }
+    }
+
+    /// Istanbul gas schedule
+    pub fn istanbul() -> Self {
+        Self {
+            tx_data_non_zero_gas: 16,
+            sload_gas: 800,
+            balance_gas: 700,
+            cold_sload_gas: 800,
+            warm_storage_read_gas: 800,
+            cold_account_access_gas: 700,
+            ..Self::byzantium()
+        }
+    }
+
+    /// Berlin gas schedule
+    pub fn berlin() -> Self {
+        Self {
+            sload_gas: 100,
+            balance_gas: 100,
+            call_gas: 100,
+            cold_sload_gas: 2100,
+            warm_storage_read_gas: 100,
+            cold_account_access_gas: 2600,
+            access_list_address_gas: 2400,
+            access_list_storage_key_gas: 1900,
+            ..Self::istanbul()
+        }
+    }
+
+    /// London gas schedule
+    pub fn london() -> Self {
+        Self {
+            sstore_clear_refund: 4800,
+            max_refund_quotient: 5,
+            ..Self::berlin()
+        }
+    }
+
+    /// Shanghai gas schedule
+    pub fn shanghai() -> Self {
+        Self {
+            initcode_word_gas: 2,
+            ..Self::london()
+        }
+    }
+
+    /// Prague gas schedule
+    pub fn prague() -> Self {
+        Self {
+            tx_data_floor_gas: 10,
+            ..Self::shanghai()
+        }
+    }
+
+    /// Intrinsic gas of a transaction
+    pub fn intrinsic_gas(&self, data: &[u8], is_creation: bool) -> u64 {
+        let mut gas = self.tx_gas;
+        if is_creation {
+            gas += self.tx_create_gas + data.len().div_ceil(32) as u64 * self.initcode_word_gas;
+        }
+
+        for byte in data {
+            gas += if *byte == 0 { self.tx_data_zero_gas } else { self.tx_data_non_zero_gas };
+        }
+        gas
+    }
+
+    /// Least gas a transaction carrying `data` is charged (EIP-7623)
+    ///
+    /// Each zero byte counts as one token and each non-zero byte as four.
+    /// Zero before Prague, where no floor applies.
+    pub fn floor_gas(&self, data: &[u8]) -> u64 {
+        if self.tx_data_floor_gas == 0 {
+            return 0;
+        }
+        let tokens: u64 = data.iter().map(|byte| if *byte == 0 { 1 } else { 4 }).sum();
+        self.tx_gas + tokens * self.tx_data_floor_gas
+    }
+
+    /// Intrinsic gas charged for an EIP-2930 access list
+    ///
+    /// Listed accounts and slots start warm, so each pays the listing charge
+    /// instead of its first cold access.
+    pub fn access_list_gas(&self, access_list: &[(crate::Address, Vec<crate::H256>)]) -> u64 {
+        access_list.iter()
+            .map(|(_, keys)| self.access_list_address_gas + keys.len() as u64 * self.access_list_storage_key_gas)
+            .sum()
+    }
+
+    /// Static gas cost of an opcode by name
+    pub fn opcode_cost(&self, operation: &str) -> u64 {
+        match operation {
+            "ADD" | "SUB" | "MUL" | "DIV" | "MOD" | "ADDMOD" | "MULMOD" => self.very_low_gas,
+            "LT" | "GT" | "SLT" | "SGT" | "EQ" => self.very_low_gas,
+            "AND" | "OR" | "XOR" => self.very_low_gas,
+            "NOT" | "BYTE" => self.very_low_gas,
+            "SHA3" => self.sha3_gas,
+            "SLOAD" => self.sload_gas,
+            "SSTORE" => self.sstore_set_gas,
+            "BALANCE" => self.balance_gas,
+            "BLOCKHASH" => self.blockhash_gas,
+            "COINBASE" | "TIMESTAMP" | "NUMBER" | "DIFFICULTY" | "GASLIMIT" => self.base_gas,
+            "POP" | "PC" | "MSIZE" | "GAS" => self.base_gas,
+            "MLOAD" | "MSTORE" | "MSTORE8" => self.very_low_gas,
+            "JUMP" => self.mid_gas,
+            "JUMPI" => self.high_gas,
+            "JUMPDEST" => self.jumpdest_gas,
+            "LOG0" | "LOG1" | "LOG2" | "LOG3" | "LOG4" => {
+                let topics = operation[3..].parse::<u64>().unwrap_or(0);
+                self.log_gas + topics * self.log_topic_gas
+            }
+            "CREATE" | "CREATE2" => self.create_gas,
+            "CALL" | "CALLCODE" | "DELEGATECALL" | "STATICCALL" => self.call_gas,
+            "RETURN" | "REVERT" => 0,
+            "SELFDESTRUCT" => self.selfdestruct_gas,
+            _ if operation.starts_with("PUSH") || operation.starts_with("DUP") || operation.starts_with("SWAP") => {
+                self.very_low_gas
+            }
+            _ => self.default_gas,
+        }
+    }
+}
+
+/// Refund quotient of schedules predating the field (EIP-3529)
+fn default_max_refund_quotient() -> u64 {
+    5
+}
+
+impl Default for GasSchedule {
+    /// Schedule of the latest hardfork
+    fn default() -> Self {
+        Self::prague()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedules_differ_per_hardfork() {
+        let byzantium = GasSchedule::new(Hardfork::Byzantium);
+        let istanbul = GasSchedule::new(Hardfork::Istanbul);
+        let data = [0x00, 0x01, 0x02];
+
+        assert_eq!(byzantium.intrinsic_gas(&data, false), 21000 + 4 + 68 * 2);
+        assert_eq!(istanbul.intrinsic_gas(&data, false), 21000 + 4 + 16 * 2);
+        assert_eq!(byzantium.opcode_cost("SLOAD"), 200);
+        assert_eq!(istanbul.opcode_cost("SLOAD"), 800);
+    }
+
+    #[test]
+    fn test_prague_floor_prices_calldata_tokens() {
+        let prague = GasSchedule::default();
+        assert_eq!(prague, GasSchedule::new(Hardfork::Prague));
+        let data = [0x00, 0x01, 0x02];
+        assert_eq!(prague.intrinsic_gas(&data, false), 21000 + 4 + 16 * 2);
+        assert_eq!(prague.floor_gas(&data), 21000 + 10 * (1 + 4 * 2));
+        assert_eq!(GasSchedule::berlin().floor_gas(&data), 0);
+
+        // Creation code is charged per word from Shanghai
+        assert_eq!(prague.intrinsic_gas(&[0x01; 33], true), 53000 + 16 * 33 + 2 * 2);
+    }
+
+    #[test]
+    fn test_byzantium_schedule_matches_legacy_costs() {
+        let schedule = GasSchedule::byzantium();
+        assert_eq!(schedule.opcode_cost("ADD"), 3);
+        assert_eq!(schedule.opcode_cost("PUSH32"), 3);
+        assert_eq!(schedule.opcode_cost("LOG2"), 1125);
+        assert_eq!(schedule.opcode_cost("JUMPI"), 10);
+        assert_eq!(schedule.opcode_cost("UNKNOWN"), 1);
+        assert_eq!(schedule.intrinsic_gas(&[], true), 53000);
+    }
+}
@@ -6,11 +6,19 @@ pub mod state;
 pub mod persistent_state;
 pub mod transaction_executor;
 pub mod environment;
+pub mod gas_schedule;
+pub mod parallel_executor;
+pub mod state_override;
+pub mod revm_database;
 
 // Re-export specific types to avoid conflicts
-pub use executive::{Executive, EvmExecutionResult as ExecutiveEvmExecutionResult};
+pub use executive::{Executive, EvmExecutionResult as ExecutiveEvmExecutionResult, ExecutionLimits, ExecutionStatus, decode_revert_reason};
 pub use precompiled::{PrecompiledContract, create_precompiled_registry};
-pub use state::{State, MemoryState};
+pub use state::{State, MemoryState, SharedState};
 pub use persistent_state::{PersistentState, StateManager};
-pub use transaction_executor::{TransactionExecutor, TransactionExecutionContext, TransactionLogEntry};
+pub use transaction_executor::{TransactionExecutor, TransactionExecutionContext, TransactionLogEntry, LogLimits};
 pub use environment::{EvmEnv, GasManager, EnvironmentLogEntry};
+pub use gas_schedule::{GasSchedule, Hardfork};
+pub use parallel_executor::{ParallelExecutor, ParallelExecutionResult};
+pub use state_override::{AccountOverride, StateOverride};
+pub use revm_database::{StateDatabase, commit_to_state};
@@ -0,0 +1,402 @@
+//! Optimistic parallel block execution
+//!
+//! Transactions are first executed concurrently against the pre-block state,
+//! each recording the state it read and buffering the state it wrote. They are
+//! then validated in block order: a transaction whose reads do not overlap the
+//! writes of the transactions committed before it has its buffered writes
+//! applied as-is, any other is re-executed against the committed state. The
+//! outcome is identical to sequential execution.
+//!
+//! Priority fees are left out of execution and credited to the coinbase as a
+//! delta once each transaction is validated, so paying the same coinbase does
+//! not make every transaction of a block conflict. Only a transaction that
+//! reads the coinbase account itself conflicts with the fees before it.
+
+use crate::core::transaction::Transaction;
+use crate::evm::state::State;
+use crate::evm::transaction_executor::{TransactionExecutionContext, TransactionExecutionResult, TransactionExecutor};
+use crate::{Address, H256, U256};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Piece of state accessed by a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessKey {
+    /// Account balance, nonce or existence
+    Account(Address),
+    /// Storage slot
+    Storage(Address, H256),
+}
+
+/// State read and buffered writes of one transaction
+#[derive(Debug, Default)]
+struct AccessRecord {
+    /// Keys read
+    reads: HashSet<AccessKey>,
+    /// Written balances
+    balances: HashMap<Address, U256>,
+    /// Written nonces
+    nonces: HashMap<Address, u64>,
+    /// Written storage slots
+    storage: HashMap<(Address, H256), H256>,
+    /// Written code
+    code: HashMap<Address, Vec<u8>>,
+    /// Created accounts
+    created: HashSet<Address>,
+    /// Deleted accounts
+    deleted: HashSet<Address>,
+}
+
+impl AccessRecord {
+    /// Keys written
+    fn writes(&self) -> HashSet<AccessKey> {
+        let accounts = self.balances.keys()
+            .chain(self.nonces.keys())
+            .chain(self.code.keys())
+            .chain(self.created.iter())
+            .chain(self.deleted.iter())
+            .map(|address| AccessKey::Account(*address));
+        let slots = self.storage.keys().map(|(address, key)| AccessKey::Storage(*address, *key));
+        accounts.chain(slots).collect()
+    }
+
+    /// Apply the buffered writes to a state
+    fn apply(self, state: &mut dyn State) {
+        for address in self.deleted {
+            state.delete_account(address);
+        }
+        for address in self.created {
+            state.create_account(address);
+        }
+        for (address, balance) in self.balances {
+            state.set_balance(address, balance);
+        }
+        for (address, nonce) in self.nonces {
+            state.set_nonce(address, nonce);
+        }
+        for ((address, key), value) in self.storage {
+            state.set_storage(address, key, value);
+        }
+        for (address, code) in self.code {
+            state.set_code(address, code);
+        }
+    }
+}
+
+/// State overlay recording reads and buffering writes over a shared base
+struct RecordingState<S: State> {
+    /// Pre-transaction state
+    base: Arc<S>,
+    /// Record shared with the worker driving the executor
+    record: Arc<Mutex<AccessRecord>>,
+}
+
+impl<S: State> State for RecordingState<S> {
+    fn get_balance(&self, address: Address) -> U256 {
+        let mut record = self.record.lock();
+        record.reads.insert(AccessKey::Account(address));
+        match record.balances.get(&address) {
+            Some(balance) => *balance,
+            None if record.deleted.contains(&address) => U256::zero(),
+            None => self.base.get_balance(address),
+        }
+    }
+
+    fn set_balance(&mut self, address: Address, balance: U256) {
+        self.record.lock().balances.insert(address, balance);
+    }
+
+    fn get_nonce(&self, address: Address) -> u64 {
+        let mut record = self.record.lock();
+        record.reads.insert(AccessKey::Account(address));
+        match record.nonces.get(&address) {
+            Some(nonce) => *nonce,
+            None if record.deleted.contains(&address) => 0,
+            None => self.base.get_nonce(address),
+        }
+    }
+
+    fn set_nonce(&mut self, address: Address, nonce: u64) {
+        self.record.lock().nonces.insert(address, nonce);
+    }
+
+    fn get_storage(&self, address: Address, key: H256) -> Option<H256> {
+        let mut record = self.record.lock();
+        record.reads.insert(AccessKey::Storage(address, key));
+        match record.storage.get(&(address, key)) {
+            Some(value) => Some(*value),
+            None if record.deleted.contains(&address) => None,
+            None => self.base.get_storage(address, key),
+        }
+    }
+
+    fn set_storage(&mut self, address: Address, key: H256, value: H256) {
+        self.record.lock().storage.insert((address, key), value);
+    }
+
+    fn set_code(&mut self, address: Address, code: Vec<u8>) {
+        self.record.lock().code.insert(address, code);
+    }
+
+    fn get_code(&self, address: Address) -> Vec<u8> {
+        let mut record = self.record.lock();
+        record.reads.insert(AccessKey::Account(address));
+        match record.code.get(&address) {
+            Some(code) => code.clone(),
+            None if record.deleted.contains(&address) => Vec::new(),
+            None => self.base.get_code(address),
+        }
+    }
+
+    fn code_size(&self, address: Address) -> usize {
+        let mut record = self.record.lock();
+        record.reads.insert(AccessKey::Account(address));
+        match record.code.get(&address) {
+            Some(code) => code.len(),
+            None if record.deleted.contains(&address) => 0,
+            None => self.base.code_size(address),
+        }
+    }
+
+    fn exists(&self, address: Address) -> bool {
+        let mut record = self.record.lock();
+        record.reads.insert(AccessKey::Account(address));
+        if record.created.contains(&address) || record.balances.contains_key(&address) || record.nonces.contains_key(&address) {
+            return true;
+        }
+        !record.deleted.contains(&address) && self.base.exists(address)
+    }
+
+    fn create_account(&mut self, address: Address) {
+        let mut record = self.record.lock();
+        record.deleted.remove(&address);
+        record.created.insert(address);
+        record.balances.insert(address, U256::zero());
+        record.nonces.insert(address, 0);
+        record.code.insert(address, Vec::new());
+    }
+
+    fn delete_account(&mut self, address: Address) {
+        let mut record = self.record.lock();
+        record.created.remove(&address);
+        record.balances.remove(&address);
+        record.nonces.remove(&address);
+        record.storage.retain(|(owner, _), _| *owner != address);
+        record.code.remove(&address);
+        record.deleted.insert(address);
+    }
+
+    fn commit(&mut self) {}
+
+    fn revert(&mut self) {}
+}
+
+/// Result of a parallel block execution
+#[derive(Debug, Clone)]
+pub struct ParallelExecutionResult {
+    /// Per-transaction results, in block order
+    pub results: Vec<TransactionExecutionResult>,
+    /// Indices of transactions re-executed after a conflict
+    pub reexecuted: Vec<usize>,
+}
+
+/// Optimistic parallel block executor
+pub struct ParallelExecutor {
+    /// Execution context shared by the block's transactions
+    context: TransactionExecutionContext,
+    /// Number of worker threads
+    threads: usize,
+}
+
+impl ParallelExecutor {
+    /// Create new parallel executor
+    pub fn new(context: TransactionExecutionContext, threads: usize) -> Self {
+        Self {
+            context: TransactionExecutionContext { defer_coinbase_fee: true, ..context },
+            threads: threads.max(1),
+        }
+    }
+
+    /// Create parallel executor using all available cores
+    pub fn new_default(context: TransactionExecutionContext) -> Self {
+        let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Self::new(context, threads)
+    }
+
+    /// Execute a block's transactions, applying their effects to `state`
+    pub fn execute_block<S>(&self, state: &mut S, transactions: &[Transaction]) -> ParallelExecutionResult
+    where
+        S: State + Default + Send + Sync + 'static,
+    {
+        let base = Arc::new(std::mem::take(state));
+        let speculative = self.execute_speculatively(&base, transactions);
+        *state = Arc::try_unwrap(base).ok().expect("workers have released the base state");
+
+        let mut results = Vec::with_capacity(transactions.len());
+        let mut reexecuted = Vec::new();
+        let mut written: HashSet<AccessKey> = HashSet::new();
+
+        for (index, (result, record)) in speculative.into_iter().enumerate() {
+            let (result, record) = if record.reads.is_disjoint(&written) {
+                (result, record)
+            } else {
+                reexecuted.push(index);
+                self.execute_on(state, &transactions[index])
+            };
+
+            written.extend(record.writes());
+            record.apply(state);
+
+            let fee = self.context.coinbase_fee(&result);
+            if !fee.is_zero() {
+                let coinbase = self.context.coinbase;
+                state.set_balance(coinbase, state.get_balance(coinbase) + fee);
+                written.insert(AccessKey::Account(coinbase));
+            }
+            results.push(result);
+        }
+
+        ParallelExecutionResult { results, reexecuted }
+    }
+
+    /// Execute all transactions concurrently against the pre-block state
+    fn execute_speculatively<S>(&self, base: &Arc<S>, transactions: &[Transaction]) -> Vec<(TransactionExecutionResult, AccessRecord)>
+    where
+        S: State + Send + Sync + 'static,
+    {
+        let threads = self.threads.min(transactions.len()).max(1);
+        let mut outcomes: Vec<Option<(TransactionExecutionResult, AccessRecord)>> = (0..transactions.len()).map(|_| None).collect();
+
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads).map(|worker| {
+                let base = Arc::clone(base);
+                scope.spawn(move || {
+                    let record = Arc::new(Mutex::new(AccessRecord::default()));
+                    let overlay = RecordingState { base, record: Arc::clone(&record) };
+                    let mut executor = TransactionExecutor::new(Box::new(overlay), self.context.clone());
+
+                    (worker..transactions.len()).step_by(threads).map(|index| {
+                        let result = executor.execute_transaction(transactions[index].clone())
+                            .unwrap_or_else(|e| TransactionExecutionResult::failed(&e));
+                        (index, result, std::mem::take(&mut *record.lock()))
+                    }).collect::<Vec<_>>()
+                })
+            }).collect();
+
+            for worker in workers {
+                for (index, result, record) in worker.join().expect("execution worker panicked") {
+                    outcomes[index] = Some((result, record));
+                }
+            }
+        });
+
+        outcomes.into_iter().map(|outcome| outcome.expect("every transaction executed")).collect()
+    }
+
+    /// Execute one transaction against the committed state, without applying it
+    fn execute_on<S>(&self, state: &mut S, transaction: &Transaction) -> (TransactionExecutionResult, AccessRecord)
+    where
+        S: State + Default + Send + Sync + 'static,
+    {
+        let base = Arc::new(std::mem::take(state));
+        let record = Arc::new(Mutex::new(AccessRecord::default()));
+        let overlay = RecordingState { base: Arc::clone(&base), record: Arc::clone(&record) };
+
+        let mut executor = TransactionExecutor::new(Box::new(overlay), self.context.clone());
+        let result = executor.execute_transaction(transaction.clone())
+            .unwrap_or_else(|e| TransactionExecutionResult::failed(&e));
+        drop(executor);
+
+        *state = Arc::try_unwrap(base).ok().expect("executor has released the base state");
+        let record = std::mem::take(&mut *record.lock());
+        (result, record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::state::MemoryState;
+
+    fn coinbase() -> Address {
+        Address::repeat_byte(0xcb)
+    }
+
+    fn context() -> TransactionExecutionContext {
+        TransactionExecutionContext { coinbase: coinbase(), ..Default::default() }
+    }
+
+    fn signed(secret: u8, nonce: u64, to: u8) -> Transaction {
+        let mut tx = Transaction::new(
+            U256::from(1000),
+            U256::from(3_000_000_000u64),
+            U256::from(100_000),
+            Address::repeat_byte(to),
+            vec![0xab; 8],
+            U256::from(nonce),
+        );
+        tx.sign_with_secret(&[secret; 32]).unwrap();
+        tx
+    }
+
+    fn funded_state(transactions: &[Transaction]) -> MemoryState {
+        let mut state = MemoryState::new();
+        for tx in transactions {
+            state.set_balance(tx.from(), U256::from(10u64).pow(U256::from(18)));
+        }
+        state
+    }
+
+    fn assert_matches_sequential(transactions: Vec<Transaction>) -> ParallelExecutionResult {
+        let mut sequential = TransactionExecutor::new(Box::new(funded_state(&transactions)), context());
+        let expected = sequential.execute_block_transactions(transactions.clone()).unwrap();
+
+        let mut state = funded_state(&transactions);
+        let parallel = ParallelExecutor::new(context(), 4)
+            .execute_block(&mut state, &transactions);
+
+        assert_eq!(parallel.results.len(), expected.len());
+        for (actual, expected) in parallel.results.iter().zip(&expected) {
+            assert_eq!(actual.transaction_hash, expected.transaction_hash);
+            assert_eq!(actual.success, expected.success);
+            assert_eq!(actual.gas_used, expected.gas_used);
+            assert_eq!(actual.output, expected.output);
+        }
+        for tx in &transactions {
+            for address in [tx.from(), tx.receive_address] {
+                assert_eq!(state.get_balance(address), sequential.state().get_balance(address));
+                assert_eq!(state.get_nonce(address), sequential.state().get_nonce(address));
+            }
+        }
+        assert!(!state.get_balance(coinbase()).is_zero());
+        assert_eq!(state.get_balance(coinbase()), sequential.state().get_balance(coinbase()));
+        parallel
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential_without_conflicts() {
+        let transactions = vec![signed(1, 0, 0x51), signed(2, 0, 0x52), signed(3, 0, 0x53)];
+        let result = assert_matches_sequential(transactions);
+        assert!(result.results.iter().all(|r| r.success));
+        assert!(result.reexecuted.is_empty());
+    }
+
+    #[test]
+    fn test_parallel_matches_sequential_with_conflicts() {
+        // Same sender with consecutive nonces, plus a second sender paying the same recipient
+        let transactions = vec![signed(1, 0, 0x51), signed(1, 1, 0x51), signed(1, 2, 0x51), signed(2, 0, 0x51)];
+        let result = assert_matches_sequential(transactions);
+        assert!(result.results.iter().all(|r| r.success));
+        assert_eq!(result.reexecuted, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_transaction_paying_coinbase_sees_earlier_fees() {
+        // The last transaction transfers to the coinbase, so it must observe the fees before it
+        let transactions = vec![signed(1, 0, 0x51), signed(2, 0, 0x52), signed(3, 0, 0xcb)];
+        let result = assert_matches_sequential(transactions);
+        assert!(result.results.iter().all(|r| r.success));
+        assert_eq!(result.reexecuted, vec![2]);
+    }
+}
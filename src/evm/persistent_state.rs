@@ -1,8 +1,11 @@
 //! Persistent EVM state management using sled database
 
 use crate::{Address, H256, U256, Result, OlympusError};
-use crate::evm::state::State;
+use crate::core::config::{CommitDurability, DatabaseConfig};
+use crate::evm::state::{accounts_root, code_hash, storage_root, State, KECCAK_EMPTY};
 use sled::{Db, Tree};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use serde::{Serialize, Deserialize};
 use bincode;
@@ -17,8 +20,33 @@ pub struct PersistentState {
     storage_tree: Tree,
     /// Code tree
     code_tree: Tree,
+    /// Code length by code hash
+    code_size_tree: Tree,
+    /// How commits reach the disk
+    durability: CommitDurability,
+    /// Writes not yet committed
+    overlay: Overlay,
+    /// Overlay as of each open checkpoint, innermost last
+    checkpoints: Vec<Overlay>,
 }
 
+/// Trees of the state database
+#[derive(Debug, Clone, Copy)]
+enum Column {
+    Accounts,
+    Storage,
+    Code,
+    CodeSize,
+}
+
+impl Column {
+    /// All columns, in overlay order
+    const ALL: [Column; 4] = [Column::Accounts, Column::Storage, Column::Code, Column::CodeSize];
+}
+
+/// Writes buffered per column until commit; `None` removes the key
+type Overlay = [BTreeMap<Vec<u8>, Option<Vec<u8>>>; 4];
+
 /// Account information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
@@ -52,7 +80,29 @@ impl PersistentState {
             sled::open(db_path)
                 .map_err(|e| OlympusError::Database(format!("Failed to open database: {}", e)))?
         );
-        
+        Self::from_db(db)
+    }
+
+    /// Create persistent state configured by a database configuration
+    pub fn open(db_path: &str, config: &DatabaseConfig) -> Result<Self> {
+        let mut state = Self::new(db_path)?;
+        state.set_durability(config.durability);
+        Ok(state)
+    }
+
+    /// Create persistent state backed by a temporary database
+    pub fn temporary() -> Result<Self> {
+        let db = Arc::new(
+            sled::Config::new()
+                .temporary(true)
+                .open()
+                .map_err(|e| OlympusError::Database(format!("Failed to open database: {}", e)))?
+        );
+        Self::from_db(db)
+    }
+
+    /// Open the state trees of a database
+    fn from_db(db: Arc<Db>) -> Result<Self> {
         let accounts_tree = db.open_tree("accounts")
             .map_err(|e| OlympusError::Database(format!("Failed to open accounts tree: {}", e)))?;
         
@@ -61,28 +111,167 @@ impl PersistentState {
         
         let code_tree = db.open_tree("code")
             .map_err(|e| OlympusError::Database(format!("Failed to open code tree: {}", e)))?;
+        
+        let code_size_tree = db.open_tree("code_size")
+            .map_err(|e| OlympusError::Database(format!("Failed to open code size tree: {}", e)))?;
 
         Ok(Self {
             db,
             accounts_tree,
             storage_tree,
             code_tree,
+            code_size_tree,
+            durability: CommitDurability::default(),
+            overlay: Overlay::default(),
+            checkpoints: Vec::new(),
         })
     }
 
+    /// Set how commits reach the disk
+    pub fn set_durability(&mut self, durability: CommitDurability) {
+        self.durability = durability;
+    }
+
+    /// Get how commits reach the disk
+    pub fn durability(&self) -> CommitDurability {
+        self.durability
+    }
+
     /// Create checkpoint
-    pub fn create_checkpoint(&self, block_number: u64) -> Result<StateCheckpoint> {
-        Ok(StateCheckpoint {
+    ///
+    /// Records the writes buffered so far; `revert` discards everything
+    /// written after it.
+    pub fn create_checkpoint(&mut self, block_number: u64) -> Result<StateCheckpoint> {
+        let checkpoint = StateCheckpoint {
             id: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_nanos() as u64,
             block_number,
-            modified_accounts: Vec::new(),
-            modified_storage: Vec::new(),
+            modified_accounts: self.overlay[Column::Accounts as usize].keys()
+                .map(|key| Address::from_slice(key))
+                .collect(),
+            modified_storage: self.overlay[Column::Storage as usize].keys()
+                .map(|key| (Address::from_slice(&key[..20]), H256::from_slice(&key[20..])))
+                .collect(),
+        };
+        self.checkpoint();
+        Ok(checkpoint)
+    }
+
+    /// Iterate over all accounts
+    ///
+    /// Entries that fail to decode are skipped.
+    pub fn iter_accounts(&self) -> impl Iterator<Item = (Address, AccountInfo)> + '_ {
+        self.scan_prefix(Column::Accounts, &[]).filter_map(move |(key, data)| {
+            if key.len() != Address::len_bytes() {
+                return None;
+            }
+            let account = self.deserialize_account(&data).ok()?;
+            Some((Address::from_slice(&key), account))
+        })
+    }
+
+    /// Export an account together with all of its storage slots
+    pub fn export_account(&self, address: Address) -> Result<(AccountInfo, Vec<(H256, H256)>)> {
+        let data = self.read(Column::Accounts, &self.account_key(address))
+            .ok_or_else(|| OlympusError::Database(format!("Account {:?} not found", address)))?;
+        Ok((self.deserialize_account(&data)?, self.storage_slots(address)))
+    }
+
+    /// Stream every account with its storage slots, in address order
+    pub fn export_all(&self) -> impl Iterator<Item = (Address, AccountInfo, Vec<(H256, H256)>)> + '_ {
+        self.iter_accounts().map(move |(address, account)| {
+            let slots = self.storage_slots(address);
+            (address, account, slots)
+        })
+    }
+
+    /// Storage slots of an account, in slot order
+    ///
+    /// Addresses are fixed width, so scanning by address prefix cannot pick up
+    /// another account's slots.
+    fn storage_slots(&self, address: Address) -> Vec<(H256, H256)> {
+        self.scan_prefix(Column::Storage, address.as_bytes()).filter_map(|(key, value)| {
+            (key.len() == 52 && value.len() == 32)
+                .then(|| (H256::from_slice(&key[20..]), H256::from_slice(&value)))
+        }).collect()
+    }
+
+    /// Number of accounts, counted without decoding them
+    pub fn account_count(&self) -> usize {
+        self.scan_prefix(Column::Accounts, &[]).count()
+    }
+
+    /// Get the tree backing a column
+    fn tree(&self, column: Column) -> &Tree {
+        match column {
+            Column::Accounts => &self.accounts_tree,
+            Column::Storage => &self.storage_tree,
+            Column::Code => &self.code_tree,
+            Column::CodeSize => &self.code_size_tree,
+        }
+    }
+
+    /// Read a key, preferring writes not yet committed over the database
+    fn read(&self, column: Column, key: &[u8]) -> Option<Vec<u8>> {
+        match self.overlay[column as usize].get(key) {
+            Some(value) => value.clone(),
+            None => self.tree(column).get(key).ok().flatten().map(|value| value.to_vec()),
+        }
+    }
+
+    /// Buffer a write, or a removal when `value` is `None`, until the next commit
+    fn write(&mut self, column: Column, key: Vec<u8>, value: Option<Vec<u8>>) {
+        self.overlay[column as usize].insert(key, value);
+    }
+
+    /// Entries under a key prefix in key order, with writes not yet committed applied
+    ///
+    /// The database is read lazily, merged with the overlay entry by entry.
+    fn scan_prefix(&self, column: Column, prefix: &[u8]) -> impl Iterator<Item = (Vec<u8>, Vec<u8>)> + '_ {
+        let mut committed = self.tree(column).scan_prefix(prefix)
+            .filter_map(|entry| entry.ok())
+            .map(|(key, value)| (key.to_vec(), value.to_vec()))
+            .peekable();
+        let prefix = prefix.to_vec();
+        let mut pending = self.overlay[column as usize].range(prefix.clone()..)
+            .take_while(move |(key, _)| key.starts_with(&prefix))
+            .peekable();
+
+        std::iter::from_fn(move || loop {
+            let order = match (committed.peek(), pending.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((key, _)), Some((pending_key, _))) => key.cmp(pending_key),
+            };
+            if order == Ordering::Less {
+                return committed.next();
+            }
+            // A pending write shadows the committed entry; a pending removal hides it
+            if order == Ordering::Equal {
+                committed.next();
+            }
+            if let Some((key, Some(value))) = pending.next() {
+                return Some((key.clone(), value.clone()));
+            }
         })
     }
 
+    /// Read an account
+    fn account(&self, address: Address) -> Option<AccountInfo> {
+        let data = self.read(Column::Accounts, &self.account_key(address))?;
+        self.deserialize_account(&data).ok()
+    }
+
+    /// Write an account
+    fn put_account(&mut self, address: Address, account: &AccountInfo) {
+        if let Ok(data) = self.serialize_account(account) {
+            self.write(Column::Accounts, self.account_key(address), Some(data));
+        }
+    }
+
     /// Serialize account info
     fn serialize_account(&self, account: &AccountInfo) -> Result<Vec<u8>> {
         bincode::serialize(account)
@@ -116,128 +305,133 @@ impl PersistentState {
 
 impl State for PersistentState {
     fn get_balance(&self, address: Address) -> U256 {
-        let key = self.account_key(address);
-        if let Ok(Some(data)) = self.accounts_tree.get(&key) {
-            if let Ok(account) = self.deserialize_account(&data) {
-                return account.balance;
-            }
-        }
-        U256::zero()
+        self.account(address).map_or(U256::zero(), |account| account.balance)
     }
     
     fn set_balance(&mut self, address: Address, balance: U256) {
-        let key = self.account_key(address);
-        let mut account = if let Ok(Some(data)) = self.accounts_tree.get(&key) {
-            self.deserialize_account(&data).unwrap_or_default()
-        } else {
-            AccountInfo {
-                balance: U256::zero(),
-                nonce: 0,
-                code_hash: H256::zero(),
-                storage_root: H256::zero(),
-            }
-        };
-        
+        let mut account = self.account(address).unwrap_or_default();
         account.balance = balance;
-        
-        if let Ok(data) = self.serialize_account(&account) {
-            let _ = self.accounts_tree.insert(&key, data);
-        }
+        self.put_account(address, &account);
     }
     
     fn get_nonce(&self, address: Address) -> u64 {
-        let key = self.account_key(address);
-        if let Ok(Some(data)) = self.accounts_tree.get(&key) {
-            if let Ok(account) = self.deserialize_account(&data) {
-                return account.nonce;
-            }
-        }
-        0
+        self.account(address).map_or(0, |account| account.nonce)
     }
     
     fn set_nonce(&mut self, address: Address, nonce: u64) {
-        let key = self.account_key(address);
-        let mut account = if let Ok(Some(data)) = self.accounts_tree.get(&key) {
-            self.deserialize_account(&data).unwrap_or_default()
-        } else {
-            AccountInfo {
-                balance: U256::zero(),
-                nonce: 0,
-                code_hash: H256::zero(),
-                storage_root: H256::zero(),
-            }
-        };
-        
+        let mut account = self.account(address).unwrap_or_default();
         account.nonce = nonce;
-        
-        if let Ok(data) = self.serialize_account(&account) {
-            let _ = self.accounts_tree.insert(&key, data);
-        }
+        self.put_account(address, &account);
     }
     
     fn get_storage(&self, address: Address, key: H256) -> Option<H256> {
-        let storage_key = self.storage_key(address, key);
-        if let Ok(Some(data)) = self.storage_tree.get(&storage_key) {
-            if data.len() == 32 {
-                let mut hash_bytes = [0u8; 32];
-                hash_bytes.copy_from_slice(&data);
-                return Some(H256::from(hash_bytes));
-            }
-        }
-        None
+        let data = self.read(Column::Storage, &self.storage_key(address, key))?;
+        (data.len() == 32).then(|| H256::from_slice(&data))
     }
     
     fn set_storage(&mut self, address: Address, key: H256, value: H256) {
         let storage_key = self.storage_key(address, key);
-        let _ = self.storage_tree.insert(&storage_key, value.as_bytes());
+        self.write(Column::Storage, storage_key, Some(value.as_bytes().to_vec()));
     }
     
-    fn exists(&self, address: Address) -> bool {
-        let key = self.account_key(address);
-        self.accounts_tree.contains_key(&key).unwrap_or(false)
+    /// Store code and record its hash on the account
+    ///
+    /// The code length is indexed by code hash, so `code_size` reads only the
+    /// account and the index.
+    fn set_code(&mut self, address: Address, code: Vec<u8>) {
+        let mut account = self.account(address).unwrap_or_default();
+        account.code_hash = code_hash(&code);
+        self.write(Column::CodeSize, account.code_hash.as_bytes().to_vec(), Some((code.len() as u64).to_be_bytes().to_vec()));
+        self.write(Column::Code, self.code_key(address), Some(code));
+        self.put_account(address, &account);
     }
     
-    fn create_account(&mut self, address: Address) {
-        let key = self.account_key(address);
-        let account = AccountInfo {
-            balance: U256::zero(),
-            nonce: 0,
-            code_hash: H256::zero(),
-            storage_root: H256::zero(),
+    fn get_code(&self, address: Address) -> Vec<u8> {
+        self.read(Column::Code, &self.code_key(address)).unwrap_or_default()
+    }
+    
+    fn code_size(&self, address: Address) -> usize {
+        let Some(account) = self.account(address) else {
+            return 0;
         };
-        
-        if let Ok(data) = self.serialize_account(&account) {
-            let _ = self.accounts_tree.insert(&key, data);
+        if account.code_hash == KECCAK_EMPTY {
+            return 0;
+        }
+        match self.read(Column::CodeSize, account.code_hash.as_bytes()) {
+            Some(size) if size.len() == 8 => u64::from_be_bytes(size.as_slice().try_into().unwrap()) as usize,
+            _ => 0,
         }
     }
     
+    fn get_code_hash(&self, address: Address) -> H256 {
+        self.account(address).map(|account| account.code_hash).unwrap_or(KECCAK_EMPTY)
+    }
+    
+    fn exists(&self, address: Address) -> bool {
+        self.read(Column::Accounts, &self.account_key(address)).is_some()
+    }
+    
+    fn create_account(&mut self, address: Address) {
+        self.put_account(address, &AccountInfo::default());
+        self.write(Column::Code, self.code_key(address), None);
+    }
+    
     fn delete_account(&mut self, address: Address) {
-        let key = self.account_key(address);
-        let _ = self.accounts_tree.remove(&key);
+        self.write(Column::Accounts, self.account_key(address), None);
         
         // Remove all storage entries for this address
-        let prefix = address.as_bytes();
-        let _ = self.storage_tree.scan_prefix(prefix).for_each(|item| {
-            if let Ok((key, _)) = item {
-                let _ = self.storage_tree.remove(&key);
-            }
-        });
+        let keys: Vec<Vec<u8>> = self.scan_prefix(Column::Storage, address.as_bytes()).map(|(key, _)| key).collect();
+        for key in keys {
+            self.write(Column::Storage, key, None);
+        }
         
         // Remove code
-        let code_key = self.code_key(address);
-        let _ = self.code_tree.remove(&code_key);
+        self.write(Column::Code, self.code_key(address), None);
+    }
+    
+    fn state_root(&self) -> H256 {
+        accounts_root(self.iter_accounts().map(|(address, account)| {
+            (address, account.balance, account.nonce, account.code_hash, storage_root(self.storage_slots(address)))
+        }))
     }
     
+    fn checkpoint(&mut self) {
+        self.checkpoints.push(self.overlay.clone());
+    }
+    
+    /// Commit written state
+    ///
+    /// Buffered writes are applied to each tree as one batch. In durable mode
+    /// the database is then fsynced before returning, so a committed state
+    /// survives a crash. In fast mode the commit only returns, leaving sled's
+    /// background flusher to persist it shortly after: commits are much cheaper,
+    /// but a crash may lose the most recent ones.
     fn commit(&mut self) {
-        let _ = self.accounts_tree.flush();
-        let _ = self.storage_tree.flush();
-        let _ = self.code_tree.flush();
+        let overlay = std::mem::take(&mut self.overlay);
+        self.checkpoints.clear();
+        for (column, writes) in Column::ALL.into_iter().zip(overlay) {
+            let mut batch = sled::Batch::default();
+            for (key, value) in writes {
+                match value {
+                    Some(value) => batch.insert(key, value),
+                    None => batch.remove(key),
+                }
+            }
+            let _ = self.tree(column).apply_batch(batch);
+        }
+
+        match self.durability {
+            CommitDurability::Durable => {
+                let _ = self.db.flush();
+            }
+            CommitDurability::Fast => {}
+        }
     }
     
+    /// Discard the writes made since the last checkpoint, or since the last
+    /// commit when there is none
     fn revert(&mut self) {
-        // For persistent state, revert is more complex
-        // In a full implementation, you would restore from checkpoint
-        // For now, this is a no-op
+        self.overlay = self.checkpoints.pop().unwrap_or_default();
     }
 }
 
@@ -246,8 +440,8 @@ impl Default for AccountInfo {
         Self {
             balance: U256::zero(),
             nonce: 0,
-            code_hash: H256::zero(),
-            storage_root: H256::zero(),
+            code_hash: KECCAK_EMPTY,
+            storage_root: crate::common::EMPTY_TRIE_ROOT,
         }
     }
 }
@@ -293,14 +487,17 @@ impl StateManager {
         
         let checkpoint_id = checkpoint.id;
         self.checkpoints.push(checkpoint);
+        self.current_state.checkpoint();
         Ok(checkpoint_id)
     }
 
-    /// Revert to checkpoint
+    /// Revert to checkpoint, discarding it and every later one
     pub fn revert_to_checkpoint(&mut self, checkpoint_id: u64) -> Result<()> {
         if let Some(pos) = self.checkpoints.iter().position(|c| c.id == checkpoint_id) {
+            for _ in pos..self.checkpoints.len() {
+                self.current_state.revert();
+            }
             self.checkpoints.truncate(pos);
-            self.current_state.revert();
             Ok(())
         } else {
             Err(OlympusError::Database("Checkpoint not found".to_string()))
@@ -313,3 +510,4 @@ impl StateManager {
         self.checkpoints.clear();
     }
 }
+
@@ -4,7 +4,7 @@ use crate::{Address, U256, Result, OlympusError};
 use sha3::Digest;
 use std::collections::HashMap;
 use num_bigint::{BigUint};
-use num_traits::{Zero, Num};
+use num_traits::Zero;
 
 /// Trait for precompiled contracts
 pub trait PrecompiledContract {
@@ -43,14 +43,15 @@ pub struct Sha256Contract;
 
 impl PrecompiledContract for Sha256Contract {
     fn execute(&self, input: &[u8]) -> Result<Vec<u8>> {
-        let mut hasher = sha3::Sha3_256::new();
+        let mut hasher = sha2::Sha256::new();
         hasher.update(input);
         let result = hasher.finalize();
         Ok(result.to_vec())
     }
 
     fn gas_cost(&self, input: &[u8]) -> U256 {
-        U256::from(60 + (input.len() / 32) * 12)
+        // Charged per started 32-byte word
+        U256::from(60 + input.len().div_ceil(32) * 12)
     }
 }
 
@@ -71,7 +72,8 @@ impl PrecompiledContract for Ripemd160Contract {
     }
 
     fn gas_cost(&self, input: &[u8]) -> U256 {
-        U256::from(600 + (input.len() / 32) * 120)
+        // Charged per started 32-byte word
+        U256::from(600 + input.len().div_ceil(32) * 120)
     }
 }
 
@@ -84,41 +86,40 @@ impl PrecompiledContract for IdentityContract {
     }
 
     fn gas_cost(&self, input: &[u8]) -> U256 {
-        U256::from(15 + (input.len() / 32) * 3)
+        // Charged per started 32-byte word
+        U256::from(15 + input.len().div_ceil(32) * 3)
     }
 }
 
+/// Longest base, exponent or modulus MODEXP accepts, in bytes
+pub const MAX_MODEXP_LENGTH: usize = 1024;
+
 /// MODEXP precompiled contract (address 0x05)
 pub struct ModExpContract;
 
 impl PrecompiledContract for ModExpContract {
+    /// Inputs with a length above `MAX_MODEXP_LENGTH` produce empty output.
+    /// Operands cut short by the end of the input are zero-padded, as the
+    /// EVM reads past the end of call data.
     fn execute(&self, input: &[u8]) -> Result<Vec<u8>> {
-        if input.len() < 96 {
-            return Ok(vec![0u8; 32]);
-        }
-        
-        // Extract base, exponent, modulus lengths
-        let base_len = u32::from_be_bytes([input[0], input[1], input[2], input[3]]) as usize;
-        let exp_len = u32::from_be_bytes([input[4], input[5], input[6], input[7]]) as usize;
-        let mod_len = u32::from_be_bytes([input[8], input[9], input[10], input[11]]) as usize;
-        
-        if input.len() < 32 + base_len + exp_len + mod_len {
-            return Ok(vec![0u8; 32]);
+        let limit = U256::from(MAX_MODEXP_LENGTH);
+        let [base_len, exp_len, mod_len] = modexp_lengths(input);
+        if base_len > limit || exp_len > limit || mod_len > limit {
+            return Ok(Vec::new());
         }
-        
-        // Extract the actual values
-        let base_start = 32;
-        let exp_start = base_start + base_len;
-        let mod_start = exp_start + exp_len;
-        
-        let base_bytes = &input[base_start..exp_start];
-        let exp_bytes = &input[exp_start..mod_start];
-        let mod_bytes = &input[mod_start..mod_start + mod_len];
+        let (base_len, exp_len, mod_len) = (base_len.as_usize(), exp_len.as_usize(), mod_len.as_usize());
+
+        // Bound the operand offsets before padding the input out to them
+        let base_start: usize = 96;
+        let Some(exp_start) = base_start.checked_add(base_len) else { return Ok(Vec::new()) };
+        let Some(mod_start) = exp_start.checked_add(exp_len) else { return Ok(Vec::new()) };
+        let Some(end) = mod_start.checked_add(mod_len) else { return Ok(Vec::new()) };
+        let input = read_padded(input, 0, end);
         
         // Convert to BigUint
-        let base = BigUint::from_bytes_be(base_bytes);
-        let exponent = BigUint::from_bytes_be(exp_bytes);
-        let modulus = BigUint::from_bytes_be(mod_bytes);
+        let base = BigUint::from_bytes_be(&input[base_start..exp_start]);
+        let exponent = BigUint::from_bytes_be(&input[exp_start..mod_start]);
+        let modulus = BigUint::from_bytes_be(&input[mod_start..end]);
         
         // Handle special cases
         if modulus.is_zero() {
@@ -137,67 +138,77 @@ impl PrecompiledContract for ModExpContract {
         Ok(output)
     }
 
+    /// EIP-2565 pricing
+    ///
+    /// The multiplication complexity grows with the square of the longer of
+    /// base and modulus in 8-byte words; the iteration count follows the
+    /// exponent's bit length.
     fn gas_cost(&self, input: &[u8]) -> U256 {
-        if input.len() < 96 {
-            return U256::from(200);
+        let [base_len, exp_len, mod_len] = modexp_lengths(input);
+        let too_long = U256::from(u64::MAX);
+        if base_len > too_long || exp_len > too_long || mod_len > too_long {
+            return U256::MAX;
         }
-        
-        let base_len = u32::from_be_bytes([input[0], input[1], input[2], input[3]]) as usize;
-        let exp_len = u32::from_be_bytes([input[4], input[5], input[6], input[7]]) as usize;
-        let mod_len = u32::from_be_bytes([input[8], input[9], input[10], input[11]]) as usize;
-        
-        // Gas cost calculation based on Ethereum specification
-        let gas_cost = (base_len + mod_len) * 50 + exp_len * 10;
-        U256::from(200 + gas_cost)
+
+        let words = (base_len.max(mod_len) + 7) / 8;
+        let complexity = words * words;
+
+        // Only the first 32 bytes of the exponent are inspected
+        let head_len = exp_len.min(U256::from(32)).as_usize();
+        let head_offset = base_len.saturating_add(U256::from(96));
+        let head = if head_offset > U256::from(input.len()) {
+            U256::zero()
+        } else {
+            U256::from_big_endian(&read_padded(input, head_offset.as_usize(), head_len))
+        };
+        let head_bits = U256::from(head.bits().saturating_sub(1));
+        let iterations = if exp_len <= U256::from(32) {
+            head_bits
+        } else {
+            (exp_len - 32) * 8 + head_bits
+        };
+
+        let gas = complexity.saturating_mul(iterations.max(U256::one())) / 3;
+        gas.max(U256::from(200))
     }
 }
 
+/// Base, exponent and modulus lengths from the MODEXP header
+///
+/// Each length is a 32-byte big-endian word; missing bytes read as zero.
+fn modexp_lengths(input: &[u8]) -> [U256; 3] {
+    let header = read_padded(input, 0, 96);
+    [
+        U256::from_big_endian(&header[0..32]),
+        U256::from_big_endian(&header[32..64]),
+        U256::from_big_endian(&header[64..96]),
+    ]
+}
+
+/// Read `len` bytes at `offset`, zero-padding past the end of the input
+fn read_padded(input: &[u8], offset: usize, len: usize) -> Vec<u8> {
+    let mut output = vec![0u8; len];
+    if offset < input.len() {
+        let available = &input[offset..input.len().min(offset.saturating_add(len))];
+        output[..available.len()].copy_from_slice(available);
+    }
+    output
+}
+
 /// ECADD precompiled contract (address 0x06)
 pub struct EcAddContract;
 
 impl PrecompiledContract for EcAddContract {
+    /// Add two G1 points
+    ///
+    /// Input shorter than 128 bytes is zero-padded. Coordinates not below the
+    /// field prime or points off the curve yield all zeros, and (0, 0)
+    /// encodes the point at infinity.
     fn execute(&self, input: &[u8]) -> Result<Vec<u8>> {
-        if input.len() < 128 {
-            return Ok(vec![0u8; 64]);
-        }
-        
-        // Extract two points (x1, y1) and (x2, y2)
-        let x1_bytes = &input[0..32];
-        let y1_bytes = &input[32..64];
-        let x2_bytes = &input[64..96];
-        let y2_bytes = &input[96..128];
-        
-        // Convert to BigUint
-        let x1 = BigUint::from_bytes_be(x1_bytes);
-        let y1 = BigUint::from_bytes_be(y1_bytes);
-        let x2 = BigUint::from_bytes_be(x2_bytes);
-        let y2 = BigUint::from_bytes_be(y2_bytes);
-        
-        // BN254 curve parameters
-        let p = BigUint::from_str_radix("21888242871839275222246405745257275088696311157297823662689037894645226208583", 10)
-            .map_err(|_| OlympusError::EvmExecution("Invalid curve parameter".to_string()))?;
-        
-        // Check if points are on curve
-        if !is_point_on_curve(&x1, &y1, &p) || !is_point_on_curve(&x2, &y2, &p) {
-            return Ok(vec![0u8; 64]);
-        }
-        
-        // Perform elliptic curve point addition
-        match ec_add(&x1, &y1, &x2, &y2, &p) {
-            Some((x3, y3)) => {
-                let mut result = vec![0u8; 64];
-                let x3_bytes = x3.to_bytes_be();
-                let y3_bytes = y3.to_bytes_be();
-                
-                let x_start = 32 - x3_bytes.len().min(32);
-                let y_start = 64 - y3_bytes.len().min(32);
-                
-                result[x_start..x_start + x3_bytes.len().min(32)].copy_from_slice(&x3_bytes[..x3_bytes.len().min(32)]);
-                result[y_start..y_start + y3_bytes.len().min(32)].copy_from_slice(&y3_bytes[..y3_bytes.len().min(32)]);
-                
-                Ok(result)
-            }
-            None => Ok(vec![0u8; 64]),
+        let input = read_padded(input, 0, 128);
+        match (decode_bn254_g1(&input[..64]), decode_bn254_g1(&input[64..])) {
+            (Some(a), Some(b)) => Ok(encode_bn254_g1((a + b).into())),
+            _ => Ok(vec![0u8; 64]),
         }
     }
 
@@ -210,43 +221,17 @@ impl PrecompiledContract for EcAddContract {
 pub struct EcMulContract;
 
 impl PrecompiledContract for EcMulContract {
+    /// Multiply a G1 point by a scalar
+    ///
+    /// Input is handled as for ECADD; a zero scalar yields the point at infinity.
     fn execute(&self, input: &[u8]) -> Result<Vec<u8>> {
-        if input.len() < 96 {
-            return Ok(vec![0u8; 64]);
-        }
-        
-        // Extract point (x, y) and scalar k
-        let x_bytes = &input[0..32];
-        let y_bytes = &input[32..64];
-        let k_bytes = &input[64..96];
-        
-        let x = BigUint::from_bytes_be(x_bytes);
-        let y = BigUint::from_bytes_be(y_bytes);
-        let k = BigUint::from_bytes_be(k_bytes);
-        
-        // BN254 curve parameters
-        let p = BigUint::from_str_radix("21888242871839275222246405745257275088696311157297823662689037894645226208583", 10)
-            .map_err(|_| OlympusError::EvmExecution("Invalid curve parameter".to_string()))?;
-        
-        // Check if point is on curve
-        if !is_point_on_curve(&x, &y, &p) {
-            return Ok(vec![0u8; 64]);
-        }
-        
-        // Perform scalar multiplication
-        match ec_mul(&x, &y, &k, &p) {
-            Some((x3, y3)) => {
-                let mut result = vec![0u8; 64];
-                let x3_bytes = x3.to_bytes_be();
-                let y3_bytes = y3.to_bytes_be();
-                
-                let x_start = 32 - x3_bytes.len().min(32);
-                let y_start = 64 - y3_bytes.len().min(32);
-                
-                result[x_start..x_start + x3_bytes.len().min(32)].copy_from_slice(&x3_bytes[..x3_bytes.len().min(32)]);
-                result[y_start..y_start + y3_bytes.len().min(32)].copy_from_slice(&y3_bytes[..y3_bytes.len().min(32)]);
-                
-                Ok(result)
+        use ark_ff::PrimeField;
+
+        let input = read_padded(input, 0, 96);
+        match decode_bn254_g1(&input[..64]) {
+            Some(point) => {
+                let scalar = ark_bn254::Fr::from_be_bytes_mod_order(&input[64..96]);
+                Ok(encode_bn254_g1((point * scalar).into()))
             }
             None => Ok(vec![0u8; 64]),
         }
@@ -261,15 +246,36 @@ impl PrecompiledContract for EcMulContract {
 pub struct EcPairingContract;
 
 impl PrecompiledContract for EcPairingContract {
+    /// Check that the product of pairings of k (G1, G2) point pairs is the identity
+    ///
+    /// Malformed input, including points off the curve or outside the prime-order
+    /// subgroup, yields all zeros.
     fn execute(&self, input: &[u8]) -> Result<Vec<u8>> {
-        if input.len() < 192 {
-            return Ok(vec![0u8; 32]);
-        }
-        
-        // For now, return a placeholder implementation
-        // Full pairing implementation would require more complex elliptic curve operations
+        use ark_bn254::Bn254;
+        use ark_ec::pairing::Pairing;
+        use ark_ff::One;
+
         let mut result = vec![0u8; 32];
-        result[31] = 1; // Return 1 (true) as placeholder
+        if !input.len().is_multiple_of(192) {
+            return Ok(result);
+        }
+
+        let mut g1_points = Vec::with_capacity(input.len() / 192);
+        let mut g2_points = Vec::with_capacity(input.len() / 192);
+        for chunk in input.chunks(192) {
+            match (decode_bn254_g1(&chunk[..64]), decode_bn254_g2(&chunk[64..])) {
+                (Some(g1), Some(g2)) => {
+                    g1_points.push(g1);
+                    g2_points.push(g2);
+                }
+                _ => return Ok(result),
+            }
+        }
+
+        let pairing = Bn254::multi_pairing(g1_points, g2_points);
+        if pairing.0.is_one() {
+            result[31] = 1;
+        }
         Ok(result)
     }
 
@@ -278,152 +284,140 @@ impl PrecompiledContract for EcPairingContract {
     }
 }
 
-/// Helper function to check if a point is on the BN254 curve
-fn is_point_on_curve(x: &BigUint, y: &BigUint, p: &BigUint) -> bool {
-    // BN254 curve equation: y^2 = x^3 + 3 (mod p)
-    let y_squared = (y * y) % p;
-    let x_cubed = (x * x * x) % p;
-    let rhs = (x_cubed + BigUint::from(3u32)) % p;
-    
-    y_squared == rhs
+/// Decode a BN254 base field element, rejecting values not below the modulus
+fn decode_bn254_fq(bytes: &[u8]) -> Option<ark_bn254::Fq> {
+    use ark_ff::PrimeField;
+
+    let modulus: BigUint = ark_bn254::Fq::MODULUS.into();
+    (BigUint::from_bytes_be(bytes) < modulus).then(|| ark_bn254::Fq::from_be_bytes_mod_order(bytes))
 }
 
-/// Helper function for elliptic curve point addition
-fn ec_add(x1: &BigUint, y1: &BigUint, x2: &BigUint, y2: &BigUint, p: &BigUint) -> Option<(BigUint, BigUint)> {
-    // Handle point at infinity
-    if x1 == &BigUint::zero() && y1 == &BigUint::zero() {
-        return Some((x2.clone(), y2.clone()));
-    }
-    if x2 == &BigUint::zero() && y2 == &BigUint::zero() {
-        return Some((x1.clone(), y1.clone()));
+/// Decode a G1 point `x || y`, with all zeros encoding the point at infinity
+fn decode_bn254_g1(bytes: &[u8]) -> Option<ark_bn254::G1Affine> {
+    if bytes.iter().all(|&b| b == 0) {
+        return Some(ark_bn254::G1Affine::identity());
     }
-    
-    // Handle same point (point doubling)
-    if x1 == x2 {
-        if y1 == y2 {
-            return ec_double(x1, y1, p);
-        } else {
-            return Some((BigUint::zero(), BigUint::zero())); // Point at infinity
-        }
-    }
-    
-    // Calculate slope
-    let numerator = (y2 + p - y1) % p;
-    let denominator = (x2 + p - x1) % p;
-    
-    // Calculate modular inverse of denominator
-    let inv_denominator = mod_inverse(&denominator, p)?;
-    let slope = (numerator * inv_denominator) % p;
-    
-    // Calculate x3 and y3
-    let x3 = (slope.clone() * slope.clone() + p - x1 - x2) % p;
-    let y3 = (slope * (x1 + p - &x3) + p - y1) % p;
-    
-    Some((x3, y3))
+    let point = ark_bn254::G1Affine::new_unchecked(decode_bn254_fq(&bytes[..32])?, decode_bn254_fq(&bytes[32..64])?);
+    (point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()).then_some(point)
 }
 
-/// Helper function for elliptic curve point doubling
-fn ec_double(x: &BigUint, y: &BigUint, p: &BigUint) -> Option<(BigUint, BigUint)> {
-    if y == &BigUint::zero() {
-        return Some((BigUint::zero(), BigUint::zero())); // Point at infinity
+/// Encode a G1 point as `x || y`, with the point at infinity as all zeros
+fn encode_bn254_g1(point: ark_bn254::G1Affine) -> Vec<u8> {
+    use ark_ff::{BigInteger, PrimeField};
+
+    if point.infinity {
+        return vec![0u8; 64];
     }
-    
-    // Calculate slope for doubling
-    let numerator = (BigUint::from(3u32) * x * x) % p;
-    let denominator = (BigUint::from(2u32) * y) % p;
-    
-    let inv_denominator = mod_inverse(&denominator, p)?;
-    let slope = (numerator * inv_denominator) % p;
-    
-    // Calculate x3 and y3
-    let x3 = (slope.clone() * slope.clone() + p - BigUint::from(2u32) * x) % p;
-    let y3 = (slope * (x + p - &x3) + p - y) % p;
-    
-    Some((x3, y3))
+    [point.x.into_bigint().to_bytes_be(), point.y.into_bigint().to_bytes_be()].concat()
 }
 
-/// Helper function for scalar multiplication
-fn ec_mul(x: &BigUint, y: &BigUint, k: &BigUint, p: &BigUint) -> Option<(BigUint, BigUint)> {
-    let mut result_x = BigUint::zero();
-    let mut result_y = BigUint::zero();
-    let mut addend_x = x.clone();
-    let mut addend_y = y.clone();
-    let mut scalar = k.clone();
-    
-    while !scalar.is_zero() {
-        if &scalar & &BigUint::from(1u32) != BigUint::zero() {
-            if result_x.is_zero() && result_y.is_zero() {
-                result_x = addend_x.clone();
-                result_y = addend_y.clone();
-            } else {
-                let (new_x, new_y) = ec_add(&result_x, &result_y, &addend_x, &addend_y, p)?;
-                result_x = new_x;
-                result_y = new_y;
-            }
-        }
-        
-        let (double_x, double_y) = ec_double(&addend_x, &addend_y, p)?;
-        addend_x = double_x;
-        addend_y = double_y;
-        scalar >>= 1;
+/// Decode a G2 point `x_im || x_re || y_im || y_re`, with all zeros encoding the point at infinity
+fn decode_bn254_g2(bytes: &[u8]) -> Option<ark_bn254::G2Affine> {
+    use ark_bn254::Fq2;
+
+    if bytes.iter().all(|&b| b == 0) {
+        return Some(ark_bn254::G2Affine::identity());
     }
-    
-    Some((result_x, result_y))
+    let x = Fq2::new(decode_bn254_fq(&bytes[32..64])?, decode_bn254_fq(&bytes[..32])?);
+    let y = Fq2::new(decode_bn254_fq(&bytes[96..128])?, decode_bn254_fq(&bytes[64..96])?);
+    let point = ark_bn254::G2Affine::new_unchecked(x, y);
+    (point.is_on_curve() && point.is_in_correct_subgroup_assuming_on_curve()).then_some(point)
 }
 
-/// Helper function to calculate modular inverse using extended Euclidean algorithm
-fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
-    if a.is_zero() {
-        return None;
-    }
-    
-    let mut old_r = a.clone();
-    let mut r = m.clone();
-    let mut old_s = BigUint::from(1u32);
-    let mut s = BigUint::zero();
-    
-    while !r.is_zero() {
-        let quotient = &old_r / &r;
-        let temp_r = old_r.clone();
-        old_r = r.clone();
-        r = temp_r - &quotient * &r;
-        
-        let temp_s = old_s.clone();
-        old_s = s.clone();
-        s = temp_s - &quotient * &s;
+/// BLAKE2F precompiled contract (address 0x09)
+pub struct Blake2FContract;
+
+/// Length of the BLAKE2 F input: rounds(4) || h(64) || m(128) || t(16) || f(1)
+const BLAKE2F_INPUT_LENGTH: usize = 213;
+
+/// BLAKE2b initialization vector
+const BLAKE2B_IV: [u64; 8] = [
+    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,
+];
+
+/// BLAKE2b message word schedule, one permutation per round
+const BLAKE2B_SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+/// BLAKE2b mixing function G
+fn blake2b_mix(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+/// BLAKE2b compression function F with a configurable number of rounds (EIP-152)
+fn blake2b_compress(rounds: u32, h: &mut [u64; 8], m: &[u64; 16], t: [u64; 2], final_block: bool) {
+    let mut v = [0u64; 16];
+    v[..8].copy_from_slice(h);
+    v[8..].copy_from_slice(&BLAKE2B_IV);
+    v[12] ^= t[0];
+    v[13] ^= t[1];
+    if final_block {
+        v[14] = !v[14];
     }
-    
-    if old_r > BigUint::from(1u32) {
-        return None; // No inverse exists
+
+    for round in 0..rounds as usize {
+        let s = &BLAKE2B_SIGMA[round % 10];
+        blake2b_mix(&mut v, 0, 4, 8, 12, m[s[0]], m[s[1]]);
+        blake2b_mix(&mut v, 1, 5, 9, 13, m[s[2]], m[s[3]]);
+        blake2b_mix(&mut v, 2, 6, 10, 14, m[s[4]], m[s[5]]);
+        blake2b_mix(&mut v, 3, 7, 11, 15, m[s[6]], m[s[7]]);
+        blake2b_mix(&mut v, 0, 5, 10, 15, m[s[8]], m[s[9]]);
+        blake2b_mix(&mut v, 1, 6, 11, 12, m[s[10]], m[s[11]]);
+        blake2b_mix(&mut v, 2, 7, 8, 13, m[s[12]], m[s[13]]);
+        blake2b_mix(&mut v, 3, 4, 9, 14, m[s[14]], m[s[15]]);
     }
-    
-    if old_s < BigUint::zero() {
-        Some(old_s + m)
-    } else {
-        Some(old_s)
+
+    for i in 0..8 {
+        h[i] ^= v[i] ^ v[i + 8];
     }
 }
 
-/// BLAKE2F precompiled contract (address 0x09)
-pub struct Blake2FContract;
-
 impl PrecompiledContract for Blake2FContract {
     fn execute(&self, input: &[u8]) -> Result<Vec<u8>> {
-        if input.len() < 213 {
-            return Ok(vec![0u8; 64]);
+        if input.len() != BLAKE2F_INPUT_LENGTH {
+            return Err(OlympusError::EvmExecution(format!("Invalid BLAKE2 F input length: {}", input.len())));
         }
-        
-        // For now, return zero (placeholder implementation)
-        Ok(vec![0u8; 64])
+        let final_block = match input[212] {
+            0 => false,
+            1 => true,
+            flag => return Err(OlympusError::EvmExecution(format!("Invalid BLAKE2 F final block flag: {}", flag))),
+        };
+
+        let word = |offset: usize| u64::from_le_bytes(input[offset..offset + 8].try_into().unwrap());
+        let rounds = u32::from_be_bytes(input[0..4].try_into().unwrap());
+        let mut h: [u64; 8] = std::array::from_fn(|i| word(4 + i * 8));
+        let m: [u64; 16] = std::array::from_fn(|i| word(68 + i * 8));
+        let t = [word(196), word(204)];
+
+        blake2b_compress(rounds, &mut h, &m, t, final_block);
+        Ok(h.iter().flat_map(|word| word.to_le_bytes()).collect())
     }
 
     fn gas_cost(&self, input: &[u8]) -> U256 {
-        if input.len() < 213 {
-            return U256::from(0);
+        if input.len() != BLAKE2F_INPUT_LENGTH {
+            return U256::zero();
         }
-        
-        let rounds = u32::from_be_bytes([input[212], input[213], input[214], input[215]]);
-        U256::from(rounds)
+
+        // One gas per round
+        U256::from(u32::from_be_bytes(input[0..4].try_into().unwrap()))
     }
 }
 
@@ -443,4 +437,196 @@ pub fn create_precompiled_registry() -> HashMap<Address, Box<dyn PrecompiledCont
     registry.insert(Address::from([0x09; 20]), Box::new(Blake2FContract) as Box<dyn PrecompiledContract>);
     
     registry
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::H256;
+
+    #[test]
+    fn test_sha256_vectors() {
+        assert_eq!(
+            Sha256Contract.execute(&[]).unwrap(),
+            hex::decode("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855").unwrap()
+        );
+        assert_eq!(
+            Sha256Contract.execute(b"abc").unwrap(),
+            hex::decode("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_blake2f_eip152_vectors() {
+        // h from the BLAKE2b-512 IV and parameter block, m = "abc", t = 3
+        let input = |rounds: &str, flag: &str| hex::decode(format!(
+            "{}{}{:0<256}{:0<32}{}",
+            rounds,
+            "48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b",
+            "616263",
+            "03",
+            flag
+        )).unwrap();
+        let vectors = [
+            ("00000000", "01", "08c9bcf367e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d282e6ad7f520e511f6c3e2b8c68059b9442be0454267ce079217e1319cde05b"),
+            ("0000000c", "01", "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"),
+            ("0000000c", "00", "75ab69d3190a562c51aef8d88f1c2775876944407270c42c9844252c26d2875298743e7f6d5ea2f2d3e8d226039cd31b4e426ac4f2d3d666a610c2116fde4735"),
+            ("00000001", "01", "b63a380cb2897d521994a85234ee2c181b5f844d2c624c002677e9703449d2fba551b3a8333bcdf5f2f7e08993d53923de3d64fcc68c034e717b9293fed7a421"),
+        ];
+        for (rounds, flag, expected) in vectors {
+            let input = input(rounds, flag);
+            assert_eq!(Blake2FContract.execute(&input).unwrap(), hex::decode(expected).unwrap());
+            assert_eq!(Blake2FContract.gas_cost(&input), U256::from(u32::from_str_radix(rounds, 16).unwrap()));
+        }
+
+        // Wrong lengths and an invalid final block flag are rejected
+        let valid = input("0000000c", "01");
+        assert!(Blake2FContract.execute(&valid[..212]).is_err());
+        assert!(Blake2FContract.execute(&[valid.clone(), vec![0]].concat()).is_err());
+        assert!(Blake2FContract.execute(&input("0000000c", "02")).is_err());
+        assert_eq!(Blake2FContract.gas_cost(&valid[..212]), U256::zero());
+    }
+
+    #[test]
+    fn test_modexp_eip2565_gas_vectors() {
+        let input = |base: &[u8], exp: &[u8], modulus: &[u8]| {
+            let mut input = Vec::new();
+            for len in [base.len(), exp.len(), modulus.len()] {
+                input.extend_from_slice(&H256::from_low_u64_be(len as u64).0);
+            }
+            [input, base.to_vec(), exp.to_vec(), modulus.to_vec()].concat()
+        };
+
+        // EIP-198 examples: 3^(p-1) mod p, and the same with an empty base
+        let prime = hex::decode("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f").unwrap();
+        let exponent = hex::decode("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e").unwrap();
+        assert_eq!(ModExpContract.gas_cost(&input(&[0x03], &exponent, &prime)), U256::from(1360));
+        assert_eq!(ModExpContract.gas_cost(&input(&[], &exponent, &prime)), U256::from(1360));
+
+        // Nagydani vectors: base and modulus of equal size, squared, cubed and raised to 0x10001
+        let vectors = [
+            (64, 200, 200, 341),
+            (128, 200, 200, 1365),
+            (256, 341, 341, 5461),
+            (512, 1365, 1365, 21845),
+            (1024, 5461, 5461, 87381),
+        ];
+        for (len, square, qube, pow) in vectors {
+            let operand = vec![0xff; len];
+            assert_eq!(ModExpContract.gas_cost(&input(&operand, &[0x02], &operand)), U256::from(square));
+            assert_eq!(ModExpContract.gas_cost(&input(&operand, &[0x03], &operand)), U256::from(qube));
+            assert_eq!(ModExpContract.gas_cost(&input(&operand, &[0x01, 0x00, 0x01], &operand)), U256::from(pow));
+        }
+
+        // Long exponents count 8 iterations per byte past the first 32
+        assert_eq!(ModExpContract.gas_cost(&input(&[0xff; 64], &[0x00; 40], &[0xff; 64])), U256::from(64 * 64 / 3));
+        assert_eq!(ModExpContract.gas_cost(&[]), U256::from(200));
+    }
+
+    #[test]
+    fn test_modexp_rejects_oversized_lengths_and_pads_truncated_input() {
+        let header = |lengths: [U256; 3]| {
+            let mut input = Vec::new();
+            for len in lengths {
+                let mut word = [0u8; 32];
+                len.to_big_endian(&mut word);
+                input.extend_from_slice(&word);
+            }
+            input
+        };
+        let one = U256::one();
+
+        // 3^2 mod 5
+        let valid = [header([one, one, one]), vec![0x03, 0x02, 0x05]].concat();
+        assert_eq!(ModExpContract.execute(&valid).unwrap(), vec![0x04]);
+
+        // Absurd lengths and lengths summing past usize
+        for lengths in [[U256::MAX, one, one], [one, U256::from(u32::MAX), one], [one, one, U256::from(u64::MAX)]] {
+            assert!(ModExpContract.execute(&header(lengths)).unwrap().is_empty());
+        }
+        let limit = U256::from(MAX_MODEXP_LENGTH);
+        assert!(ModExpContract.execute(&header([one, one, limit + 1])).unwrap().is_empty());
+
+        // Truncated operands read as zero-padded: a missing modulus is zero, and
+        // a two-byte modulus cut after 0x01 is 0x0100
+        assert_eq!(ModExpContract.execute(&valid[..98]).unwrap(), vec![0x00]);
+        assert_eq!(ModExpContract.execute(&header([limit, limit, limit])).unwrap(), vec![0u8; MAX_MODEXP_LENGTH]);
+        let truncated = [header([one, one, U256::from(2)]), vec![0x03, 0x02, 0x01]].concat();
+        assert_eq!(ModExpContract.execute(&truncated).unwrap(), vec![0x00, 0x09]);
+    }
+
+    #[test]
+    fn test_ec_add_and_mul_vectors() {
+        let g1 = hex::decode("0000000000000000000000000000000000000000000000000000000000000001\
+                              0000000000000000000000000000000000000000000000000000000000000002").unwrap();
+        let double = hex::decode("030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3\
+                                  15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4").unwrap();
+        let infinity = vec![0u8; 64];
+        let scalar = |k: u64| H256::from_low_u64_be(k).0.to_vec();
+
+        assert_eq!(EcAddContract.execute(&[g1.clone(), g1.clone()].concat()).unwrap(), double);
+        assert_eq!(EcMulContract.execute(&[g1.clone(), scalar(2)].concat()).unwrap(), double);
+
+        // (0, 0) is the point at infinity, and missing input reads as zeros
+        assert_eq!(EcAddContract.execute(&[infinity.clone(), g1.clone()].concat()).unwrap(), g1);
+        assert_eq!(EcAddContract.execute(&g1).unwrap(), g1);
+        assert_eq!(EcMulContract.execute(&[g1.clone(), scalar(0)].concat()).unwrap(), infinity);
+        assert_eq!(EcMulContract.execute(&[infinity.clone(), scalar(5)].concat()).unwrap(), infinity);
+
+        // x = p + 1 is on the curve once reduced, but must be rejected
+        let unreduced = hex::decode("30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd48\
+                                     0000000000000000000000000000000000000000000000000000000000000002").unwrap();
+        assert_eq!(EcAddContract.execute(&[unreduced.clone(), g1.clone()].concat()).unwrap(), infinity);
+        assert_eq!(EcMulContract.execute(&[unreduced, scalar(1)].concat()).unwrap(), infinity);
+    }
+
+    #[test]
+    fn test_ecpairing_vectors() {
+        // G1 generator, its negation, and the G2 generator
+        let g1 = "0000000000000000000000000000000000000000000000000000000000000001\
+                  0000000000000000000000000000000000000000000000000000000000000002";
+        let neg_g1 = "0000000000000000000000000000000000000000000000000000000000000001\
+                      30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45";
+        let g2 = "198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2\
+                  1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed\
+                  090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b\
+                  12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa";
+        let pairing = |pairs: &[(&str, &str)]| {
+            let input: String = pairs.iter().map(|(g1, g2)| format!("{}{}", g1, g2)).collect();
+            EcPairingContract.execute(&hex::decode(input).unwrap()).unwrap()[31]
+        };
+
+        // empty_data, one_point, two_point_match_2
+        assert_eq!(pairing(&[]), 1);
+        assert_eq!(pairing(&[(g1, g2)]), 0);
+        assert_eq!(pairing(&[(g1, g2), (neg_g1, g2)]), 1);
+        assert_eq!(pairing(&[(g1, g2), (g1, g2)]), 0);
+
+        // A point at infinity contributes the identity
+        let infinity_g1 = "0".repeat(128);
+        assert_eq!(pairing(&[(&infinity_g1, g2)]), 1);
+
+        // Off-curve points and truncated input are rejected
+        let off_curve = "0000000000000000000000000000000000000000000000000000000000000001\
+                         0000000000000000000000000000000000000000000000000000000000000003";
+        assert_eq!(pairing(&[(off_curve, g2), (neg_g1, g2)]), 0);
+        let truncated = hex::decode(format!("{}{}", g1, g2)).unwrap();
+        assert_eq!(EcPairingContract.execute(&truncated[..191]).unwrap(), vec![0u8; 32]);
+    }
+
+    #[test]
+    fn test_ripemd160_abc_vector() {
+        let output = Ripemd160Contract.execute(b"abc").unwrap();
+        let mut expected = vec![0u8; 12];
+        expected.extend_from_slice(&hex::decode("8eb208f7e05d987a9b044a8e98c6b087f15a0bfc").unwrap());
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_ripemd160_gas_rounds_up_partial_words() {
+        assert_eq!(Ripemd160Contract.gas_cost(&[]), U256::from(600));
+        assert_eq!(Ripemd160Contract.gas_cost(&[0u8; 1]), U256::from(720));
+        assert_eq!(Ripemd160Contract.gas_cost(&[0u8; 32]), U256::from(720));
+        assert_eq!(Ripemd160Contract.gas_cost(&[0u8; 33]), U256::from(840));
+    }
+}
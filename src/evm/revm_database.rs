@@ -0,0 +1,133 @@
+//! REVM database backed by the node state
+
+use crate::evm::state::State;
+use crate::{Address, H256, U256, Result, OlympusError};
+use revm::{
+    database::{DBErrorMarker, DatabaseRef},
+    primitives::{Address as RevmAddress, Bytes, StorageKey, StorageValue, B256, U256 as RevmU256},
+    state::{AccountInfo, Bytecode, EvmState},
+};
+use std::collections::BTreeMap;
+
+/// Number of recent blocks whose hashes BLOCKHASH can read
+pub const BLOCK_HASH_WINDOW: u64 = 256;
+
+/// Source of canonical block hashes by number, read by BLOCKHASH
+pub trait BlockHashes: Send + Sync {
+    /// Get the hash of the canonical block at a height
+    fn block_hash(&self, number: u64) -> Result<Option<H256>>;
+}
+
+impl BlockHashes for BTreeMap<u64, H256> {
+    fn block_hash(&self, number: u64) -> Result<Option<H256>> {
+        Ok(self.get(&number).copied())
+    }
+}
+
+impl DBErrorMarker for OlympusError {}
+
+/// Read-only view of a `State` as a REVM database
+pub struct StateDatabase<'a> {
+    /// Underlying state
+    state: &'a dyn State,
+    /// Canonical block hashes, if known
+    block_hashes: Option<&'a dyn BlockHashes>,
+    /// Number of the block being executed
+    block_number: u64,
+}
+
+impl<'a> StateDatabase<'a> {
+    /// Create a database reading from `state`
+    pub fn new(state: &'a dyn State) -> Self {
+        Self { state, block_hashes: None, block_number: 0 }
+    }
+
+    /// Serve BLOCKHASH from `block_hashes` while executing block `block_number`
+    pub fn with_block_hashes(mut self, block_hashes: Option<&'a dyn BlockHashes>, block_number: u64) -> Self {
+        self.block_hashes = block_hashes;
+        self.block_number = block_number;
+        self
+    }
+}
+
+impl DatabaseRef for StateDatabase<'_> {
+    type Error = OlympusError;
+
+    /// The code hash comes from the state, so the code is not hashed on every load
+    fn basic_ref(&self, address: RevmAddress) -> Result<Option<AccountInfo>> {
+        let address = Address::from_slice(address.as_slice());
+        let code = self.state.get_code(address);
+        if code.is_empty() && !self.state.exists(address) {
+            return Ok(None);
+        }
+
+        Ok(Some(AccountInfo {
+            balance: to_revm_u256(self.state.get_balance(address)),
+            nonce: self.state.get_nonce(address),
+            code_hash: B256::from(self.state.get_code_hash(address).0),
+            code: Some(Bytecode::new_raw(Bytes::from(code))),
+        }))
+    }
+
+    /// Code is always returned with its account, so lookups by hash never happen
+    fn code_by_hash_ref(&self, _code_hash: B256) -> Result<Bytecode> {
+        Ok(Bytecode::default())
+    }
+
+    fn storage_ref(&self, address: RevmAddress, index: StorageKey) -> Result<StorageValue> {
+        let address = Address::from_slice(address.as_slice());
+        let value = self.state.get_storage(address, H256(index.to_be_bytes()));
+        Ok(value.map_or(RevmU256::ZERO, |value| RevmU256::from_be_bytes(value.0)))
+    }
+
+    /// Hash of one of the 256 blocks before the executing one, zero for any other block
+    fn block_hash_ref(&self, number: u64) -> Result<B256> {
+        let in_window = number < self.block_number && self.block_number - number <= BLOCK_HASH_WINDOW;
+        let hash = match self.block_hashes {
+            Some(block_hashes) if in_window => block_hashes.block_hash(number)?.unwrap_or_default(),
+            _ => H256::zero(),
+        };
+        Ok(B256::from(hash.0))
+    }
+}
+
+/// Write the accounts changed by a REVM execution back to a state
+///
+/// Accounts only loaded are left alone so reads never create accounts, and
+/// touched accounts left empty are deleted (EIP-161).
+pub fn commit_to_state(state: &mut dyn State, changes: &EvmState) {
+    for (address, account) in changes {
+        if !account.is_touched() {
+            continue;
+        }
+        let address = Address::from_slice(address.as_slice());
+        if account.is_selfdestructed() {
+            state.delete_account(address);
+            continue;
+        }
+        if account.is_empty() {
+            if state.exists(address) {
+                state.delete_account(address);
+            }
+            continue;
+        }
+
+        state.set_balance(address, U256::from_big_endian(&account.info.balance.to_be_bytes::<32>()));
+        state.set_nonce(address, account.info.nonce);
+        if account.is_created() {
+            if let Some(code) = &account.info.code {
+                state.set_code(address, code.original_bytes().to_vec());
+            }
+        }
+        for (slot, value) in account.changed_storage_slots() {
+            state.set_storage(address, H256(slot.to_be_bytes()), H256(value.present_value.to_be_bytes()));
+        }
+    }
+}
+
+/// Convert a balance to REVM's integer type
+fn to_revm_u256(value: U256) -> RevmU256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    RevmU256::from_be_bytes(bytes)
+}
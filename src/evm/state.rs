@@ -1,7 +1,9 @@
 //! EVM state management
 
 use crate::{Address, H256, U256};
-use std::collections::HashMap;
+use parking_lot::Mutex;
+use rlp::RlpStream;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 /// EVM state interface
 pub trait State {
@@ -23,6 +25,20 @@ pub trait State {
     /// Set storage value
     fn set_storage(&mut self, address: Address, key: H256, value: H256);
     
+    /// Set account code
+    fn set_code(&mut self, address: Address, code: Vec<u8>);
+    
+    /// Get account code
+    fn get_code(&self, address: Address) -> Vec<u8>;
+    
+    /// Get the length of account code, without loading the code
+    fn code_size(&self, address: Address) -> usize;
+    
+    /// Get the hash of account code, zero for accounts without code
+    fn get_code_hash(&self, address: Address) -> H256 {
+        code_hash(&self.get_code(address))
+    }
+    
     /// Check if account exists
     fn exists(&self, address: Address) -> bool;
     
@@ -32,6 +48,18 @@ pub trait State {
     /// Delete account
     fn delete_account(&mut self, address: Address);
     
+    /// Get a commitment to the full state
+    ///
+    /// States that cannot enumerate their accounts return zero.
+    fn state_root(&self) -> H256 {
+        H256::zero()
+    }
+    
+    /// Mark a point that the next `revert` rolls back to
+    ///
+    /// States without rollback support ignore checkpoints.
+    fn checkpoint(&mut self) {}
+    
     /// Commit state changes
     fn commit(&mut self);
     
@@ -39,13 +67,71 @@ pub trait State {
     fn revert(&mut self);
 }
 
+/// Code hash of accounts without code, keccak256 of the empty string
+pub const KECCAK_EMPTY: H256 = H256([
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+]);
+
+/// Hash account code
+pub fn code_hash(code: &[u8]) -> H256 {
+    if code.is_empty() {
+        KECCAK_EMPTY
+    } else {
+        crate::common::keccak256(code)
+    }
+}
+
+/// Commit to an account's storage as a Merkle-Patricia trie root
+///
+/// Slots are keyed by keccak256 of the slot and hold the RLP of the value.
+/// Zero-valued slots are skipped, so clearing a slot restores the previous root.
+pub fn storage_root(slots: impl IntoIterator<Item = (H256, H256)>) -> H256 {
+    crate::common::secure_trie_root(slots.into_iter()
+        .filter(|(_, value)| !value.is_zero())
+        .map(|(slot, value)| (slot.as_bytes().to_vec(), rlp::encode(&U256::from_big_endian(value.as_bytes())).to_vec())))
+}
+
+/// Commit to accounts given as `(address, balance, nonce, code_hash, storage_root)`
+///
+/// Accounts are keyed by keccak256 of the address and hold the RLP list
+/// `[nonce, balance, storage_root, code_hash]`, as in Ethereum.
+pub fn accounts_root(accounts: impl IntoIterator<Item = (Address, U256, u64, H256, H256)>) -> H256 {
+    crate::common::secure_trie_root(accounts.into_iter().map(|(address, balance, nonce, code_hash, storage_root)| {
+        (address.as_bytes().to_vec(), account_leaf(balance, nonce, code_hash, storage_root))
+    }))
+}
+
+/// RLP of an account in the state trie, `[nonce, balance, storage_root, code_hash]`
+fn account_leaf(balance: U256, nonce: u64, code_hash: H256, storage_root: H256) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(4);
+    stream.append(&nonce).append(&balance).append(&storage_root).append(&code_hash);
+    stream.out().to_vec()
+}
+
 /// In-memory state implementation
 pub struct MemoryState {
     balances: HashMap<Address, U256>,
     nonces: HashMap<Address, u64>,
-    storage: HashMap<(Address, H256), H256>,
+    storage: HashMap<Address, HashMap<H256, H256>>,
+    /// Code of each account, with its hash
+    code: HashMap<Address, (Vec<u8>, H256)>,
+    /// Trie leaves of the accounts, refreshed for changed accounts by `state_root`
+    leaves: Mutex<AccountLeaves>,
+}
+
+/// Account trie leaves kept between state root computations
+#[derive(Default)]
+struct AccountLeaves {
+    /// Hashed trie key and leaf of each account
+    leaves: BTreeMap<Address, (Vec<u8>, Vec<u8>)>,
+    /// Accounts changed since the leaves were last refreshed
+    dirty: HashSet<Address>,
 }
 
+/// State shared between the node and its RPC handlers
+pub type SharedState = std::sync::Arc<parking_lot::RwLock<Box<dyn State + Send + Sync>>>;
+
 impl MemoryState {
     /// Create new memory state
     pub fn new() -> Self {
@@ -53,8 +139,28 @@ impl MemoryState {
             balances: HashMap::new(),
             nonces: HashMap::new(),
             storage: HashMap::new(),
+            code: HashMap::new(),
+            leaves: Mutex::new(AccountLeaves::default()),
         }
     }
+
+    /// Record that an account changed, so its leaf is rebuilt by the next `state_root`
+    fn touch(&mut self, address: Address) {
+        self.leaves.get_mut().dirty.insert(address);
+    }
+
+    /// Trie leaf of an account, `None` if it does not exist
+    fn leaf(&self, address: Address) -> Option<Vec<u8>> {
+        let slots = self.storage.get(&address);
+        let exists = self.balances.contains_key(&address)
+            || self.nonces.contains_key(&address)
+            || self.code.contains_key(&address)
+            || slots.is_some();
+        exists.then(|| {
+            let slots = slots.into_iter().flatten().map(|(slot, value)| (*slot, *value));
+            account_leaf(self.get_balance(address), self.get_nonce(address), self.get_code_hash(address), storage_root(slots))
+        })
+    }
 }
 
 impl Default for MemoryState {
@@ -70,6 +176,7 @@ impl State for MemoryState {
     
     fn set_balance(&mut self, address: Address, balance: U256) {
         self.balances.insert(address, balance);
+        self.touch(address);
     }
     
     fn get_nonce(&self, address: Address) -> u64 {
@@ -78,14 +185,34 @@ impl State for MemoryState {
     
     fn set_nonce(&mut self, address: Address, nonce: u64) {
         self.nonces.insert(address, nonce);
+        self.touch(address);
     }
     
     fn get_storage(&self, address: Address, key: H256) -> Option<H256> {
-        self.storage.get(&(address, key)).cloned()
+        self.storage.get(&address).and_then(|slots| slots.get(&key)).cloned()
     }
     
     fn set_storage(&mut self, address: Address, key: H256, value: H256) {
-        self.storage.insert((address, key), value);
+        self.storage.entry(address).or_default().insert(key, value);
+        self.touch(address);
+    }
+    
+    fn set_code(&mut self, address: Address, code: Vec<u8>) {
+        let hash = code_hash(&code);
+        self.code.insert(address, (code, hash));
+        self.touch(address);
+    }
+    
+    fn get_code(&self, address: Address) -> Vec<u8> {
+        self.code.get(&address).map(|(code, _)| code.clone()).unwrap_or_default()
+    }
+    
+    fn code_size(&self, address: Address) -> usize {
+        self.code.get(&address).map_or(0, |(code, _)| code.len())
+    }
+
+    fn get_code_hash(&self, address: Address) -> H256 {
+        self.code.get(&address).map_or_else(|| code_hash(&[]), |(_, hash)| *hash)
     }
     
     fn exists(&self, address: Address) -> bool {
@@ -95,13 +222,33 @@ impl State for MemoryState {
     fn create_account(&mut self, address: Address) {
         self.balances.insert(address, U256::zero());
         self.nonces.insert(address, 0);
+        self.code.remove(&address);
+        self.touch(address);
     }
     
     fn delete_account(&mut self, address: Address) {
         self.balances.remove(&address);
         self.nonces.remove(&address);
-        // Remove all storage entries for this address
-        self.storage.retain(|(addr, _), _| *addr != address);
+        self.storage.remove(&address);
+        self.code.remove(&address);
+        self.touch(address);
+    }
+    
+    /// Only accounts changed since the last call have their leaf and storage root rebuilt
+    fn state_root(&self) -> H256 {
+        let mut leaves = self.leaves.lock();
+        let AccountLeaves { leaves, dirty } = &mut *leaves;
+        for address in dirty.drain() {
+            match self.leaf(address) {
+                Some(leaf) => {
+                    leaves.insert(address, (crate::common::keccak256(address.as_bytes()).as_bytes().to_vec(), leaf));
+                }
+                None => {
+                    leaves.remove(&address);
+                }
+            }
+        }
+        crate::common::trie_root(leaves.values().cloned())
     }
     
     fn commit(&mut self) {
@@ -114,3 +261,144 @@ impl State for MemoryState {
         // In a persistent state implementation, this would restore from checkpoint
     }
 }
+
+/// Shared state, locked for the duration of each access
+impl State for SharedState {
+    fn get_balance(&self, address: Address) -> U256 {
+        self.read().get_balance(address)
+    }
+
+    fn set_balance(&mut self, address: Address, balance: U256) {
+        self.write().set_balance(address, balance)
+    }
+
+    fn get_nonce(&self, address: Address) -> u64 {
+        self.read().get_nonce(address)
+    }
+
+    fn set_nonce(&mut self, address: Address, nonce: u64) {
+        self.write().set_nonce(address, nonce)
+    }
+
+    fn get_storage(&self, address: Address, key: H256) -> Option<H256> {
+        self.read().get_storage(address, key)
+    }
+
+    fn set_storage(&mut self, address: Address, key: H256, value: H256) {
+        self.write().set_storage(address, key, value)
+    }
+
+    fn set_code(&mut self, address: Address, code: Vec<u8>) {
+        self.write().set_code(address, code)
+    }
+
+    fn get_code(&self, address: Address) -> Vec<u8> {
+        self.read().get_code(address)
+    }
+
+    fn code_size(&self, address: Address) -> usize {
+        self.read().code_size(address)
+    }
+
+    fn get_code_hash(&self, address: Address) -> H256 {
+        self.read().get_code_hash(address)
+    }
+
+    fn exists(&self, address: Address) -> bool {
+        self.read().exists(address)
+    }
+
+    fn create_account(&mut self, address: Address) {
+        self.write().create_account(address)
+    }
+
+    fn delete_account(&mut self, address: Address) {
+        self.write().delete_account(address)
+    }
+
+    fn state_root(&self) -> H256 {
+        self.read().state_root()
+    }
+
+    fn checkpoint(&mut self) {
+        self.write().checkpoint()
+    }
+
+    fn commit(&mut self) {
+        self.write().commit()
+    }
+
+    fn revert(&mut self) {
+        self.write().revert()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_round_trip_and_hash() {
+        let mut state = MemoryState::new();
+        let contract = Address::from([0xc0; 20]);
+        assert_eq!(state.get_code_hash(contract), KECCAK_EMPTY);
+        assert_eq!(KECCAK_EMPTY, crate::common::keccak256(&[]));
+
+        state.set_code(contract, vec![0x60, 0x00]);
+        assert_eq!(state.get_code(contract), vec![0x60, 0x00]);
+        assert_eq!(state.get_code_hash(contract), crate::common::keccak256(&[0x60, 0x00]));
+
+        state.create_account(contract);
+        assert!(state.get_code(contract).is_empty());
+        assert_eq!(state.get_code_hash(contract), KECCAK_EMPTY);
+    }
+
+    #[test]
+    fn test_state_root_is_trie_root() {
+        let mut state = MemoryState::new();
+        assert_eq!(state.state_root(), crate::common::EMPTY_TRIE_ROOT);
+
+        let account = Address::from([0x11; 20]);
+        state.set_balance(account, U256::from(1000));
+        let root = state.state_root();
+        assert_ne!(root, crate::common::EMPTY_TRIE_ROOT);
+
+        let mut other = MemoryState::new();
+        other.set_balance(account, U256::from(1000));
+        assert_eq!(other.state_root(), root);
+
+        other.set_nonce(account, 1);
+        assert_ne!(other.state_root(), root);
+    }
+
+    #[test]
+    fn test_incremental_state_root_matches_full_rebuild() {
+        let (a, b) = (Address::from([0x11; 20]), Address::from([0x22; 20]));
+        let mut state = MemoryState::new();
+        state.set_balance(a, U256::from(1000));
+        state.set_storage(b, H256::repeat_byte(1), H256::repeat_byte(2));
+        state.state_root();
+
+        // Only the changed accounts are refreshed, yet the root equals a state built in one go
+        state.set_storage(b, H256::repeat_byte(3), H256::repeat_byte(4));
+        state.delete_account(a);
+        let mut fresh = MemoryState::new();
+        fresh.set_storage(b, H256::repeat_byte(1), H256::repeat_byte(2));
+        fresh.set_storage(b, H256::repeat_byte(3), H256::repeat_byte(4));
+        assert_eq!(state.state_root(), fresh.state_root());
+        assert_eq!(state.state_root(), accounts_root([(b, U256::zero(), 0, KECCAK_EMPTY, storage_root(vec![
+            (H256::repeat_byte(1), H256::repeat_byte(2)),
+            (H256::repeat_byte(3), H256::repeat_byte(4)),
+        ]))]));
+    }
+
+    #[test]
+    fn test_single_account_state_root_matches_ethereum() {
+        let mut state = MemoryState::new();
+        let account: Address = "a94f5374fce5edbc8e2a8697c15331677e6ebf0b".parse().unwrap();
+        state.set_balance(account, U256::from(10u64).pow(U256::from(18)));
+
+        let expected: H256 = "517f2cdf6adb1a644878c390ffab4e130f1bed4b498ef7ce58c5addd98d61018".parse().unwrap();
+        assert_eq!(state.state_root(), expected);
+    }
+}
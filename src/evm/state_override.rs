@@ -0,0 +1,22 @@
+//! Per-call state overrides for read-only calls
+
+use crate::{Address, H256, U256};
+use std::collections::HashMap;
+
+/// Temporary changes to one account, applied for a single call
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountOverride {
+    /// Replacement balance
+    pub balance: Option<U256>,
+    /// Replacement nonce
+    pub nonce: Option<u64>,
+    /// Replacement code
+    pub code: Option<Vec<u8>>,
+    /// Replacement for the whole storage
+    pub state: Option<HashMap<H256, H256>>,
+    /// Storage slots changed on top of the existing storage
+    pub state_diff: HashMap<H256, H256>,
+}
+
+/// Account overrides keyed by address
+pub type StateOverride = HashMap<Address, AccountOverride>;
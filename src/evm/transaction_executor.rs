@@ -1,11 +1,22 @@
 //! Transaction execution logic
 
+use crate::core::receipt::Receipt;
 use crate::core::transaction::Transaction;
 use crate::{Address, H256, U256, Result, OlympusError};
 use crate::evm::{Executive, State};
-use crate::evm::executive::EvmExecutionResult;
+use crate::evm::executive::{ExecutionLimits, ExecutionStatus};
+use crate::evm::revm_database::BlockHashes;
+use crate::evm::gas_schedule::GasSchedule;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Capacity of the pending transaction event channel
+const PENDING_EVENTS_CAPACITY: usize = 1024;
+
+/// Minimum gas price increase, in percent, for a transaction to replace another with the same sender and nonce
+pub const REPLACEMENT_PRICE_BUMP_PERCENT: u64 = 10;
 
 /// Transaction execution context
 #[derive(Debug, Clone)]
@@ -20,6 +31,30 @@ pub struct TransactionExecutionContext {
     pub block_gas_limit: U256,
     /// Base fee per gas
     pub base_fee: U256,
+    /// Block randomness from consensus
+    pub prevrandao: H256,
+    /// Caps on the logs emitted by transactions and blocks
+    pub log_limits: LogLimits,
+    /// Account credited with priority fees
+    pub coinbase: Address,
+    /// Leave priority fees out of the state, for the caller to credit with `coinbase_fee`
+    pub defer_coinbase_fee: bool,
+}
+
+impl TransactionExecutionContext {
+    /// Priority fee a transaction's execution owes the coinbase
+    pub fn coinbase_fee(&self, result: &TransactionExecutionResult) -> U256 {
+        result.gas_used * result.gas_price.saturating_sub(self.base_fee)
+    }
+}
+
+/// Caps on the number of logs emitted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogLimits {
+    /// Maximum logs per transaction
+    pub max_per_transaction: Option<usize>,
+    /// Maximum logs per block
+    pub max_per_block: Option<usize>,
 }
 
 /// Transaction execution result
@@ -33,6 +68,8 @@ pub struct TransactionExecutionResult {
     pub gas_price: U256,
     /// Success status
     pub success: bool,
+    /// Execution outcome
+    pub status: ExecutionStatus,
     /// Output data
     pub output: Vec<u8>,
     /// Logs emitted
@@ -43,6 +80,38 @@ pub struct TransactionExecutionResult {
     pub error: Option<String>,
 }
 
+impl TransactionExecutionResult {
+    /// Result of a transaction rejected before execution, which consumes no gas
+    pub fn failed(error: &OlympusError) -> Self {
+        Self {
+            transaction_hash: H256::zero(),
+            gas_used: U256::zero(),
+            gas_price: U256::zero(),
+            success: false,
+            status: ExecutionStatus::Rejected,
+            output: vec![],
+            logs: vec![],
+            contract_address: None,
+            error: Some(error.to_string()),
+        }
+    }
+
+    /// Result of a transaction left out of a full block, which consumes no gas
+    pub fn skipped(transaction_hash: H256) -> Self {
+        Self {
+            transaction_hash,
+            gas_used: U256::zero(),
+            gas_price: U256::zero(),
+            success: false,
+            status: ExecutionStatus::Skipped,
+            output: vec![],
+            logs: vec![],
+            contract_address: None,
+            error: ExecutionStatus::Skipped.error_message().map(str::to_string),
+        }
+    }
+}
+
 /// Log entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionLogEntry {
@@ -56,36 +125,47 @@ pub struct TransactionLogEntry {
 
 /// Transaction executor
 pub struct TransactionExecutor {
-    /// EVM executive
+    /// EVM executive, owning the state transactions execute against
     executive: Executive,
-    /// State manager
-    state_manager: Box<dyn State>,
     /// Execution context
     context: TransactionExecutionContext,
     /// Transaction pool
     transaction_pool: HashMap<H256, Transaction>,
+    /// Logs emitted in the current block
+    block_log_count: usize,
+    /// Receipts of the transactions in the current block
+    block_receipts: Vec<Receipt>,
 }
 
 impl TransactionExecutor {
     /// Create new transaction executor
     pub fn new(state_manager: Box<dyn State>, context: TransactionExecutionContext) -> Self {
         Self {
-            executive: Executive::new(),
-            state_manager,
+            executive: Executive::new_with_state(state_manager),
             context,
             transaction_pool: HashMap::new(),
+            block_log_count: 0,
+            block_receipts: Vec::new(),
         }
     }
 
     /// Execute a single transaction
     pub fn execute_transaction(&mut self, transaction: Transaction) -> Result<TransactionExecutionResult> {
+        self.execute_with_prewarm(transaction, &[])
+    }
+
+    /// Execute a transaction with the given accounts and storage slots already warm
+    ///
+    /// Used for access list creation and bundle simulation, where slots touched by
+    /// earlier transactions should be charged the warm price.
+    pub fn execute_with_prewarm(&mut self, transaction: Transaction, warm: &[(Address, Vec<H256>)]) -> Result<TransactionExecutionResult> {
         let transaction_hash = transaction.hash();
         
         // Validate transaction
         self.validate_transaction(&transaction)?;
         
         // Check nonce
-        let sender_nonce = self.state_manager.get_nonce(transaction.from());
+        let sender_nonce = self.state().get_nonce(transaction.from());
         if transaction.nonce() != U256::from(sender_nonce) {
             return Err(OlympusError::InvalidTransaction(
                 format!("Invalid nonce: expected {}, got {}", sender_nonce, transaction.nonce())
@@ -93,7 +173,7 @@ impl TransactionExecutor {
         }
         
         // Check balance
-        let sender_balance = self.state_manager.get_balance(transaction.from());
+        let sender_balance = self.state().get_balance(transaction.from());
         let total_cost = transaction.value() + (transaction.gas() * transaction.gas_price());
         if sender_balance < total_cost {
             return Err(OlympusError::InvalidTransaction(
@@ -101,66 +181,92 @@ impl TransactionExecutor {
             ));
         }
         
-        // Initialize EVM executive
+        // Initialize EVM executive, failing the transaction if it emits more logs than allowed;
+        // block execution is otherwise bounded by gas alone, so every node reaches the same result
+        self.executive.set_execution_limits(ExecutionLimits { max_logs: self.log_cap(), ..Default::default() });
+        self.executive.set_coinbase(self.context.coinbase);
+        self.executive.set_defer_coinbase_fee(self.context.defer_coinbase_fee);
+        self.executive.set_base_fee(self.context.base_fee);
+        self.executive.set_prevrandao(self.context.prevrandao);
         self.executive.initialize(&transaction, self.context.block_number, self.context.timestamp)?;
+        self.executive.prewarm(warm);
         
         // Execute transaction
         let evm_result = self.executive.execute(&transaction)?;
+        self.block_log_count += evm_result.logs.len();
         
-        // Update state if successful
-        if evm_result.success {
-            self.update_state_after_transaction(&transaction, &evm_result)?;
-        }
+        // REVM has already charged the sender, paid the coinbase and applied the changes
+        let gas_used = evm_result.gas_used;
         
         // Create execution result
         let result = TransactionExecutionResult {
             transaction_hash,
-            gas_used: evm_result.gas_used,
-            gas_price: transaction.gas_price(),
+            gas_used,
+            gas_price: transaction.effective_gas_price(self.context.base_fee),
             success: evm_result.success,
+            status: evm_result.status,
             output: evm_result.output,
-            logs: vec![], // TODO: Extract logs from EVM execution
+            logs: evm_result.logs.into_iter().map(|log| TransactionLogEntry {
+                address: log.address,
+                topics: log.topics,
+                data: log.data,
+            }).collect(),
             contract_address: if transaction.receive_address == Address::zero() {
                 Some(self.calculate_contract_address(&transaction))
             } else {
                 None
             },
-            error: if evm_result.success { None } else { Some("Transaction execution failed".to_string()) },
+            error: evm_result.error,
         };
         
         Ok(result)
     }
 
     /// Execute multiple transactions in a block
+    ///
+    /// A transaction whose gas limit would take the gas used so far past the
+    /// block gas limit is skipped, and later ones that still fit are executed.
+    /// Transactions rejected before execution get no receipt; the receipts of
+    /// the executed ones are available from `receipts`.
     pub fn execute_block_transactions(&mut self, transactions: Vec<Transaction>) -> Result<Vec<TransactionExecutionResult>> {
         let mut results = Vec::new();
+        self.block_log_count = 0;
+        self.block_receipts.clear();
+        let mut cumulative_gas_used = U256::zero();
         
         for transaction in transactions {
-            match self.execute_transaction(transaction) {
-                Ok(result) => results.push(result),
+            let transaction_hash = transaction.hash();
+            if cumulative_gas_used.saturating_add(transaction.gas()) > self.context.block_gas_limit {
+                results.push(TransactionExecutionResult::skipped(transaction_hash));
+                continue;
+            }
+
+            // A rejected transaction reports why in its result and the block carries on
+            let result = match self.execute_transaction(transaction) {
+                Ok(result) => result,
                 Err(e) => {
-                    // Log error but continue with other transactions
-                    eprintln!("Transaction execution failed: {}", e);
-                    // Create failed result
-                    results.push(TransactionExecutionResult {
-                        transaction_hash: H256::zero(),
-                        gas_used: U256::zero(),
-                        gas_price: U256::zero(),
-                        success: false,
-                        output: vec![],
-                        logs: vec![],
-                        contract_address: None,
-                        error: Some(e.to_string()),
-                    });
+                    results.push(TransactionExecutionResult { transaction_hash, ..TransactionExecutionResult::failed(&e) });
+                    continue;
                 }
-            }
+            };
+            cumulative_gas_used += result.gas_used;
+            self.block_receipts.push(Receipt::new(&result, cumulative_gas_used));
+            results.push(result);
         }
         
         Ok(results)
     }
 
+    /// Get the receipts of the last executed block, in transaction order
+    pub fn receipts(&self) -> &[Receipt] {
+        &self.block_receipts
+    }
+
     /// Validate transaction
     fn validate_transaction(&self, transaction: &Transaction) -> Result<()> {
+        // Check the signature, rather than executing unsigned transactions as the zero address
+        transaction.sender()?;
+
         // Check gas limit
         if transaction.gas() > self.context.block_gas_limit {
             return Err(OlympusError::InvalidTransaction(
@@ -168,6 +274,20 @@ impl TransactionExecutor {
             ));
         }
         
+        // Check intrinsic gas, including the calldata floor, under the block's hardfork
+        let block_number = self.context.block_number.low_u64();
+        let schedule = GasSchedule::new(self.executive.context().env.fork_for_block(block_number));
+        if transaction.gas() < U256::from(transaction.intrinsic_gas(&schedule)) {
+            return Err(OlympusError::InvalidTransaction("intrinsic gas too low".to_string()));
+        }
+
+        // Check deadline
+        if transaction.is_expired(block_number) {
+            return Err(OlympusError::InvalidTransaction(
+                format!("Transaction expired: valid until block {:?}, current block {}", transaction.valid_until, block_number)
+            ));
+        }
+
         // Check gas price
         if transaction.gas_price() < self.context.base_fee {
             return Err(OlympusError::InvalidTransaction(
@@ -186,39 +306,19 @@ impl TransactionExecutor {
         Ok(())
     }
 
-    /// Update state after successful transaction
-    fn update_state_after_transaction(&mut self, transaction: &Transaction, evm_result: &EvmExecutionResult) -> Result<()> {
-        // Update sender nonce
-        let sender_nonce = self.state_manager.get_nonce(transaction.from());
-        self.state_manager.set_nonce(transaction.from(), sender_nonce + 1);
-        
-        // Deduct gas cost from sender
-        let gas_cost = evm_result.gas_used * transaction.gas_price();
-        let sender_balance = self.state_manager.get_balance(transaction.from());
-        self.state_manager.set_balance(transaction.from(), sender_balance - gas_cost);
-        
-        // Add value to recipient (if not contract creation)
-        if transaction.receive_address != Address::zero() {
-            let recipient_balance = self.state_manager.get_balance(transaction.receive_address);
-            self.state_manager.set_balance(transaction.receive_address, recipient_balance + transaction.value());
-        }
-        
-        // Create account if it doesn't exist
-        if !self.state_manager.exists(transaction.receive_address) && transaction.receive_address != Address::zero() {
-            self.state_manager.create_account(transaction.receive_address);
+    /// Most logs the next transaction may emit, from the transaction and remaining block caps
+    fn log_cap(&self) -> Option<usize> {
+        let limits = self.context.log_limits;
+        let block_remaining = limits.max_per_block.map(|cap| cap.saturating_sub(self.block_log_count));
+        match (limits.max_per_transaction, block_remaining) {
+            (Some(tx_cap), Some(block_cap)) => Some(tx_cap.min(block_cap)),
+            (tx_cap, block_cap) => tx_cap.or(block_cap),
         }
-        
-        Ok(())
     }
 
     /// Calculate contract address for contract creation
     fn calculate_contract_address(&self, transaction: &Transaction) -> Address {
-        // Simple contract address calculation based on sender and nonce
-        // In a full implementation, this would use CREATE2 or proper CREATE logic
-        let mut data = Vec::new();
-        data.extend_from_slice(transaction.from().as_bytes());
-        data.extend_from_slice(&transaction.nonce().as_u64().to_be_bytes());
-        crate::common::keccak256(&data).into()
+        crate::common::contract_address_from_sender(transaction.from(), transaction.nonce())
     }
 
     /// Estimate gas for transaction
@@ -256,12 +356,28 @@ impl TransactionExecutor {
     /// Update execution context
     pub fn update_context(&mut self, context: TransactionExecutionContext) {
         self.context = context;
+        self.block_log_count = 0;
+    }
+
+    /// Set the canonical block hashes BLOCKHASH reads
+    pub fn set_block_hashes(&mut self, block_hashes: Arc<dyn BlockHashes>) {
+        self.executive.set_block_hashes(block_hashes);
     }
 
     /// Get current context
     pub fn get_context(&self) -> &TransactionExecutionContext {
         &self.context
     }
+
+    /// Get the state transactions execute against
+    pub fn state(&self) -> &dyn State {
+        self.executive.state()
+    }
+
+    /// Get mutable state
+    pub fn state_mut(&mut self) -> &mut dyn State {
+        self.executive.state_mut()
+    }
 }
 
 impl Default for TransactionExecutionContext {
@@ -272,6 +388,10 @@ impl Default for TransactionExecutionContext {
             block_hash: H256::zero(),
             block_gas_limit: U256::from(30_000_000), // 30M gas limit
             base_fee: U256::from(1_000_000_000), // 1 gwei base fee
+            prevrandao: H256::zero(),
+            log_limits: LogLimits::default(),
+            coinbase: Address::zero(),
+            defer_coinbase_fee: false,
         }
     }
 }
@@ -282,36 +402,154 @@ pub struct TransactionPool {
     pending: HashMap<H256, Transaction>,
     /// Queued transactions
     queued: HashMap<H256, Transaction>,
+    /// Pooled transaction hashes of each sender by nonce
+    by_sender: HashMap<Address, BTreeMap<U256, H256>>,
+    /// Each sender's highest-nonce transaction by gas price, cheapest first
+    evictable: BTreeSet<(U256, H256)>,
     /// Maximum pool size
     max_size: usize,
+    /// Base fee that pending transactions must tip above
+    base_fee: U256,
+    /// Events for transactions entering pending
+    pending_events: broadcast::Sender<Transaction>,
 }
 
 impl TransactionPool {
     /// Create new transaction pool
     pub fn new(max_size: usize) -> Self {
+        let (pending_events, _) = broadcast::channel(PENDING_EVENTS_CAPACITY);
+
         Self {
             pending: HashMap::new(),
             queued: HashMap::new(),
+            by_sender: HashMap::new(),
+            evictable: BTreeSet::new(),
             max_size,
+            base_fee: U256::from(1_000_000_000), // 1 gwei
+            pending_events,
         }
     }
 
-    /// Add transaction to pool
-    pub fn add_transaction(&mut self, transaction: Transaction) -> Result<()> {
+    /// Set the base fee used to price newly added transactions
+    pub fn set_base_fee(&mut self, base_fee: U256) {
+        self.base_fee = base_fee;
+    }
+
+    /// Get the sender of pending transaction events, used to create subscriptions
+    pub fn pending_transaction_events(&self) -> broadcast::Sender<Transaction> {
+        self.pending_events.clone()
+    }
+
+    /// Subscribe to transactions entering pending, on admission or promotion
+    pub fn subscribe_pending_transactions(&self) -> broadcast::Receiver<Transaction> {
+        self.pending_events.subscribe()
+    }
+
+    /// Add transaction to pool, returning the hash of any transaction evicted to make room
+    pub fn add_transaction(&mut self, transaction: Transaction) -> Result<Option<H256>> {
+        // Add to pending if it tips above the base fee, otherwise to queued
+        let executable = transaction.effective_gas_price(self.base_fee) > self.base_fee;
+        self.insert(transaction, executable)
+    }
+
+    /// Add transaction to pool, queueing it if its nonce is ahead of the sender's account nonce
+    pub fn add_transaction_for_nonce(&mut self, transaction: Transaction, account_nonce: u64) -> Result<Option<H256>> {
+        if transaction.nonce() < U256::from(account_nonce) {
+            return Err(OlympusError::InvalidTransaction(
+                format!("Nonce too low: account nonce {}, got {}", account_nonce, transaction.nonce())
+            ));
+        }
+
+        let executable = transaction.nonce() == U256::from(account_nonce);
+        self.insert(transaction, executable)
+    }
+
+    /// Insert an admitted transaction into pending or queued
+    ///
+    /// A transaction sharing a sender and nonce with a pooled one replaces it
+    /// only if it raises the gas price by `REPLACEMENT_PRICE_BUMP_PERCENT`.
+    /// When the pool is full, the cheapest of the other senders' highest-nonce
+    /// transactions is evicted if the new one pays more; its hash is returned.
+    /// Evicting only the last of a sender's transactions, and never the new
+    /// transaction's sender's own, leaves no nonce gap.
+    fn insert(&mut self, transaction: Transaction, executable: bool) -> Result<Option<H256>> {
         let hash = transaction.hash();
-        
-        if self.pending.len() + self.queued.len() >= self.max_size {
-            return Err(OlympusError::InvalidTransaction("Transaction pool is full".to_string()));
+        if self.get_transaction(hash).is_some() {
+            return Err(OlympusError::InvalidTransaction(format!("Transaction {:?} is already in the pool", hash)));
         }
-        
-        // Add to pending if gas price is high enough, otherwise to queued
-        if transaction.gas_price() > U256::from(1_000_000_000) { // 1 gwei threshold
-            self.pending.insert(hash, transaction);
+
+        let (sender, nonce) = (transaction.from(), transaction.nonce());
+        let replaced = self.pending.iter().chain(self.queued.iter())
+            .find(|(_, pooled)| pooled.from() == sender && pooled.nonce() == nonce)
+            .map(|(hash, pooled)| (*hash, pooled.gas_price()));
+        if let Some((replaced_hash, replaced_price)) = replaced {
+            let bumped = replaced_price.saturating_mul(U256::from(100 + REPLACEMENT_PRICE_BUMP_PERCENT));
+            if transaction.gas_price().saturating_mul(U256::from(100)) < bumped {
+                return Err(OlympusError::InvalidTransaction(format!(
+                    "Replacement transaction underpriced: gas price {} must be at least {}% above {}",
+                    transaction.gas_price(), REPLACEMENT_PRICE_BUMP_PERCENT, replaced_price
+                )));
+            }
+            self.remove_transaction(replaced_hash);
+        }
+
+        let mut evicted = None;
+        if self.is_full() {
+            if !self.can_admit(&transaction) {
+                return Err(OlympusError::InvalidTransaction("Transaction pool is full".to_string()));
+            }
+            evicted = self.cheapest_transaction(sender);
+            if let Some(hash) = evicted {
+                self.remove_transaction(hash);
+            }
+        }
+
+        if let Some(tail) = self.sender_tail(sender) {
+            self.evictable.remove(&tail);
+        }
+        self.by_sender.entry(sender).or_default().insert(nonce, hash);
+        if executable {
+            self.insert_pending(hash, transaction);
         } else {
             self.queued.insert(hash, transaction);
         }
-        
-        Ok(())
+        if let Some(tail) = self.sender_tail(sender) {
+            self.evictable.insert(tail);
+        }
+
+        Ok(evicted)
+    }
+
+    /// Move a transaction into pending and notify subscribers; having none is not an error
+    fn insert_pending(&mut self, hash: H256, transaction: Transaction) {
+        let _ = self.pending_events.send(transaction.clone());
+        self.pending.insert(hash, transaction);
+    }
+
+    /// Gas price and hash of the sender's highest-nonce pooled transaction
+    fn sender_tail(&self, sender: Address) -> Option<(U256, H256)> {
+        let hash = *self.by_sender.get(&sender)?.values().next_back()?;
+        Some((self.get_transaction(hash)?.gas_price(), hash))
+    }
+
+    /// Check if the pool has reached its maximum size
+    pub fn is_full(&self) -> bool {
+        self.pending.len() + self.queued.len() >= self.max_size
+    }
+
+    /// Check if a transaction fits, evicting a cheaper one from another sender if the pool is full
+    pub fn can_admit(&self, transaction: &Transaction) -> bool {
+        !self.is_full() || self.cheapest_transaction(transaction.from())
+            .and_then(|hash| self.get_transaction(hash))
+            .is_some_and(|cheapest| transaction.gas_price() > cheapest.gas_price())
+    }
+
+    /// Hash of the cheapest transaction that is the last of a sender's other than `excluded`
+    fn cheapest_transaction(&self, excluded: Address) -> Option<H256> {
+        let excluded_tail = self.sender_tail(excluded);
+        self.evictable.iter()
+            .find(|tail| Some(**tail) != excluded_tail)
+            .map(|(_, hash)| *hash)
     }
 
     /// Get pending transactions
@@ -319,15 +557,65 @@ impl TransactionPool {
         self.pending.values().collect()
     }
 
+    /// Get pending transactions in block-building order
+    ///
+    /// Each sender's transactions follow in ascending nonce order, stopping at
+    /// the first nonce gap. Senders are ordered by the gas price of their
+    /// lowest-nonce transaction, highest first.
+    pub fn get_ordered_pending(&self) -> Vec<&Transaction> {
+        let mut by_sender: HashMap<Address, Vec<&Transaction>> = HashMap::new();
+        for transaction in self.pending.values() {
+            by_sender.entry(transaction.from()).or_default().push(transaction);
+        }
+
+        let mut runs: Vec<(Address, Vec<&Transaction>)> = by_sender.into_iter()
+            .map(|(sender, mut transactions)| {
+                transactions.sort_by_key(|transaction| transaction.nonce());
+                let first_nonce = transactions[0].nonce();
+                let contiguous = transactions.iter().zip(0u64..)
+                    .take_while(|(transaction, offset)| transaction.nonce() == first_nonce + *offset)
+                    .count();
+                transactions.truncate(contiguous);
+                (sender, transactions)
+            })
+            .collect();
+        runs.sort_by(|(sender_a, a), (sender_b, b)| {
+            b[0].gas_price().cmp(&a[0].gas_price()).then(sender_a.cmp(sender_b))
+        });
+        runs.into_iter().flat_map(|(_, transactions)| transactions).collect()
+    }
+
     /// Get queued transactions
     pub fn get_queued_transactions(&self) -> Vec<&Transaction> {
         self.queued.values().collect()
     }
 
+    /// Get a pending or queued transaction by hash
+    pub fn get_transaction(&self, hash: H256) -> Option<&Transaction> {
+        self.pending.get(&hash).or_else(|| self.queued.get(&hash))
+    }
+
     /// Remove transaction
     pub fn remove_transaction(&mut self, hash: H256) {
+        let Some((sender, nonce)) = self.get_transaction(hash).map(|tx| (tx.from(), tx.nonce())) else {
+            return;
+        };
+        if let Some(tail) = self.sender_tail(sender) {
+            self.evictable.remove(&tail);
+        }
+
         self.pending.remove(&hash);
         self.queued.remove(&hash);
+        if let Some(nonces) = self.by_sender.get_mut(&sender) {
+            nonces.remove(&nonce);
+            if nonces.is_empty() {
+                self.by_sender.remove(&sender);
+            }
+        }
+
+        if let Some(tail) = self.sender_tail(sender) {
+            self.evictable.insert(tail);
+        }
     }
 
     /// Promote queued transactions to pending
@@ -342,11 +630,104 @@ impl TransactionPool {
         
         for hash in to_promote {
             if let Some(transaction) = self.queued.remove(&hash) {
-                self.pending.insert(hash, transaction);
+                self.insert_pending(hash, transaction);
             }
         }
     }
 
+    /// Drop transactions that can no longer be included at `block_number`
+    pub fn prune_expired(&mut self, block_number: u64) -> Vec<H256> {
+        let expired: Vec<H256> = self.pending.iter().chain(self.queued.iter())
+            .filter(|(_, transaction)| transaction.is_expired(block_number))
+            .map(|(hash, _)| *hash)
+            .collect();
+
+        for hash in &expired {
+            self.remove_transaction(*hash);
+        }
+        expired
+    }
+
+    /// Evict transactions sharing a sender and nonce with transactions included in a block
+    ///
+    /// This drops both the included transactions and any transaction they
+    /// replaced, so neither can be included again. Returns the evicted hashes.
+    pub fn remove_included(&mut self, included: &[Transaction]) -> Vec<H256> {
+        let slots: HashSet<(Address, U256)> = included.iter().map(|tx| (tx.from(), tx.nonce())).collect();
+        let evicted: Vec<H256> = self.pending.iter().chain(self.queued.iter())
+            .filter(|(_, transaction)| slots.contains(&(transaction.from(), transaction.nonce())))
+            .map(|(hash, _)| *hash)
+            .collect();
+
+        for hash in &evicted {
+            self.remove_transaction(*hash);
+        }
+        evicted
+    }
+
+    /// Replace the transaction `hash` with a signed cancel transaction
+    ///
+    /// The cancel must be signed by the original sender, reuse its nonce and
+    /// pay a strictly higher gas price. It takes the original's place in the
+    /// pool, so including it consumes the nonce instead of the original.
+    pub fn cancel_transaction(&mut self, hash: H256, cancel: Transaction) -> Result<()> {
+        let (original, executable) = match (self.pending.get(&hash), self.queued.get(&hash)) {
+            (Some(transaction), _) => (transaction, true),
+            (None, Some(transaction)) => (transaction, false),
+            (None, None) => return Err(OlympusError::InvalidTransaction(format!("Transaction {:?} is not in the pool", hash))),
+        };
+
+        if cancel.sender()? != original.from() {
+            return Err(OlympusError::InvalidTransaction("Cancel must be signed by the original sender".to_string()));
+        }
+        if cancel.nonce() != original.nonce() {
+            return Err(OlympusError::InvalidTransaction(
+                format!("Cancel nonce {} does not match original nonce {}", cancel.nonce(), original.nonce())
+            ));
+        }
+        if cancel.gas_price() <= original.gas_price() {
+            return Err(OlympusError::InvalidTransaction(
+                format!("Cancel gas price {} must exceed original gas price {}", cancel.gas_price(), original.gas_price())
+            ));
+        }
+
+        self.remove_transaction(hash);
+        self.insert(cancel, executable)?;
+        Ok(())
+    }
+
+    /// Handle a sender's account nonce advancing, e.g. after block application
+    ///
+    /// Transactions with a nonce below `new_nonce` are dropped, and queued
+    /// transactions continuing the sender's nonce sequence from `new_nonce` are
+    /// promoted to pending. Returns the promoted hashes.
+    pub fn on_nonce_advanced(&mut self, sender: Address, new_nonce: u64) -> Vec<H256> {
+        let new_nonce = U256::from(new_nonce);
+        let stale: Vec<H256> = self.by_sender.get(&sender)
+            .map(|nonces| nonces.range(..new_nonce).map(|(_, hash)| *hash).collect())
+            .unwrap_or_default();
+        for hash in stale {
+            self.remove_transaction(hash);
+        }
+
+        let mut promoted = Vec::new();
+        let mut next_nonce = new_nonce;
+        loop {
+            let next = self.by_sender.get(&sender).and_then(|nonces| nonces.get(&next_nonce)).copied();
+
+            match next.and_then(|hash| self.queued.remove(&hash).map(|transaction| (hash, transaction))) {
+                Some((hash, transaction)) => {
+                    self.insert_pending(hash, transaction);
+                    promoted.push(hash);
+                    next_nonce += U256::one();
+                }
+                None => break,
+            }
+        }
+
+        promoted
+    }
+
     /// Get pool statistics
     pub fn get_statistics(&self) -> PoolStatistics {
         PoolStatistics {
@@ -366,3 +747,495 @@ pub struct PoolStatistics {
     pub total_count: usize,
     pub max_size: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::state::MemoryState;
+    use crate::evm::gas_schedule::Hardfork;
+
+    const SECRET: [u8; 32] = [0x11; 32];
+
+    fn signed(mut tx: Transaction) -> Transaction {
+        tx.sign_with_secret(&SECRET).unwrap();
+        tx
+    }
+
+    fn signer() -> Address {
+        signed(Transaction::new(U256::zero(), U256::zero(), U256::from(21_000), Address::zero(), vec![], U256::zero())).sender().unwrap()
+    }
+
+    #[test]
+    fn test_prewarmed_slots_charged_warm_price() {
+        // PUSH20 target BALANCE POP PUSH1 1 SLOAD POP STOP
+        let target = Address::repeat_byte(0x77);
+        let contract = Address::repeat_byte(0x5c);
+        let slot = H256::from_low_u64_be(1);
+        let code = [vec![0x73], target.as_bytes().to_vec(), vec![0x31, 0x50, 0x60, 0x01, 0x54, 0x50, 0x00]].concat();
+        let run = |warm: &[(Address, Vec<H256>)]| {
+            let mut state = MemoryState::new();
+            state.set_balance(signer(), U256::from(10u64).pow(U256::from(18)));
+            state.set_code(contract, code.clone());
+            let mut executor = TransactionExecutor::new(Box::new(state), TransactionExecutionContext::default());
+            let tx = signed(Transaction::new(U256::zero(), U256::from(1_000_000_000u64), U256::from(100_000), contract, vec![], U256::zero()));
+            let result = executor.execute_with_prewarm(tx, warm).unwrap();
+            assert!(result.success);
+            result.gas_used
+        };
+
+        let cold = run(&[]);
+        let warm_account = run(&[(target, vec![])]);
+        let warm_both = run(&[(target, vec![]), (contract, vec![slot])]);
+        // A cold BALANCE costs 2600 and a warm one 100; a cold SLOAD 2100 and a warm one 100
+        assert_eq!(cold - warm_account, U256::from(2600 - 100));
+        assert_eq!(warm_account - warm_both, U256::from(2100 - 100));
+    }
+
+    #[test]
+    fn test_calldata_heavy_transaction_accepted_at_revm_minimum() {
+        let secret = [0x66u8; 32];
+        let gas_price = U256::from(1_000_000_000u64);
+        // Under Prague 1000 non-zero bytes cost 21000 + 16000, but the EIP-7623 floor is 21000 + 40000
+        let tx = |gas: u64| {
+            let mut tx = Transaction::new(U256::zero(), gas_price, U256::from(gas), Address::repeat_byte(0x42), vec![0xff; 1000], U256::zero());
+            tx.sign_with_secret(&secret).unwrap();
+            tx
+        };
+        assert_eq!(tx(61_000).base_gas_required(), 61_000);
+        assert!(tx(61_000).validate(crate::core::transaction::CheckTransaction::Everything).is_ok());
+        assert!(tx(60_999).validate(crate::core::transaction::CheckTransaction::Cheap).is_err());
+
+        let mut state = MemoryState::new();
+        state.set_balance(tx(0).sender().unwrap(), U256::from(10u64).pow(U256::from(18)));
+        let mut executor = TransactionExecutor::new(Box::new(state), TransactionExecutionContext::default());
+        let result = executor.execute_transaction(tx(61_000)).unwrap();
+        assert!(result.success);
+        assert_eq!(result.gas_used, U256::from(61_000));
+    }
+
+    #[test]
+    fn test_intrinsic_gas_priced_by_active_fork() {
+        let secret = [0x67u8; 32];
+        let mut tx = Transaction::new(U256::zero(), U256::from(1_000_000_000u64), U256::from(37_000), Address::repeat_byte(0x42), vec![0xff; 1000], U256::zero());
+        tx.sign_with_secret(&secret).unwrap();
+        let run = |hardfork: Hardfork| {
+            let mut state = MemoryState::new();
+            state.set_balance(tx.sender().unwrap(), U256::from(10u64).pow(U256::from(18)));
+            let mut executor = TransactionExecutor::new(Box::new(state), TransactionExecutionContext::default());
+            executor.executive.set_hardforks(vec![(0, hardfork)]).unwrap();
+            executor.execute_transaction(tx.clone())
+        };
+
+        // Enough for Berlin calldata pricing, below the Prague floor
+        assert!(run(Hardfork::Berlin).unwrap().success);
+        assert!(matches!(
+            run(Hardfork::Prague),
+            Err(OlympusError::InvalidTransaction(message)) if message == "intrinsic gas too low"
+        ));
+        assert!(tx.validate_with_schedule(crate::core::transaction::CheckTransaction::Everything, &GasSchedule::berlin()).is_ok());
+        assert!(tx.validate(crate::core::transaction::CheckTransaction::Everything).is_err());
+    }
+
+    #[test]
+    fn test_failed_transaction_consumes_and_charges_gas() {
+        let initial = U256::from(10u64).pow(U256::from(18));
+        let mut state = MemoryState::new();
+        state.set_balance(signer(), initial);
+        let mut executor = TransactionExecutor::new(Box::new(state), TransactionExecutionContext::default());
+
+        // The recipient's code is the INVALID opcode, which halts and consumes all gas
+        let gas_price = U256::from(1_000_000_000u64);
+        let recipient = Address::repeat_byte(0x5a);
+        executor.state_mut().set_code(recipient, vec![0xfe]);
+        let tx = signed(Transaction::new(U256::from(1000), gas_price, U256::from(50_000), recipient, vec![], U256::zero()));
+
+        let result = executor.execute_block_transactions(vec![tx]).unwrap().remove(0);
+        assert!(!result.success);
+        assert_eq!(result.gas_used, U256::from(50_000));
+
+        let state = executor.state();
+        assert_eq!(state.get_balance(signer()), initial - U256::from(50_000) * gas_price);
+        assert_eq!(state.get_balance(recipient), U256::zero());
+        assert_eq!(state.get_nonce(signer()), 1);
+    }
+
+    #[test]
+    fn test_unfunded_transfer_keeps_nonce() {
+        let secret = [0x44u8; 32];
+        let gas_price = U256::from(1_000_000_000u64);
+        let recipient = Address::repeat_byte(0x5b);
+        let transfer = |nonce: u64| {
+            let mut tx = Transaction::new(U256::from(1000), gas_price, U256::from(21_000), recipient, vec![], U256::from(nonce));
+            tx.sign_with_secret(&secret).unwrap();
+            tx
+        };
+        let sender = transfer(0).sender().unwrap();
+
+        // One wei short of the gas and value
+        let short = U256::from(21_000) * gas_price + U256::from(999);
+        let mut state = MemoryState::new();
+        state.set_balance(sender, short);
+        let mut executor = TransactionExecutor::new(Box::new(state), TransactionExecutionContext::default());
+
+        let result = executor.execute_block_transactions(vec![transfer(0)]).unwrap().remove(0);
+        assert!(!result.success);
+        assert_eq!(result.gas_used, U256::zero());
+        assert_eq!(executor.state().get_nonce(sender), 0);
+        assert_eq!(executor.state().get_balance(sender), short);
+        assert!(!executor.state().exists(recipient));
+
+        // Once funded, the same nonce transfers and costs exactly the intrinsic gas
+        executor.state_mut().set_balance(sender, short + U256::one());
+        let result = executor.execute_transaction(transfer(0)).unwrap();
+        assert!(result.success);
+        assert_eq!(result.gas_used, U256::from(21_000));
+        assert_eq!(executor.state().get_nonce(sender), 1);
+        assert_eq!(executor.state().get_balance(sender), U256::zero());
+        assert_eq!(executor.state().get_balance(recipient), U256::from(1000));
+    }
+
+    #[test]
+    fn test_deployed_contract_code_size() {
+        let mut state = MemoryState::new();
+        state.set_balance(signer(), U256::from(10u64).pow(U256::from(18)));
+        let context = TransactionExecutionContext { base_fee: U256::zero(), ..Default::default() };
+        let mut executor = TransactionExecutor::new(Box::new(state), context);
+
+        // Init code returning 5 bytes of runtime code: PUSH5 .. PUSH1 0 MSTORE PUSH1 5 PUSH1 27 RETURN
+        let init_code = hex::decode("64aabbccddee6000526005601bf3").unwrap();
+        let tx = signed(Transaction::new(U256::zero(), U256::zero(), U256::from(100_000), Address::zero(), init_code, U256::zero()));
+        let result = executor.execute_transaction(tx).unwrap();
+        assert!(result.success);
+
+        assert_eq!(executor.state().code_size(result.contract_address.unwrap()), 5);
+        assert_eq!(executor.state().code_size(Address::zero()), 0);
+    }
+
+    #[test]
+    fn test_nonce_advance_promotes_queued_transaction() {
+        let mut pool = TransactionPool::new(10);
+        let tx = |nonce: u64| Transaction::new(
+            U256::zero(),
+            U256::from(2_000_000_000u64),
+            U256::from(21_000),
+            Address::from([0x42; 20]),
+            vec![],
+            U256::from(nonce),
+        );
+        let sender = tx(0).from();
+        let mut events = pool.subscribe_pending_transactions();
+        let mut notified = || std::iter::from_fn(|| events.try_recv().ok()).map(|tx| tx.hash()).collect::<Vec<_>>();
+
+        // Sender is at nonce 4; nonce 5 and 6 are queued behind it
+        pool.add_transaction_for_nonce(tx(5), 4).unwrap();
+        pool.add_transaction_for_nonce(tx(6), 4).unwrap();
+        pool.add_transaction_for_nonce(tx(4), 4).unwrap();
+        assert_eq!(pool.get_queued_transactions().len(), 2);
+        assert_eq!(notified(), vec![tx(4).hash()]);
+
+        let promoted = pool.on_nonce_advanced(sender, 5);
+        assert_eq!(promoted, vec![tx(5).hash(), tx(6).hash()]);
+        assert!(pool.get_queued_transactions().is_empty());
+        assert_eq!(notified(), promoted);
+
+        // The included nonce-4 transaction is dropped as stale
+        let pending: Vec<_> = pool.get_pending_transactions().iter().map(|t| t.hash()).collect();
+        assert_eq!(pending.len(), 2);
+        assert!(!pending.contains(&tx(4).hash()));
+    }
+
+    #[test]
+    fn test_ordered_pending_groups_senders_by_nonce() {
+        let gwei = U256::from(1_000_000_000u64);
+        let tx = |secret: u8, nonce: u64, gas_price: U256| {
+            let mut tx = Transaction::new(U256::zero(), gas_price, U256::from(21_000), Address::from([0x42; 20]), vec![], U256::from(nonce));
+            tx.sign_with_secret(&[secret; 32]).unwrap();
+            tx
+        };
+
+        // Sender 0x11 leads with 2 gwei and has a gap after nonce 1; sender 0x22 leads with 3 gwei
+        let mut pool = TransactionPool::new(10);
+        pool.add_transaction(tx(0x11, 1, gwei * 5)).unwrap();
+        pool.add_transaction(tx(0x22, 6, gwei * 2)).unwrap();
+        pool.add_transaction(tx(0x11, 3, gwei * 5)).unwrap();
+        pool.add_transaction(tx(0x22, 5, gwei * 3)).unwrap();
+        pool.add_transaction(tx(0x11, 0, gwei * 2)).unwrap();
+
+        let ordered: Vec<_> = pool.get_ordered_pending().iter().map(|t| t.hash()).collect();
+        assert_eq!(ordered, vec![
+            tx(0x22, 5, gwei * 3).hash(),
+            tx(0x22, 6, gwei * 2).hash(),
+            tx(0x11, 0, gwei * 2).hash(),
+            tx(0x11, 1, gwei * 5).hash(),
+        ]);
+    }
+
+    #[test]
+    fn test_same_nonce_replacement_needs_price_bump() {
+        let gwei = U256::from(1_000_000_000u64);
+        let tx = |gas_price: U256| {
+            let mut tx = Transaction::new(U256::zero(), gas_price, U256::from(21_000), Address::from([0x42; 20]), vec![], U256::zero());
+            tx.sign_with_secret(&[0x33; 32]).unwrap();
+            tx
+        };
+
+        let mut pool = TransactionPool::new(10);
+        pool.add_transaction(tx(gwei * 10)).unwrap();
+        assert!(matches!(pool.add_transaction(tx(gwei * 10)), Err(OlympusError::InvalidTransaction(_))));
+
+        // 9% is not enough, 10% replaces the original
+        assert!(pool.add_transaction(tx(gwei * 109 / 10)).is_err());
+        pool.add_transaction(tx(gwei * 11)).unwrap();
+        let pending: Vec<_> = pool.get_pending_transactions().iter().map(|t| t.hash()).collect();
+        assert_eq!(pending, vec![tx(gwei * 11).hash()]);
+    }
+
+    #[test]
+    fn test_full_pool_evicts_cheapest_for_pricier_transaction() {
+        let gwei = U256::from(1_000_000_000u64);
+        let tx = |secret: u8, nonce: u64, gas_price: U256| {
+            let mut tx = Transaction::new(U256::zero(), gas_price, U256::from(21_000), Address::from([0x42; 20]), vec![], U256::from(nonce));
+            tx.sign_with_secret(&[secret; 32]).unwrap();
+            tx
+        };
+
+        let mut pool = TransactionPool::new(3);
+        for nonce in 0..3 {
+            assert_eq!(pool.add_transaction(tx(0x11, nonce, gwei)).unwrap(), None);
+        }
+        assert!(pool.add_transaction(tx(0x22, 0, gwei)).is_err());
+
+        // Evicting the sender's own tail for its next nonce would leave a gap
+        assert!(!pool.can_admit(&tx(0x11, 3, gwei * 100)));
+        assert!(pool.add_transaction(tx(0x11, 3, gwei * 100)).is_err());
+        assert!(pool.get_transaction(tx(0x11, 2, gwei).hash()).is_some());
+
+        // Only the sender's highest nonce is evictable
+        let evicted = pool.add_transaction(tx(0x22, 0, gwei * 100)).unwrap().unwrap();
+        assert_eq!(evicted, tx(0x11, 2, gwei).hash());
+        assert!(pool.get_transaction(evicted).is_none());
+        assert!(pool.get_transaction(tx(0x22, 0, gwei * 100).hash()).is_some());
+        assert_eq!(pool.get_statistics().total_count, 3);
+    }
+
+    #[test]
+    fn test_full_pool_evicts_cheapest_sender_tail() {
+        let gwei = U256::from(1_000_000_000u64);
+        let tx = |secret: u8, nonce: u64, gas_price: U256| {
+            let mut tx = Transaction::new(U256::zero(), gas_price, U256::from(21_000), Address::from([0x42; 20]), vec![], U256::from(nonce));
+            tx.sign_with_secret(&[secret; 32]).unwrap();
+            tx
+        };
+
+        // Sender 0x11's cheap nonce 0 is followed by a pricey nonce 1, so sender 0x22's tail is cheapest
+        let mut pool = TransactionPool::new(3);
+        pool.add_transaction(tx(0x11, 0, gwei * 2)).unwrap();
+        pool.add_transaction(tx(0x11, 1, gwei * 20)).unwrap();
+        pool.add_transaction(tx(0x22, 0, gwei * 5)).unwrap();
+
+        assert!(!pool.can_admit(&tx(0x33, 0, gwei * 3)));
+        assert!(pool.add_transaction(tx(0x33, 0, gwei * 3)).is_err());
+
+        let evicted = pool.add_transaction(tx(0x33, 0, gwei * 6)).unwrap();
+        assert_eq!(evicted, Some(tx(0x22, 0, gwei * 5).hash()));
+        assert!(pool.get_transaction(tx(0x11, 0, gwei * 2).hash()).is_some());
+
+        // Once 0x11's tail is gone, its nonce 0 becomes the cheapest tail
+        pool.remove_transaction(tx(0x11, 1, gwei * 20).hash());
+        pool.add_transaction(tx(0x22, 0, gwei * 5)).unwrap();
+        let evicted = pool.add_transaction(tx(0x44, 0, gwei * 3)).unwrap();
+        assert_eq!(evicted, Some(tx(0x11, 0, gwei * 2).hash()));
+    }
+
+    #[test]
+    fn test_expired_transaction_rejected_at_inclusion() {
+        let mut state = MemoryState::new();
+        state.set_balance(signer(), U256::from(10u64).pow(U256::from(18)));
+        let context = TransactionExecutionContext {
+            block_number: U256::from(10),
+            ..Default::default()
+        };
+        let mut executor = TransactionExecutor::new(Box::new(state), context);
+
+        let tx = |nonce: u64, valid_until: u64| {
+            let mut tx = Transaction::new(U256::zero(), U256::from(1_000_000_000u64), U256::from(100_000), Address::repeat_byte(0x42), vec![0x01], U256::from(nonce));
+            tx.set_valid_until(Some(valid_until));
+            signed(tx)
+        };
+
+        assert!(executor.execute_transaction(tx(0, 9)).is_err());
+        assert!(executor.execute_transaction(tx(0, 10)).unwrap().success);
+
+        let mut pool = TransactionPool::new(10);
+        pool.add_transaction(tx(1, 9)).unwrap();
+        pool.add_transaction(tx(2, 10)).unwrap();
+        assert_eq!(pool.prune_expired(10), vec![tx(1, 9).hash()]);
+        assert_eq!(pool.get_statistics().total_count, 1);
+    }
+
+    #[test]
+    fn test_self_transfer_only_consumes_gas() {
+        let mut tx = Transaction::new(U256::from(5_000), U256::from(1_000_000_000u64), U256::from(21_000), Address::zero(), vec![], U256::zero());
+        tx.sign_with_secret(&[0x42u8; 32]).unwrap();
+        let account = tx.sender().unwrap();
+        tx.receive_address = account;
+        tx.sign_with_secret(&[0x42u8; 32]).unwrap();
+
+        let initial = U256::from(10u64).pow(U256::from(18));
+        let mut state = MemoryState::new();
+        state.set_balance(account, initial);
+        let mut executor = TransactionExecutor::new(Box::new(state), TransactionExecutionContext::default());
+
+        let result = executor.execute_transaction(tx).unwrap();
+        assert!(result.success);
+        assert_eq!(executor.state().get_balance(account), initial - result.gas_used * result.gas_price);
+        assert_eq!(executor.state().get_nonce(account), 1);
+    }
+
+    #[test]
+    fn test_priority_fee_paid_to_coinbase() {
+        let gwei = U256::from(1_000_000_000u64);
+        let coinbase = Address::repeat_byte(0xcb);
+        let run = |priority_fee: U256| {
+            let secret = [0x55u8; 32];
+            let mut tx = Transaction::new_eip1559(U256::zero(), gwei * 10, priority_fee, U256::from(100_000), Address::repeat_byte(0x42), vec![0x01], U256::zero());
+            tx.sign_with_secret(&secret).unwrap();
+            let sender = tx.sender().unwrap();
+
+            let initial = U256::from(10u64).pow(U256::from(18));
+            let mut state = MemoryState::new();
+            state.set_balance(sender, initial);
+            let context = TransactionExecutionContext { base_fee: gwei, coinbase, ..Default::default() };
+            let mut executor = TransactionExecutor::new(Box::new(state), context);
+
+            let result = executor.execute_transaction(tx).unwrap();
+            assert!(result.success);
+            assert_eq!(executor.state().get_balance(sender), initial - result.gas_used * result.gas_price);
+            (result.gas_used, executor.state().get_balance(coinbase))
+        };
+
+        let (gas_used, low_tip) = run(gwei * 2);
+        assert_eq!(low_tip, gas_used * gwei * 2);
+        // A priority fee above the max fee less the base fee only tips up to the cap
+        let (gas_used, capped_tip) = run(gwei * 10);
+        assert_eq!(capped_tip, gas_used * gwei * 9);
+    }
+
+    #[test]
+    fn test_log1_topic_and_data_reach_result() {
+        // PUSH1 0xab PUSH1 0 MSTORE8 PUSH32 topic PUSH1 1 PUSH1 0 LOG1 STOP
+        let topic = H256::repeat_byte(0x5e);
+        let init_code = [vec![0x60, 0xab, 0x60, 0x00, 0x53, 0x7f], topic.as_bytes().to_vec(), vec![0x60, 0x01, 0x60, 0x00, 0xa1, 0x00]].concat();
+        let context = TransactionExecutionContext { base_fee: U256::zero(), ..Default::default() };
+        let mut executor = TransactionExecutor::new(Box::new(MemoryState::new()), context);
+
+        let tx = signed(Transaction::new_contract_creation(U256::zero(), U256::zero(), U256::from(100_000), init_code, U256::zero()));
+        let result = executor.execute_transaction(tx).unwrap();
+        assert!(result.success);
+        assert_eq!(result.logs.len(), 1);
+        assert_eq!(result.logs[0].address, result.contract_address.unwrap());
+        assert_eq!(result.logs[0].topics, vec![topic]);
+        assert_eq!(result.logs[0].data, vec![0xab]);
+    }
+
+    #[test]
+    fn test_block_receipts_accumulate_gas_and_bloom_topics() {
+        // PUSH32 topic PUSH1 0 PUSH1 0 LOG1 STOP
+        let topic = H256::repeat_byte(0x5e);
+        let init_code = [vec![0x7f], topic.as_bytes().to_vec(), vec![0x60, 0x00, 0x60, 0x00, 0xa1, 0x00]].concat();
+        let context = TransactionExecutionContext { base_fee: U256::zero(), ..Default::default() };
+        let mut state = MemoryState::new();
+        state.set_balance(signer(), U256::one());
+        let mut executor = TransactionExecutor::new(Box::new(state), context);
+
+        let logging = signed(Transaction::new_contract_creation(U256::zero(), U256::zero(), U256::from(100_000), init_code, U256::zero()));
+        let transfer = signed(Transaction::new(U256::from(1), U256::zero(), U256::from(21_000), Address::repeat_byte(0x42), vec![], U256::one()));
+        let results = executor.execute_block_transactions(vec![logging, transfer]).unwrap();
+
+        let receipts = executor.receipts();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].cumulative_gas_used, results[0].gas_used);
+        assert_eq!(receipts[1].cumulative_gas_used, results[0].gas_used + results[1].gas_used);
+        assert!(receipts[0].status && receipts[0].contract_address.is_some());
+
+        assert!(receipts[0].bloom_contains(topic.as_bytes()));
+        assert!(receipts[0].bloom_contains(receipts[0].contract_address.unwrap().as_bytes()));
+        assert!(!receipts[0].bloom_contains(H256::repeat_byte(0x5f).as_bytes()));
+        assert_eq!(receipts[1].logs_bloom, [0u8; 256]);
+    }
+
+    #[test]
+    fn test_transactions_past_block_gas_limit_skipped() {
+        let mut state = MemoryState::new();
+        state.set_balance(signer(), U256::from(10u64).pow(U256::from(18)));
+        let context = TransactionExecutionContext {
+            base_fee: U256::zero(),
+            block_gas_limit: U256::from(50_000),
+            ..Default::default()
+        };
+        let mut executor = TransactionExecutor::new(Box::new(state), context);
+
+        let transfer = |nonce: u64, gas: u64| Transaction::new(U256::one(), U256::zero(), U256::from(gas), Address::from([0x22; 20]), vec![], U256::from(nonce));
+        let unsigned = transfer(1, 21_000);
+        let transactions = vec![
+            signed(transfer(0, 30_000)),
+            signed(transfer(1, 30_000)),
+            unsigned.clone(),
+            signed(transfer(1, 21_000)),
+            signed(transfer(2, 21_000)),
+        ];
+        let results = executor.execute_block_transactions(transactions.clone()).unwrap();
+
+        // After 21000 gas a 30000 gas limit no longer fits, but a 21000 one still does
+        let statuses: Vec<_> = results.iter().map(|result| result.status).collect();
+        assert_eq!(statuses, vec![
+            ExecutionStatus::Success,
+            ExecutionStatus::Skipped,
+            ExecutionStatus::Rejected,
+            ExecutionStatus::Success,
+            ExecutionStatus::Skipped,
+        ]);
+        for (result, tx) in results.iter().zip(&transactions) {
+            assert_eq!(result.transaction_hash, tx.hash());
+        }
+        assert!(results[2].error.is_some());
+
+        // Skipped and rejected transactions get no receipt
+        assert_eq!(executor.receipts().len(), 2);
+        assert_eq!(executor.receipts()[1].cumulative_gas_used, U256::from(42_000));
+        assert_eq!(executor.state().get_nonce(signer()), 2);
+    }
+
+    #[test]
+    fn test_log_limits_fail_transactions_over_cap() {
+        // Init code emitting `count` empty logs: PUSH1 0x00 PUSH1 0x00 LOG0
+        let gas = U256::from(100_000);
+        let run = |count: usize, log_limits: LogLimits| {
+            let mut state = MemoryState::new();
+            state.set_balance(signer(), U256::from(10u64).pow(U256::from(18)));
+            let context = TransactionExecutionContext {
+                base_fee: U256::zero(),
+                log_limits,
+                ..Default::default()
+            };
+            let mut executor = TransactionExecutor::new(Box::new(state), context);
+
+            let tx = signed(Transaction::new(U256::zero(), U256::zero(), gas, Address::zero(), [0x60, 0x00, 0x60, 0x00, 0xa0].repeat(count), U256::zero()));
+            executor.execute_block_transactions(vec![tx]).unwrap().remove(0)
+        };
+        let per_transaction = LogLimits { max_per_transaction: Some(2), max_per_block: None };
+
+        let under = run(2, per_transaction);
+        assert!(under.success);
+        assert_eq!(under.logs.len(), 2);
+
+        let over = run(3, per_transaction);
+        assert!(!over.success);
+        assert_eq!(over.status, ExecutionStatus::LogLimitExceeded);
+        assert!(over.logs.is_empty());
+        assert_eq!(over.gas_used, gas);
+
+        let over_block = run(2, LogLimits { max_per_transaction: None, max_per_block: Some(1) });
+        assert_eq!(over_block.status, ExecutionStatus::LogLimitExceeded);
+    }
+}
@@ -1,10 +1,22 @@
 //! Olympus blockchain node main entry point
 
 use clap::{Parser, Subcommand};
-use olympus::core::config::Config;
-use olympus::Result;
+use libp2p::Multiaddr;
+use olympus::chain::ChainManager;
+use olympus::core::block::Block;
+use olympus::core::config::{Config, LoggingConfig};
+use olympus::core::genesis::Genesis;
+use olympus::core::logging::init_logging;
+use olympus::db::{BlockStore, SledDatabase};
+use olympus::evm::state::{MemoryState, State};
+use olympus::evm::transaction_executor::{TransactionExecutionContext, TransactionExecutor, TransactionPool};
+use olympus::p2p::{NetworkManager, NetworkMessage, INVALID_MESSAGE_PENALTY};
+use olympus::{OlympusError, Result, U256};
 use std::path::PathBuf;
-use tracing::info;
+use tracing::{info, warn};
+
+/// Most transactions the node's pool holds
+const TRANSACTION_POOL_SIZE: usize = 4096;
 
 #[derive(Parser)]
 #[command(name = "olympus")]
@@ -25,6 +37,9 @@ enum Commands {
         /// Configuration file path
         #[arg(long)]
         config: Option<PathBuf>,
+        /// Genesis file path, the default genesis if omitted
+        #[arg(long)]
+        genesis: Option<PathBuf>,
         /// Run as witness node
         #[arg(long)]
         witness: bool,
@@ -47,23 +62,25 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt::init();
-
     let cli = Cli::parse();
 
     match cli.command {
         Commands::Start {
             data_path,
             config,
+            genesis,
             witness,
             witness_account,
             password,
         } => {
+            let config = load_config(config)?;
+            let genesis = load_genesis(genesis)?;
+            init_logging(&config.logging)?;
             info!("Starting Olympus node...");
-            start_node(data_path, config, witness, witness_account, password).await?;
+            start_node(data_path, config, genesis, witness, witness_account, password).await?;
         }
         Commands::Init { output } => {
+            init_logging(&LoggingConfig::default())?;
             info!("Initializing configuration...");
             init_config(output)?;
         }
@@ -78,36 +95,55 @@ async fn main() -> Result<()> {
 
 async fn start_node(
     data_path: PathBuf,
-    config_path: Option<PathBuf>,
+    config: Config,
+    genesis: Genesis,
     _witness: bool,
     _witness_account: Option<PathBuf>,
     _password: Option<String>,
 ) -> Result<()> {
-    // Load configuration
-    let config = if let Some(path) = config_path {
-        Config::load_from_file(path)
-            .map_err(|e| olympus::OlympusError::Serialization(e.to_string()))?
-    } else {
-        Config::default()
-    };
-
     info!("Configuration loaded");
     info!("Data path: {:?}", data_path);
+    info!("Chain ID: {}, genesis: {:?}", genesis.chain_id(), genesis.hash());
     info!("Network: {}:{}", config.network.listen_address, config.network.listen_port);
     info!("RPC: {}:{}", config.rpc.listen_address, config.rpc.listen_port);
 
     // Create data directory if it doesn't exist
     if !data_path.exists() {
         std::fs::create_dir_all(&data_path)
-            .map_err(|e| olympus::OlympusError::Database(e.to_string()))?;
+            .map_err(|e| OlympusError::Database(e.to_string()))?;
         info!("Created data directory: {:?}", data_path);
     }
 
-    // TODO: Initialize database
     info!("Initializing database...");
+    let block_store = BlockStore::new(SledDatabase::new(&data_path.join("chain").to_string_lossy())?);
+    block_store.check_genesis(genesis.hash())?;
+    let genesis_state = || -> Box<dyn State> {
+        let mut state = MemoryState::new();
+        genesis.build_state(&mut state);
+        Box::new(state)
+    };
+    let context = TransactionExecutionContext {
+        block_gas_limit: U256::from(config.consensus.gas_limit),
+        ..Default::default()
+    };
+    let executor = TransactionExecutor::new(genesis_state(), context);
+    let mut chain = ChainManager::new(executor, TransactionPool::new(TRANSACTION_POOL_SIZE));
+    chain.set_block_store(block_store);
+    chain.replay_from_genesis(genesis_state())?;
+    info!("Chain head at block {}", chain.head_number());
 
-    // TODO: Initialize P2P network
     info!("Initializing P2P network...");
+    let mut network = NetworkManager::from_config(&config.network, &genesis)?;
+    let listen_address: Multiaddr = format!("/ip4/{}/tcp/{}", config.network.listen_address, config.network.listen_port)
+        .parse()
+        .map_err(|e| OlympusError::Network(format!("Invalid listen address: {}", e)))?;
+    network.start(listen_address)?;
+    for node in &config.network.bootstrap_nodes {
+        match node.parse::<Multiaddr>() {
+            Ok(address) => network.dial(address)?,
+            Err(e) => warn!("Skipping bootstrap node {}: {}", node, e),
+        }
+    }
 
     // TODO: Initialize consensus engine
     info!("Initializing consensus engine...");
@@ -124,17 +160,66 @@ async fn start_node(
 
     info!("Olympus node started successfully!");
 
-    // Keep the node running
-    tokio::signal::ctrl_c().await
-        .map_err(|e| olympus::OlympusError::Network(e.to_string()))?;
+    // Drive the network and import received blocks until interrupted
+    loop {
+        tokio::select! {
+            polled = network.poll_network() => {
+                polled?;
+                import_blocks(&mut network, &mut chain);
+            }
+            signal = tokio::signal::ctrl_c() => {
+                signal.map_err(|e| OlympusError::Network(e.to_string()))?;
+                break;
+            }
+        }
+    }
 
     info!("Shutting down Olympus node...");
     Ok(())
 }
 
+/// Import the blocks received from peers, penalizing peers that sent invalid ones
+fn import_blocks(network: &mut NetworkManager, chain: &mut ChainManager) {
+    for (peer_id, message) in network.handle_inbound(chain.pool_mut()) {
+        let NetworkMessage::Block { block_hash, block_data } = message else {
+            continue;
+        };
+        let imported = rlp::decode::<Block>(&block_data)
+            .map_err(OlympusError::from)
+            .and_then(|block| {
+                if block.hash() != block_hash {
+                    return Err(OlympusError::InvalidBlock(format!("Block data does not hash to {:?}", block_hash)));
+                }
+                chain.import_block(&block)
+            });
+        match imported {
+            Ok(_) => info!("Imported block {:?} at height {}", block_hash, chain.head_number()),
+            Err(e) => {
+                warn!("Rejected block {:?} from {}: {}", block_hash, peer_id, e);
+                network.penalize_peer(peer_id, INVALID_MESSAGE_PENALTY);
+            }
+        }
+    }
+}
+
+fn load_config(config_path: Option<PathBuf>) -> Result<Config> {
+    match config_path {
+        Some(path) => Config::load_from_file(path)
+            .map_err(|e| OlympusError::Serialization(e.to_string())),
+        None => Ok(Config::default()),
+    }
+}
+
+fn load_genesis(genesis_path: Option<PathBuf>) -> Result<Genesis> {
+    match genesis_path {
+        Some(path) => Genesis::load_from_file(&path),
+        None => Ok(Genesis::default()),
+    }
+}
+
 fn init_config(output: PathBuf) -> Result<()> {
     Config::create_default_config(output)
-        .map_err(|e| olympus::OlympusError::Serialization(e.to_string()))?;
+        .map_err(|e| OlympusError::Serialization(e.to_string()))?;
     
     println!("Configuration file created successfully!");
     Ok(())
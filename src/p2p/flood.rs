@@ -0,0 +1,310 @@
+//! Flood publishing over libp2p
+//!
+//! Every payload is written to each connected peer on its own substream of
+//! the flood protocol, which acts as the single topic all nodes subscribe to.
+
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{AsyncRead, AsyncReadExt, AsyncWriteExt, FutureExt, StreamExt};
+use libp2p::core::upgrade::ReadyUpgrade;
+use libp2p::core::Endpoint;
+use libp2p::swarm::handler::{ConnectionEvent, FullyNegotiatedInbound, FullyNegotiatedOutbound};
+use libp2p::swarm::{
+    ConnectionDenied, ConnectionHandler, ConnectionHandlerEvent, ConnectionId, FromSwarm,
+    NetworkBehaviour, NotifyHandler, SubstreamProtocol, THandler, THandlerInEvent, THandlerOutEvent,
+    ToSwarm,
+};
+use libp2p::{Multiaddr, PeerId, Stream, StreamProtocol};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+
+/// Protocol carrying flooded payloads
+pub const FLOOD_PROTOCOL: StreamProtocol = StreamProtocol::new("/olympus/flood/1");
+
+/// Largest payload accepted from a peer
+pub const MAX_PAYLOAD_SIZE: usize = 16 * 1024 * 1024;
+
+/// Most substreams a peer may have reading payloads at once, across its connections
+pub const MAX_INBOUND_SUBSTREAMS: usize = 32;
+
+/// Payload received from a peer
+#[derive(Debug)]
+pub struct FloodEvent {
+    /// Sending peer
+    pub peer_id: PeerId,
+    /// Received bytes
+    pub payload: Vec<u8>,
+}
+
+/// Behaviour sending payloads to connected peers
+#[derive(Default)]
+pub struct Flood {
+    /// Peers with at least one open connection and their count of substreams being read
+    peers: HashMap<PeerId, Arc<AtomicUsize>>,
+    /// Events waiting to be returned to the swarm
+    events: VecDeque<ToSwarm<FloodEvent, Vec<u8>>>,
+    /// Waker of the swarm task, woken when events are queued
+    waker: Option<Waker>,
+}
+
+impl Flood {
+    /// Send a payload to every connected peer, returning the number of peers
+    pub fn publish(&mut self, payload: Vec<u8>) -> usize {
+        let peers: Vec<PeerId> = self.peers.keys().copied().collect();
+        for peer_id in &peers {
+            self.send(*peer_id, payload.clone());
+        }
+        peers.len()
+    }
+
+    /// Send a payload to one peer; dropped if the peer is not connected
+    pub fn send(&mut self, peer_id: PeerId, payload: Vec<u8>) {
+        self.events.push_back(ToSwarm::NotifyHandler { peer_id, handler: NotifyHandler::Any, event: payload });
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Peers with at least one open connection
+    pub fn connected_peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.peers.keys()
+    }
+
+    /// Build a handler sharing the peer's inbound substream count
+    fn handler(&mut self, peer_id: PeerId) -> FloodHandler {
+        FloodHandler::new(self.peers.entry(peer_id).or_default().clone())
+    }
+}
+
+impl NetworkBehaviour for Flood {
+    type ConnectionHandler = FloodHandler;
+    type ToSwarm = FloodEvent;
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer: PeerId,
+        _local_addr: &Multiaddr,
+        _remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        Ok(self.handler(peer))
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        peer: PeerId,
+        _addr: &Multiaddr,
+        _role_override: Endpoint,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        Ok(self.handler(peer))
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        match event {
+            FromSwarm::ConnectionEstablished(established) => {
+                self.peers.entry(established.peer_id).or_default();
+            }
+            FromSwarm::ConnectionClosed(closed) if closed.remaining_established == 0 => {
+                self.peers.remove(&closed.peer_id);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        peer_id: PeerId,
+        _connection_id: ConnectionId,
+        payload: THandlerOutEvent<Self>,
+    ) {
+        self.events.push_back(ToSwarm::GenerateEvent(FloodEvent { peer_id, payload }));
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        match self.events.pop_front() {
+            Some(event) => Poll::Ready(event),
+            None => {
+                self.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Connection handler writing and reading one payload per substream
+pub struct FloodHandler {
+    /// Substreams of the peer reading payloads, shared by its connections
+    inbound: Arc<AtomicUsize>,
+    /// Payloads waiting for an outbound substream
+    queued: VecDeque<Vec<u8>>,
+    /// Substreams writing a payload
+    sending: FuturesUnordered<BoxFuture<'static, io::Result<()>>>,
+    /// Substreams reading a payload
+    receiving: FuturesUnordered<BoxFuture<'static, io::Result<Vec<u8>>>>,
+}
+
+impl FloodHandler {
+    /// Create a handler counting inbound substreams in the peer's shared count
+    fn new(inbound: Arc<AtomicUsize>) -> Self {
+        Self {
+            inbound,
+            queued: VecDeque::new(),
+            sending: FuturesUnordered::new(),
+            receiving: FuturesUnordered::new(),
+        }
+    }
+}
+
+impl ConnectionHandler for FloodHandler {
+    type FromBehaviour = Vec<u8>;
+    type ToBehaviour = Vec<u8>;
+    type InboundProtocol = ReadyUpgrade<StreamProtocol>;
+    type OutboundProtocol = ReadyUpgrade<StreamProtocol>;
+    type InboundOpenInfo = ();
+    type OutboundOpenInfo = Vec<u8>;
+
+    fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol, Self::InboundOpenInfo> {
+        SubstreamProtocol::new(ReadyUpgrade::new(FLOOD_PROTOCOL), ())
+    }
+
+    /// Gossip connections stay open while idle
+    fn connection_keep_alive(&self) -> bool {
+        true
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<ConnectionHandlerEvent<Self::OutboundProtocol, Self::OutboundOpenInfo, Self::ToBehaviour>> {
+        if let Some(payload) = self.queued.pop_front() {
+            return Poll::Ready(ConnectionHandlerEvent::OutboundSubstreamRequest {
+                protocol: SubstreamProtocol::new(ReadyUpgrade::new(FLOOD_PROTOCOL), payload),
+            });
+        }
+
+        // Failed writes only lose that payload; gossip is best effort
+        while let Poll::Ready(Some(_)) = self.sending.poll_next_unpin(cx) {}
+
+        while let Poll::Ready(Some(received)) = self.receiving.poll_next_unpin(cx) {
+            if let Ok(payload) = received {
+                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(payload));
+            }
+        }
+
+        Poll::Pending
+    }
+
+    fn on_behaviour_event(&mut self, payload: Self::FromBehaviour) {
+        self.queued.push_back(payload);
+    }
+
+    fn on_connection_event(
+        &mut self,
+        event: ConnectionEvent<Self::InboundProtocol, Self::OutboundProtocol, Self::InboundOpenInfo, Self::OutboundOpenInfo>,
+    ) {
+        match event {
+            ConnectionEvent::FullyNegotiatedInbound(FullyNegotiatedInbound { protocol: stream, .. }) => {
+                // Substreams past the peer's limit are dropped, which resets them
+                let Some(slot) = InboundSlot::acquire(&self.inbound) else {
+                    return;
+                };
+                self.receiving.push(async move {
+                    let payload = read_payload(stream).await;
+                    drop(slot);
+                    payload
+                }.boxed());
+            }
+            ConnectionEvent::FullyNegotiatedOutbound(FullyNegotiatedOutbound { protocol: stream, info: payload }) => {
+                self.sending.push(write_payload(stream, payload).boxed());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One of a peer's inbound substreams, released when dropped
+struct InboundSlot(Arc<AtomicUsize>);
+
+impl InboundSlot {
+    /// Take a slot from the peer's count, or `None` once it is at the limit
+    fn acquire(count: &Arc<AtomicUsize>) -> Option<Self> {
+        count.fetch_update(Ordering::AcqRel, Ordering::Acquire, |open| {
+            (open < MAX_INBOUND_SUBSTREAMS).then_some(open + 1)
+        }).ok()?;
+        Some(Self(count.clone()))
+    }
+}
+
+impl Drop for InboundSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Read one length-prefixed payload from a substream
+///
+/// The buffer grows with the bytes actually received, so a peer announcing a
+/// large length cannot make us allocate it up front.
+async fn read_payload<S: AsyncRead + Unpin>(mut stream: S) -> io::Result<Vec<u8>> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length).await?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_PAYLOAD_SIZE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Payload of {} bytes is too large", length)));
+    }
+
+    let mut payload = Vec::new();
+    stream.take(length as u64).read_to_end(&mut payload).await?;
+    if payload.len() != length {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Payload ended early"));
+    }
+    Ok(payload)
+}
+
+/// Write one length-prefixed payload to a substream and close it
+async fn write_payload(mut stream: Stream, payload: Vec<u8>) -> io::Result<()> {
+    stream.write_all(&(payload.len() as u32).to_be_bytes()).await?;
+    stream.write_all(&payload).await?;
+    stream.close().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::io::Cursor;
+
+    fn framed(length: u32, payload: &[u8]) -> Cursor<Vec<u8>> {
+        let mut bytes = length.to_be_bytes().to_vec();
+        bytes.extend_from_slice(payload);
+        Cursor::new(bytes)
+    }
+
+    #[tokio::test]
+    async fn test_read_payload_checks_announced_length() {
+        assert_eq!(read_payload(framed(3, b"abc")).await.unwrap(), b"abc");
+
+        let oversized = read_payload(framed(MAX_PAYLOAD_SIZE as u32 + 1, b"")).await.unwrap_err();
+        assert_eq!(oversized.kind(), io::ErrorKind::InvalidData);
+
+        // A large announced length with few bytes behind it fails without reading past the stream
+        let truncated = read_payload(framed(MAX_PAYLOAD_SIZE as u32, b"abc")).await.unwrap_err();
+        assert_eq!(truncated.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_inbound_substreams_limited_per_peer() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let slots: Vec<InboundSlot> = (0..MAX_INBOUND_SUBSTREAMS)
+            .map(|_| InboundSlot::acquire(&count).unwrap())
+            .collect();
+        assert!(InboundSlot::acquire(&count).is_none());
+
+        drop(slots);
+        assert_eq!(count.load(Ordering::Acquire), 0);
+        assert!(InboundSlot::acquire(&count).is_some());
+    }
+}
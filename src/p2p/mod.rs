@@ -1,7 +1,9 @@
 //! P2P networking
 
+pub mod flood;
 pub mod network;
 pub mod peer;
 
+pub use flood::*;
 pub use network::*;
 pub use peer::*;
@@ -1,16 +1,64 @@
 //! P2P network implementation
 
-use crate::Result;
-use libp2p::{identity, Multiaddr, PeerId};
+use crate::{H256, Result, OlympusError};
+use crate::common::{Clock, SystemClock};
+use crate::core::config::{NetworkConfig, PeerScoring, TransactionPropagation, DEFAULT_SEEN_CACHE_CAPACITY};
+use crate::core::genesis::Genesis;
+use crate::core::transaction::{CheckTransaction, Transaction};
+use crate::evm::transaction_executor::TransactionPool;
+use crate::p2p::flood::{Flood, FloodEvent};
+use futures::StreamExt;
+use libp2p::swarm::SwarmEvent;
+use libp2p::{identity, Multiaddr, PeerId, Swarm};
+use lru::LruCache;
+use rand::seq::SliceRandom;
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
 use serde::{Serialize, Deserialize};
 
+/// P2P wire protocol version
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// How long a connection with no open substreams stays up
+const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Score lost for a message that fails to decode or validate
+pub const INVALID_MESSAGE_PENALTY: f64 = 0.5;
+
 /// P2P network manager
 pub struct NetworkManager {
     /// Local peer ID
     pub peer_id: PeerId,
+    /// Identity keypair the peer ID derives from
+    keypair: identity::Keypair,
+    /// Running libp2p swarm, once started
+    swarm: Option<Swarm<Flood>>,
     /// Connected peers
     pub peers: HashMap<PeerId, PeerInfo>,
+    /// Chain ID announced in the handshake
+    pub chain_id: u64,
+    /// Genesis hash announced in the handshake
+    pub genesis_hash: H256,
+    /// Hashes of blocks and transactions recently gossiped, least recent evicted first
+    seen: LruCache<H256, ()>,
+    /// Messages waiting to be written to the transport
+    outbound: Vec<NetworkMessage>,
+    /// Messages waiting to be written to a single peer
+    peer_outbound: Vec<(PeerId, NetworkMessage)>,
+    /// Transaction hashes requested from peers and not yet answered
+    requested: LruCache<H256, PeerId>,
+    /// How gossiped transactions reach peers
+    transaction_propagation: TransactionPropagation,
+    /// Messages received from peers
+    inbound: Vec<(PeerId, NetworkMessage)>,
+    /// Peer score limits and bans
+    peer_scoring: PeerScoring,
+    /// Banned peers and the time their ban ends
+    banned: HashMap<PeerId, u64>,
+    /// Time source for bans
+    clock: Arc<dyn Clock>,
 }
 
 /// Peer information
@@ -20,6 +68,8 @@ pub struct PeerInfo {
     pub address: Multiaddr,
     /// Connection status
     pub connected: bool,
+    /// Whether the peer's Status matched our chain on the current connection
+    pub handshake_complete: bool,
     /// Last seen timestamp
     pub last_seen: u64,
     /// Peer score
@@ -39,12 +89,49 @@ pub enum NetworkMessage {
         transaction_hash: crate::H256,
         transaction_data: Vec<u8>,
     },
+    /// Hashes of transactions available from the sender's pool
+    NewPooledTransactionHashes {
+        transaction_hashes: Vec<H256>,
+    },
+    /// Request for the bodies of announced transactions
+    GetPooledTransactions {
+        transaction_hashes: Vec<H256>,
+    },
+    /// Transaction bodies answering a request
+    PooledTransactions {
+        transactions: Vec<Vec<u8>>,
+    },
+    /// Handshake status message
+    Status {
+        chain_id: u64,
+        genesis_hash: H256,
+    },
     /// Ping message
     Ping,
     /// Pong message
     Pong,
 }
 
+impl NetworkMessage {
+    /// Get the key identifying the message content in the gossip seen-set
+    ///
+    /// Content messages are keyed by the announced block or transaction hash,
+    /// so differing encodings of the same item share a key. Control messages
+    /// return `None` and are never deduplicated.
+    pub fn dedup_key(&self) -> Option<H256> {
+        match self {
+            NetworkMessage::Block { block_hash, .. } => Some(*block_hash),
+            NetworkMessage::Transaction { transaction_hash, .. } => Some(*transaction_hash),
+            NetworkMessage::NewPooledTransactionHashes { .. }
+            | NetworkMessage::GetPooledTransactions { .. }
+            | NetworkMessage::PooledTransactions { .. }
+            | NetworkMessage::Status { .. }
+            | NetworkMessage::Ping
+            | NetworkMessage::Pong => None,
+        }
+    }
+}
+
 impl NetworkManager {
     /// Create new network manager
     pub fn new() -> Result<Self> {
@@ -53,22 +140,306 @@ impl NetworkManager {
         
         Ok(Self {
             peer_id,
+            keypair,
+            swarm: None,
             peers: HashMap::new(),
+            chain_id: crate::core::types::CHAIN_ID,
+            genesis_hash: H256::zero(),
+            seen: LruCache::new(seen_cache_capacity(DEFAULT_SEEN_CACHE_CAPACITY)),
+            outbound: Vec::new(),
+            peer_outbound: Vec::new(),
+            requested: LruCache::new(seen_cache_capacity(DEFAULT_SEEN_CACHE_CAPACITY)),
+            transaction_propagation: TransactionPropagation::default(),
+            inbound: Vec::new(),
+            peer_scoring: PeerScoring::default(),
+            banned: HashMap::new(),
+            clock: Arc::new(SystemClock),
         })
     }
 
+    /// Create a network manager on the chain of `genesis`, with the propagation,
+    /// scoring and cache settings of a config
+    pub fn from_config(config: &NetworkConfig, genesis: &Genesis) -> Result<Self> {
+        let mut network = Self::new()?;
+        network.set_genesis(genesis);
+        network.set_transaction_propagation(config.transaction_propagation);
+        network.set_peer_scoring(config.peer_scoring);
+        network.set_seen_cache_capacity(config.seen_cache_capacity);
+        Ok(network)
+    }
+
+    /// Set the peer score limits and ban duration
+    pub fn set_peer_scoring(&mut self, peer_scoring: PeerScoring) {
+        self.peer_scoring = peer_scoring;
+    }
+
+    /// Set how many gossiped hashes are remembered for deduplication
+    pub fn set_seen_cache_capacity(&mut self, capacity: usize) {
+        self.seen.resize(seen_cache_capacity(capacity));
+    }
+
+    /// Check if a block or transaction hash was gossiped recently
+    pub fn already_seen(&self, hash: H256) -> bool {
+        self.seen.contains(&hash)
+    }
+
+    /// Record a gossiped hash, returning `false` if it was already seen
+    fn mark_seen(&mut self, hash: H256) -> bool {
+        self.seen.put(hash, ()).is_none()
+    }
+
+    /// Set the time source used for bans
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Start the libp2p swarm listening on `listen_address`
+    ///
+    /// Must be called within a Tokio runtime.
+    pub fn start(&mut self, listen_address: Multiaddr) -> Result<()> {
+        let mut swarm = libp2p::SwarmBuilder::with_existing_identity(self.keypair.clone())
+            .with_tokio()
+            .with_tcp(libp2p::tcp::Config::default(), libp2p::noise::Config::new, libp2p::yamux::Config::default)
+            .map_err(|e| OlympusError::Network(format!("Failed to build transport: {}", e)))?
+            .with_behaviour(|_| Flood::default())
+            .map_err(|e| OlympusError::Network(format!("Failed to build behaviour: {}", e)))?
+            .with_swarm_config(|config| config.with_idle_connection_timeout(IDLE_CONNECTION_TIMEOUT))
+            .build();
+        swarm.listen_on(listen_address)
+            .map_err(|e| OlympusError::Network(format!("Failed to listen: {}", e)))?;
+
+        self.swarm = Some(swarm);
+        Ok(())
+    }
+
+    /// Addresses the swarm is listening on
+    pub fn listen_addresses(&self) -> Vec<Multiaddr> {
+        self.swarm.as_ref().map_or_else(Vec::new, |swarm| swarm.listeners().cloned().collect())
+    }
+
+    /// Dial a peer address
+    pub fn dial(&mut self, address: Multiaddr) -> Result<()> {
+        self.started_swarm()?.dial(address)
+            .map_err(|e| OlympusError::Network(format!("Failed to dial: {}", e)))
+    }
+
+    /// Drive the swarm, handling events until it fails
+    pub async fn run(&mut self) -> Result<()> {
+        loop {
+            self.poll_network().await?;
+        }
+    }
+
+    /// Send messages queued for single peers, then handle the next swarm event
+    ///
+    /// Connection events update the peer's `connected` and `last_seen` and
+    /// send our Status to new peers, and messages received from peers are
+    /// queued for `handle_inbound`. Dropping the future before it completes
+    /// loses nothing.
+    pub async fn poll_network(&mut self) -> Result<()> {
+        let swarm = self.swarm.as_mut()
+            .ok_or_else(|| OlympusError::Network("Network is not started".to_string()))?;
+        for (peer_id, message) in self.peer_outbound.drain(..) {
+            swarm.behaviour_mut().send(peer_id, encode_message(&message)?);
+        }
+
+        let event = swarm.select_next_some().await;
+        self.handle_swarm_event(event);
+        Ok(())
+    }
+
+    /// Take all messages received from peers
+    pub fn drain_inbound(&mut self) -> Vec<(PeerId, NetworkMessage)> {
+        std::mem::take(&mut self.inbound)
+    }
+
+    /// Handle all messages received from peers, returning the blocks
+    ///
+    /// Status completes the handshake, pings are answered and transaction
+    /// messages go through the pool. Peers must complete the handshake before
+    /// anything else they send is accepted, and peers sending invalid messages
+    /// are penalized.
+    pub fn handle_inbound(&mut self, pool: &mut TransactionPool) -> Vec<(PeerId, NetworkMessage)> {
+        let mut blocks = Vec::new();
+        for (peer_id, message) in self.drain_inbound() {
+            if let NetworkMessage::Status { chain_id, genesis_hash } = message {
+                let _ = self.handle_status(peer_id, chain_id, genesis_hash);
+                continue;
+            }
+            if !self.peers.get(&peer_id).is_some_and(|info| info.handshake_complete) {
+                continue;
+            }
+
+            let handled = match message {
+                NetworkMessage::Block { .. } => {
+                    blocks.push((peer_id, message));
+                    Ok(())
+                }
+                NetworkMessage::Transaction { transaction_hash, transaction_data } => {
+                    self.handle_transaction_message(pool, transaction_hash, transaction_data).map(|_| ())
+                }
+                NetworkMessage::NewPooledTransactionHashes { transaction_hashes } => {
+                    self.handle_transaction_announcement(peer_id, transaction_hashes);
+                    Ok(())
+                }
+                NetworkMessage::GetPooledTransactions { transaction_hashes } => {
+                    self.handle_get_pooled_transactions(peer_id, pool, &transaction_hashes);
+                    Ok(())
+                }
+                NetworkMessage::PooledTransactions { transactions } => {
+                    self.handle_pooled_transactions(peer_id, pool, transactions);
+                    Ok(())
+                }
+                NetworkMessage::Ping => {
+                    self.peer_outbound.push((peer_id, NetworkMessage::Pong));
+                    Ok(())
+                }
+                NetworkMessage::Status { .. } | NetworkMessage::Pong => Ok(()),
+            };
+            if handled.is_err() {
+                self.penalize_peer(peer_id, INVALID_MESSAGE_PENALTY);
+            }
+        }
+        blocks
+    }
+
+    /// Get the swarm, failing if the network was not started
+    fn started_swarm(&mut self) -> Result<&mut Swarm<Flood>> {
+        self.swarm.as_mut().ok_or_else(|| OlympusError::Network("Network is not started".to_string()))
+    }
+
+    /// Update peers and inbound messages from a swarm event
+    fn handle_swarm_event(&mut self, event: SwarmEvent<FloodEvent>) {
+        match event {
+            SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+                if self.is_banned(peer_id) {
+                    if let Some(swarm) = self.swarm.as_mut() {
+                        let _ = swarm.disconnect_peer_id(peer_id);
+                    }
+                    return;
+                }
+                let initial_score = self.peer_scoring.initial_score;
+                let info = self.peers.entry(peer_id).or_insert_with(|| PeerInfo {
+                    address: endpoint.get_remote_address().clone(),
+                    connected: false,
+                    handshake_complete: false,
+                    last_seen: 0,
+                    score: initial_score,
+                });
+                info.connected = true;
+                info.last_seen = crate::common::current_timestamp();
+                let status = self.status_message();
+                self.peer_outbound.push((peer_id, status));
+            }
+            SwarmEvent::ConnectionClosed { peer_id, num_established: 0, .. } => {
+                if let Some(info) = self.peers.get_mut(&peer_id) {
+                    info.connected = false;
+                    info.handshake_complete = false;
+                }
+            }
+            SwarmEvent::Behaviour(FloodEvent { peer_id, payload }) => {
+                // Undecodable payloads and recently seen items are dropped
+                if let Ok(message) = bincode::deserialize::<NetworkMessage>(&payload) {
+                    if message.dedup_key().is_some_and(|hash| self.already_seen(hash)) {
+                        return;
+                    }
+                    if let Some(info) = self.peers.get_mut(&peer_id) {
+                        info.last_seen = crate::common::current_timestamp();
+                    }
+                    self.inbound.push((peer_id, message));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Set how gossiped transactions are propagated to peers
+    pub fn set_transaction_propagation(&mut self, propagation: TransactionPropagation) {
+        self.transaction_propagation = propagation;
+    }
+
+    /// Bind the network to a genesis configuration
+    pub fn set_genesis(&mut self, genesis: &Genesis) {
+        self.chain_id = genesis.chain_id;
+        self.genesis_hash = genesis.hash();
+    }
+
+    /// Build the handshake status message for this node
+    pub fn status_message(&self) -> NetworkMessage {
+        NetworkMessage::Status {
+            chain_id: self.chain_id,
+            genesis_hash: self.genesis_hash,
+        }
+    }
+
+    /// Handle a peer's handshake status, rejecting peers on a different chain
+    pub fn handle_status(&mut self, peer_id: PeerId, chain_id: u64, genesis_hash: H256) -> Result<()> {
+        if chain_id != self.chain_id || genesis_hash != self.genesis_hash {
+            self.remove_peer(peer_id);
+            return Err(OlympusError::Network(format!(
+                "Peer {} is on a different chain: chain id {}, genesis {:?}",
+                peer_id, chain_id, genesis_hash
+            )));
+        }
+
+        if let Some(info) = self.peers.get_mut(&peer_id) {
+            info.connected = true;
+            info.handshake_complete = true;
+            info.last_seen = crate::common::current_timestamp();
+        }
+        Ok(())
+    }
+
     /// Add peer
-    pub fn add_peer(&mut self, peer_id: PeerId, address: Multiaddr) {
+    ///
+    /// Returns `false` without adding the peer while it is banned.
+    pub fn add_peer(&mut self, peer_id: PeerId, address: Multiaddr) -> bool {
+        if self.is_banned(peer_id) {
+            return false;
+        }
+        self.banned.remove(&peer_id);
+
         let peer_info = PeerInfo {
             address,
             connected: false,
+            handshake_complete: false,
             last_seen: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
-            score: 1.0,
+            score: self.peer_scoring.initial_score,
         };
         self.peers.insert(peer_id, peer_info);
+        true
+    }
+
+    /// Lower a peer's score, removing and banning it once below the ban threshold
+    pub fn penalize_peer(&mut self, peer_id: PeerId, amount: f64) {
+        let Some(info) = self.peers.get_mut(&peer_id) else {
+            return;
+        };
+        info.score -= amount;
+        if info.score >= self.peer_scoring.ban_threshold {
+            return;
+        }
+
+        self.remove_peer(peer_id);
+        self.banned.insert(peer_id, self.clock.now().saturating_add(self.peer_scoring.ban_duration));
+        if let Some(swarm) = self.swarm.as_mut() {
+            let _ = swarm.disconnect_peer_id(peer_id);
+        }
+    }
+
+    /// Raise a peer's score, up to the maximum score
+    pub fn reward_peer(&mut self, peer_id: PeerId, amount: f64) {
+        if let Some(info) = self.peers.get_mut(&peer_id) {
+            info.score = (info.score + amount).min(self.peer_scoring.max_score);
+        }
+    }
+
+    /// Check if a peer is banned
+    pub fn is_banned(&self, peer_id: PeerId) -> bool {
+        self.banned.get(&peer_id).is_some_and(|until| self.clock.now() < *until)
     }
 
     /// Remove peer
@@ -84,17 +455,186 @@ impl NetworkManager {
             .collect()
     }
 
+    /// Get connected peers that completed the Status handshake
+    pub fn get_handshaken_peers(&self) -> Vec<PeerId> {
+        self.peers.iter()
+            .filter(|(_, info)| info.connected && info.handshake_complete)
+            .map(|(peer_id, _)| *peer_id)
+            .collect()
+    }
+
     /// Get peer info
     pub fn get_peer_info(&self, peer_id: PeerId) -> Option<&PeerInfo> {
         self.peers.get(&peer_id)
     }
 
     /// Broadcast message to all peers
-    pub fn broadcast_message(&self, _message: NetworkMessage) -> Result<()> {
-        // TODO: Implement actual message broadcasting
+    ///
+    /// Block and transaction messages whose hash was already gossiped are
+    /// skipped. Once the network is started the message is sent to the peers
+    /// that completed the handshake; before that it is queued for
+    /// `drain_outbound`.
+    pub fn broadcast_message(&mut self, message: NetworkMessage) -> Result<()> {
+        if let Some(hash) = message.dedup_key() {
+            if !self.mark_seen(hash) {
+                return Ok(());
+            }
+        }
+
+        if self.swarm.is_none() {
+            self.outbound.push(message);
+            return Ok(());
+        }
+        let payload = encode_message(&message)?;
+        let peers = self.get_handshaken_peers();
+        let swarm = self.started_swarm()?;
+        for peer_id in peers {
+            swarm.behaviour_mut().send(peer_id, payload.clone());
+        }
+        Ok(())
+    }
+
+    /// Take all messages queued for the transport
+    pub fn drain_outbound(&mut self) -> Vec<NetworkMessage> {
+        std::mem::take(&mut self.outbound)
+    }
+
+    /// Take all messages queued for individual peers
+    pub fn drain_peer_outbound(&mut self) -> Vec<(PeerId, NetworkMessage)> {
+        std::mem::take(&mut self.peer_outbound)
+    }
+
+    /// Gossip a transaction according to the propagation strategy
+    ///
+    /// A random subset of connected peers receives the full body and the
+    /// others only its hash; flooding broadcasts the body to everyone.
+    fn propagate_transaction(&mut self, transaction_hash: H256, transaction_data: Vec<u8>) -> Result<()> {
+        let message = NetworkMessage::Transaction { transaction_hash, transaction_data };
+        if self.transaction_propagation == TransactionPropagation::Flood {
+            return self.broadcast_message(message);
+        }
+        if !self.mark_seen(transaction_hash) {
+            return Ok(());
+        }
+
+        let mut peers = self.get_handshaken_peers();
+        peers.shuffle(&mut rand::thread_rng());
+        let fanout = self.transaction_propagation.fanout(peers.len());
+        let announcement = NetworkMessage::NewPooledTransactionHashes { transaction_hashes: vec![transaction_hash] };
+        for (index, peer_id) in peers.into_iter().enumerate() {
+            let message = if index < fanout { message.clone() } else { announcement.clone() };
+            self.peer_outbound.push((peer_id, message));
+        }
         Ok(())
     }
 
+    /// Admit a locally submitted transaction to the pool and gossip it to peers
+    ///
+    /// The transaction must pass full validation and carry a recoverable sender.
+    pub fn submit_transaction(&mut self, pool: &mut TransactionPool, transaction: Transaction) -> Result<H256> {
+        let transaction_hash = transaction.hash();
+        let transaction_data = rlp::encode(&transaction).to_vec();
+
+        check_transaction(&transaction)?;
+        pool.add_transaction(transaction)?;
+        self.propagate_transaction(transaction_hash, transaction_data)?;
+
+        Ok(transaction_hash)
+    }
+
+    /// Handle a peer announcing transaction hashes, requesting the bodies not yet seen
+    ///
+    /// Hashes already requested from another peer are not requested again.
+    pub fn handle_transaction_announcement(&mut self, peer_id: PeerId, transaction_hashes: Vec<H256>) {
+        let transaction_hashes: Vec<H256> = transaction_hashes.into_iter()
+            .filter(|hash| !self.already_seen(*hash) && !self.requested.contains(hash))
+            .collect();
+        for hash in &transaction_hashes {
+            self.requested.put(*hash, peer_id);
+        }
+        if !transaction_hashes.is_empty() {
+            self.peer_outbound.push((peer_id, NetworkMessage::GetPooledTransactions { transaction_hashes }));
+        }
+    }
+
+    /// Answer a peer's request with the requested transactions found in the pool
+    pub fn handle_get_pooled_transactions(&mut self, peer_id: PeerId, pool: &TransactionPool, transaction_hashes: &[H256]) {
+        let transactions = transaction_hashes.iter()
+            .filter_map(|hash| pool.get_transaction(*hash))
+            .map(|transaction| rlp::encode(transaction).to_vec())
+            .collect();
+        self.peer_outbound.push((peer_id, NetworkMessage::PooledTransactions { transactions }));
+    }
+
+    /// Handle transaction bodies a peer sent in answer to a request
+    ///
+    /// Bodies that were not requested from the peer, fail to decode or are
+    /// rejected by the pool are skipped and cost the peer score. The answer
+    /// settles every outstanding request to the peer. Returns the number of
+    /// transactions that were new.
+    pub fn handle_pooled_transactions(
+        &mut self,
+        peer_id: PeerId,
+        pool: &mut TransactionPool,
+        transactions: Vec<Vec<u8>>,
+    ) -> usize {
+        let mut admitted = 0;
+        for transaction_data in transactions {
+            let handled = rlp::decode::<Transaction>(&transaction_data)
+                .map_err(OlympusError::from)
+                .and_then(|transaction| {
+                    let hash = transaction.hash();
+                    if self.requested.peek(&hash) != Some(&peer_id) {
+                        return Err(OlympusError::Network(format!("Transaction {:?} was not requested", hash)));
+                    }
+                    self.requested.pop(&hash);
+                    self.handle_transaction_message(pool, hash, transaction_data)
+                });
+            match handled {
+                Ok(true) => admitted += 1,
+                Ok(false) => {}
+                Err(_) => self.penalize_peer(peer_id, INVALID_MESSAGE_PENALTY),
+            }
+        }
+
+        let unanswered: Vec<H256> = self.requested.iter()
+            .filter(|(_, requested_from)| **requested_from == peer_id)
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in unanswered {
+            self.requested.pop(&hash);
+        }
+        admitted
+    }
+
+    /// Handle a transaction gossiped by a peer
+    ///
+    /// Returns `false` when the transaction was already seen, otherwise the
+    /// transaction is admitted to the pool and re-gossiped once. Transactions
+    /// failing validation or without a recoverable sender are rejected and
+    /// never re-gossiped.
+    pub fn handle_transaction_message(
+        &mut self,
+        pool: &mut TransactionPool,
+        transaction_hash: H256,
+        transaction_data: Vec<u8>,
+    ) -> Result<bool> {
+        if self.already_seen(transaction_hash) {
+            return Ok(false);
+        }
+
+        let transaction: Transaction = rlp::decode(&transaction_data)?;
+        if transaction.hash() != transaction_hash {
+            return Err(OlympusError::Network("Transaction hash mismatch".to_string()));
+        }
+
+        check_transaction(&transaction)?;
+        pool.add_transaction(transaction)?;
+        self.propagate_transaction(transaction_hash, transaction_data)?;
+
+        Ok(true)
+    }
+
     /// Get network statistics
     pub fn get_statistics(&self) -> NetworkStatistics {
         let connected_count = self.peers.values().filter(|info| info.connected).count();
@@ -109,6 +649,24 @@ impl NetworkManager {
     }
 }
 
+/// Capacity of the seen-cache, which holds at least one hash
+fn seen_cache_capacity(capacity: usize) -> NonZeroUsize {
+    NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Serialize a message for the wire
+fn encode_message(message: &NetworkMessage) -> Result<Vec<u8>> {
+    bincode::serialize(message)
+        .map_err(|e| OlympusError::Serialization(format!("Failed to encode network message: {}", e)))
+}
+
+/// Check a transaction before it enters the pool, as `eth_sendRawTransactions` does
+fn check_transaction(transaction: &Transaction) -> Result<()> {
+    transaction.validate(CheckTransaction::Everything)?;
+    transaction.sender()?;
+    Ok(())
+}
+
 /// Network statistics
 #[derive(Debug, Clone)]
 pub struct NetworkStatistics {
@@ -122,4 +680,282 @@ impl Default for NetworkManager {
     fn default() -> Self {
         Self::new().unwrap()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Signed transfer to an ordinary account
+    fn transfer(nonce: u64) -> Transaction {
+        let mut transaction = Transaction::new(
+            crate::U256::from(1000),
+            crate::U256::from(20_000_000_000u64),
+            crate::U256::from(21000),
+            crate::Address::from([0x42; 20]),
+            vec![],
+            crate::U256::from(nonce),
+        );
+        transaction.sign_with_secret(&[0x42; 32]).unwrap();
+        transaction
+    }
+
+    #[test]
+    fn test_handshake_checks_genesis() {
+        let genesis = Genesis::default();
+        let mut network = NetworkManager::new().unwrap();
+        network.set_genesis(&genesis);
+
+        let good_peer = PeerId::random();
+        let bad_peer = PeerId::random();
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/30607".parse().unwrap();
+        network.add_peer(good_peer, address.clone());
+        network.add_peer(bad_peer, address);
+
+        assert!(network.handle_status(good_peer, genesis.chain_id, genesis.hash()).is_ok());
+        assert!(network.get_peer_info(good_peer).unwrap().connected);
+
+        assert!(network.handle_status(bad_peer, genesis.chain_id, H256::repeat_byte(0xab)).is_err());
+        assert!(network.get_peer_info(bad_peer).is_none());
+    }
+
+    #[test]
+    fn test_penalized_peer_banned_until_cooldown_ends() {
+        let clock = Arc::new(crate::common::MockClock::new(1_000));
+        let mut network = NetworkManager::new().unwrap();
+        network.set_clock(clock.clone());
+        let peer_id = PeerId::random();
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/30607".parse().unwrap();
+        assert!(network.add_peer(peer_id, address.clone()));
+
+        network.reward_peer(peer_id, 100.0);
+        assert_eq!(network.get_peer_info(peer_id).unwrap().score, PeerScoring::default().max_score);
+        network.penalize_peer(peer_id, 9.5);
+        assert_eq!(network.get_peer_info(peer_id).unwrap().score, 0.5);
+        assert!(!network.is_banned(peer_id));
+
+        network.penalize_peer(peer_id, 1.0);
+        assert!(network.get_peer_info(peer_id).is_none());
+        assert!(network.is_banned(peer_id));
+        assert!(!network.add_peer(peer_id, address.clone()));
+
+        clock.advance(PeerScoring::default().ban_duration);
+        assert!(network.add_peer(peer_id, address));
+        assert_eq!(network.get_peer_info(peer_id).unwrap().score, PeerScoring::default().initial_score);
+    }
+
+    #[test]
+    fn test_inbound_messages_wait_for_handshake() {
+        let mut network = NetworkManager::new().unwrap();
+        let mut pool = TransactionPool::new(100);
+        let peer_id = PeerId::random();
+        network.add_peer(peer_id, "/ip4/127.0.0.1/tcp/30606".parse().unwrap());
+
+        // Anything before the peer's Status is dropped
+        network.inbound.push((peer_id, NetworkMessage::Ping));
+        assert!(network.handle_inbound(&mut pool).is_empty());
+        assert!(network.drain_peer_outbound().is_empty());
+        assert!(network.get_handshaken_peers().is_empty());
+
+        let status = network.status_message();
+        let block = NetworkMessage::Block { block_hash: H256::repeat_byte(1), block_data: vec![1] };
+        network.inbound.extend([(peer_id, status), (peer_id, NetworkMessage::Ping), (peer_id, block)]);
+        let blocks = network.handle_inbound(&mut pool);
+        assert_eq!(network.get_handshaken_peers(), vec![peer_id]);
+        assert!(matches!(network.drain_peer_outbound().as_slice(), [(id, NetworkMessage::Pong)] if *id == peer_id));
+        assert!(matches!(blocks.as_slice(), [(id, NetworkMessage::Block { .. })] if *id == peer_id));
+
+        // Undecodable transactions cost the sender score
+        network.inbound.push((peer_id, NetworkMessage::Transaction {
+            transaction_hash: H256::repeat_byte(2),
+            transaction_data: vec![0xff],
+        }));
+        network.handle_inbound(&mut pool);
+        assert_eq!(
+            network.get_peer_info(peer_id).unwrap().score,
+            PeerScoring::default().initial_score - INVALID_MESSAGE_PENALTY
+        );
+    }
+
+    #[test]
+    fn test_dedup_key_ignores_encoding() {
+        let block = crate::core::block::Block::new(
+            crate::Address::from([0x01; 20]),
+            H256::zero(),
+            vec![],
+            vec![H256::repeat_byte(0x11)],
+            vec![],
+            H256::zero(),
+            H256::zero(),
+            H256::zero(),
+            1_600_000_000,
+            crate::U256::zero(),
+            crate::core::types::Signature { v: 27, r: H256::zero(), s: H256::zero() },
+        );
+        let block_hash = block.hash();
+        let rlp_message = NetworkMessage::Block { block_hash, block_data: block.rlp_bytes() };
+        let json_message = NetworkMessage::Block { block_hash, block_data: serde_json::to_vec(&block).unwrap() };
+
+        assert_eq!(rlp_message.dedup_key(), Some(block_hash));
+        assert_eq!(rlp_message.dedup_key(), json_message.dedup_key());
+        assert_eq!(NetworkMessage::Ping.dedup_key(), None);
+
+        // Control messages are never suppressed by the seen-set
+        let mut network = NetworkManager::new().unwrap();
+        network.broadcast_message(rlp_message).unwrap();
+        network.broadcast_message(json_message).unwrap();
+        network.broadcast_message(NetworkMessage::Ping).unwrap();
+        network.broadcast_message(NetworkMessage::Ping).unwrap();
+        assert_eq!(network.drain_outbound().len(), 3);
+    }
+
+    #[test]
+    fn test_seen_cache_suppresses_recent_duplicates() {
+        let mut network = NetworkManager::new().unwrap();
+        network.set_seen_cache_capacity(2);
+        let block = |byte: u8| NetworkMessage::Block { block_hash: H256::repeat_byte(byte), block_data: vec![byte] };
+
+        network.broadcast_message(block(1)).unwrap();
+        assert!(network.already_seen(H256::repeat_byte(1)));
+        network.broadcast_message(block(1)).unwrap();
+        assert_eq!(network.drain_outbound().len(), 1);
+
+        // Two newer hashes push the first out of the cache, so it is gossiped again
+        network.broadcast_message(block(2)).unwrap();
+        network.broadcast_message(block(3)).unwrap();
+        assert!(!network.already_seen(H256::repeat_byte(1)));
+        network.broadcast_message(block(1)).unwrap();
+        assert_eq!(network.drain_outbound().len(), 3);
+    }
+
+    #[test]
+    fn test_local_transaction_broadcast_once() {
+        let mut network = NetworkManager::new().unwrap();
+        let mut pool = TransactionPool::new(100);
+
+        let tx = transfer(0);
+        let hash = network.submit_transaction(&mut pool, tx.clone()).unwrap();
+
+        // A peer echoing the same transaction back must not trigger a second broadcast
+        network.broadcast_message(NetworkMessage::Transaction {
+            transaction_hash: hash,
+            transaction_data: rlp::encode(&tx).to_vec(),
+        }).unwrap();
+
+        let outbound = network.drain_outbound();
+        assert_eq!(outbound.len(), 1);
+        assert!(matches!(
+            &outbound[0],
+            NetworkMessage::Transaction { transaction_hash, .. } if *transaction_hash == hash
+        ));
+        assert_eq!(pool.get_statistics().total_count, 1);
+    }
+
+    #[test]
+    fn test_unsigned_transactions_rejected_and_not_propagated() {
+        let mut network = NetworkManager::new().unwrap();
+        let mut pool = TransactionPool::new(100);
+        let unsigned = Transaction::new(
+            crate::U256::from(1000),
+            crate::U256::from(20_000_000_000u64),
+            crate::U256::from(21000),
+            crate::Address::from([0x42; 20]),
+            vec![],
+            crate::U256::zero(),
+        );
+
+        let gossiped = network.handle_transaction_message(&mut pool, unsigned.hash(), rlp::encode(&unsigned).to_vec());
+        assert!(gossiped.is_err());
+        assert!(network.submit_transaction(&mut pool, unsigned).is_err());
+        assert_eq!(pool.get_statistics().total_count, 0);
+        assert!(network.drain_outbound().is_empty());
+        assert!(network.drain_peer_outbound().is_empty());
+    }
+
+    #[test]
+    fn test_transaction_bodies_sent_to_fanout_subset() {
+        let config = NetworkConfig {
+            transaction_propagation: TransactionPropagation::Sqrt,
+            ..NetworkConfig::default()
+        };
+        let mut network = NetworkManager::from_config(&config, &Genesis::default()).unwrap();
+        assert!(matches!(
+            network.status_message(),
+            NetworkMessage::Status { genesis_hash, .. } if genesis_hash == Genesis::default().hash()
+        ));
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/30607".parse().unwrap();
+        for _ in 0..9 {
+            let peer_id = PeerId::random();
+            network.add_peer(peer_id, address.clone());
+            network.handle_status(peer_id, network.chain_id, network.genesis_hash).unwrap();
+        }
+
+        let mut pool = TransactionPool::new(100);
+        let tx = transfer(0);
+        let hash = network.submit_transaction(&mut pool, tx).unwrap();
+
+        // Three of nine peers get the body, the other six only the hash
+        let outbound = network.drain_peer_outbound();
+        let bodies: Vec<_> = outbound.iter()
+            .filter(|(_, message)| matches!(message, NetworkMessage::Transaction { .. }))
+            .map(|(peer_id, _)| *peer_id)
+            .collect();
+        let announced: Vec<_> = outbound.iter()
+            .filter(|(_, message)| matches!(
+                message,
+                NetworkMessage::NewPooledTransactionHashes { transaction_hashes } if *transaction_hashes == vec![hash]
+            ))
+            .map(|(peer_id, _)| *peer_id)
+            .collect();
+        assert_eq!(bodies.len(), 3);
+        assert_eq!(announced.len(), 6);
+        assert!(bodies.iter().all(|peer_id| !announced.contains(peer_id)));
+        assert!(network.drain_outbound().is_empty());
+
+        // A peer announced to fetches the body and admits it
+        let mut receiver = NetworkManager::new().unwrap();
+        receiver.handle_transaction_announcement(network.peer_id, vec![hash]);
+        let requests = receiver.drain_peer_outbound();
+        assert_eq!(requests.len(), 1);
+        let (_, NetworkMessage::GetPooledTransactions { transaction_hashes }) = &requests[0] else {
+            panic!("expected a body request");
+        };
+        network.handle_get_pooled_transactions(announced[0], &pool, transaction_hashes);
+        let Some((peer_id, NetworkMessage::PooledTransactions { transactions })) = network.drain_peer_outbound().pop() else {
+            panic!("expected pooled transactions");
+        };
+        assert_eq!(peer_id, announced[0]);
+
+        let mut receiver_pool = TransactionPool::new(100);
+        assert_eq!(receiver.handle_pooled_transactions(network.peer_id, &mut receiver_pool, transactions), 1);
+        assert!(receiver_pool.get_transaction(hash).is_some());
+    }
+
+    #[test]
+    fn test_pooled_transactions_only_admit_requested_bodies() {
+        let mut network = NetworkManager::new().unwrap();
+        let mut pool = TransactionPool::new(100);
+        let peer_id = PeerId::random();
+        network.add_peer(peer_id, "/ip4/127.0.0.1/tcp/30608".parse().unwrap());
+        network.reward_peer(peer_id, PeerScoring::default().max_score);
+
+        let requested = transfer(0);
+        let unrequested = transfer(1);
+        network.handle_transaction_announcement(peer_id, vec![requested.hash()]);
+        assert_eq!(network.drain_peer_outbound().len(), 1);
+
+        // A second announcement of an in-flight hash is not requested again
+        network.handle_transaction_announcement(PeerId::random(), vec![requested.hash()]);
+        assert!(network.drain_peer_outbound().is_empty());
+
+        // The undecodable and unrequested entries are skipped, one penalty each
+        let transactions = vec![vec![0xff], rlp::encode(&unrequested).to_vec(), rlp::encode(&requested).to_vec()];
+        assert_eq!(network.handle_pooled_transactions(peer_id, &mut pool, transactions), 1);
+        assert!(pool.get_transaction(requested.hash()).is_some());
+        assert!(pool.get_transaction(unrequested.hash()).is_none());
+        assert_eq!(network.get_peer_info(peer_id).unwrap().score, PeerScoring::default().max_score - 2.0 * INVALID_MESSAGE_PENALTY);
+
+        // Answered requests are settled, so replaying the body is unrequested
+        network.handle_pooled_transactions(peer_id, &mut pool, vec![rlp::encode(&requested).to_vec()]);
+        assert_eq!(network.get_peer_info(peer_id).unwrap().score, PeerScoring::default().max_score - 3.0 * INVALID_MESSAGE_PENALTY);
+    }
+}
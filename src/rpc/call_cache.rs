@@ -0,0 +1,243 @@
+//! Execution-result cache for repeated eth_call requests
+
+use crate::{Address, H256, Result};
+use crate::core::config::RpcConfig;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Cache key identifying a call against a specific state
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CallCacheKey {
+    /// Caller address
+    pub from: Address,
+    /// Callee address
+    pub to: Address,
+    /// Call data
+    pub data: Vec<u8>,
+    /// Block tag the call was made against
+    pub block_tag: String,
+    /// State root the result was computed on
+    pub state_root: H256,
+}
+
+/// Cached call output
+#[derive(Debug, Clone)]
+struct CallCacheEntry {
+    /// Call output
+    output: Vec<u8>,
+    /// Insertion time, used for TTL expiry
+    inserted_at: Instant,
+    /// Last access tick, used for LRU eviction
+    last_used: u64,
+}
+
+/// LRU cache of eth_call results
+///
+/// Entries are bound to the state root they were computed on, so a lookup
+/// against another root misses. The whole cache is dropped when a block is
+/// committed, which is the only time the served state changes.
+pub struct CallCache {
+    /// Cached entries
+    entries: HashMap<CallCacheKey, CallCacheEntry>,
+    /// Maximum number of entries
+    capacity: usize,
+    /// Time-to-live of an entry
+    ttl: Duration,
+    /// State root of the last committed block
+    state_root: H256,
+    /// Access counter
+    tick: u64,
+    /// Cache hits
+    hits: u64,
+    /// Cache misses
+    misses: u64,
+}
+
+impl CallCache {
+    /// Create new call cache
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            ttl,
+            state_root: H256::zero(),
+            tick: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Create call cache from RPC configuration, `None` when disabled
+    pub fn from_config(config: &RpcConfig) -> Option<Self> {
+        if config.call_cache_size == 0 {
+            return None;
+        }
+        Some(Self::new(config.call_cache_size, Duration::from_secs(config.call_cache_ttl)))
+    }
+
+    /// Notify the cache of a committed block, invalidating it if the state root changed
+    pub fn on_block_committed(&mut self, state_root: H256) {
+        if state_root != self.state_root {
+            self.entries.clear();
+            self.state_root = state_root;
+        }
+    }
+
+    /// State root of the last committed block, which calls are keyed by
+    pub fn state_root(&self) -> H256 {
+        self.state_root
+    }
+
+    /// Look up a cached result
+    ///
+    /// A key for another state root is only a miss; it never invalidates entries.
+    pub fn get(&mut self, key: &CallCacheKey) -> Option<Vec<u8>> {
+        self.tick += 1;
+
+        let expired = match self.entries.get_mut(key) {
+            Some(entry) if entry.inserted_at.elapsed() <= self.ttl => {
+                entry.last_used = self.tick;
+                self.hits += 1;
+                return Some(entry.output.clone());
+            }
+            Some(_) => true,
+            None => false,
+        };
+
+        if expired {
+            self.entries.remove(key);
+        }
+        self.misses += 1;
+        None
+    }
+
+    /// Store a call result
+    pub fn insert(&mut self, key: CallCacheKey, output: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            self.evict_least_recently_used();
+        }
+
+        self.tick += 1;
+        self.entries.insert(key, CallCacheEntry {
+            output,
+            inserted_at: Instant::now(),
+            last_used: self.tick,
+        });
+    }
+
+    /// Return the cached result or compute and cache it
+    pub fn get_or_execute<F>(&mut self, key: CallCacheKey, execute: F) -> Result<Vec<u8>>
+    where
+        F: FnOnce() -> Result<Vec<u8>>,
+    {
+        if let Some(output) = self.get(&key) {
+            return Ok(output);
+        }
+
+        let output = execute()?;
+        self.insert(key, output.clone());
+        Ok(output)
+    }
+
+    /// Evict the least recently used entry
+    fn evict_least_recently_used(&mut self) {
+        let oldest = self.entries.iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+
+    /// Number of cached entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of cache hits
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Number of cache misses
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(state_root: H256) -> CallCacheKey {
+        CallCacheKey {
+            from: Address::zero(),
+            to: Address::from([0x04; 20]),
+            data: vec![0xde, 0xad, 0xbe, 0xef],
+            block_tag: "latest".to_string(),
+            state_root,
+        }
+    }
+
+    #[test]
+    fn test_identical_calls_hit_cache() {
+        let mut cache = CallCache::new(16, Duration::from_secs(60));
+        let root = H256::repeat_byte(0x01);
+        let mut executions = 0;
+
+        for _ in 0..2 {
+            let output = cache.get_or_execute(key(root), || {
+                executions += 1;
+                Ok(vec![0xde, 0xad, 0xbe, 0xef])
+            }).unwrap();
+            assert_eq!(output, vec![0xde, 0xad, 0xbe, 0xef]);
+        }
+
+        assert_eq!(executions, 1);
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn test_new_state_root_invalidates_cache() {
+        let mut cache = CallCache::new(16, Duration::from_secs(60));
+        cache.insert(key(H256::repeat_byte(0x01)), vec![0x01]);
+        assert_eq!(cache.len(), 1);
+
+        // Looking up another root misses without dropping the entry
+        assert!(cache.get(&key(H256::repeat_byte(0x02))).is_none());
+        assert_eq!(cache.get(&key(H256::repeat_byte(0x01))), Some(vec![0x01]));
+
+        // A new block moves the state root; the old result must not be served
+        cache.on_block_committed(H256::repeat_byte(0x02));
+        assert!(cache.is_empty());
+        assert!(cache.get(&key(H256::repeat_byte(0x01))).is_none());
+    }
+
+    #[test]
+    fn test_lru_eviction_and_ttl() {
+        let root = H256::repeat_byte(0x01);
+        let mut cache = CallCache::new(1, Duration::from_secs(60));
+        let mut other = key(root);
+        other.data = vec![0x00];
+
+        cache.insert(key(root), vec![0x01]);
+        cache.insert(other.clone(), vec![0x02]);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&other), Some(vec![0x02]));
+
+        let mut expired = CallCache::new(4, Duration::ZERO);
+        expired.insert(key(root), vec![0x01]);
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(expired.get(&key(root)).is_none());
+    }
+}
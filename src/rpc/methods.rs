@@ -1,11 +1,31 @@
 //! RPC methods
 
+use crate::{Address, H256, U256, Result};
+use crate::consensus::dag::ConsensusSnapshot;
+use crate::core::block::Block;
+use crate::consensus::witness::WitnessHistory;
+use crate::core::transaction::{CheckTransaction, Transaction};
+use crate::core::types::{CHAIN_ID, Epoch};
+use crate::evm::executive::{ExecutionLimits, Executive};
+use crate::rpc::call_cache::{CallCache, CallCacheKey};
+use crate::evm::state_override::{AccountOverride, StateOverride};
+use crate::evm::state::SharedState;
+use crate::evm::transaction_executor::TransactionPool;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use crate::p2p::network::PROTOCOL_VERSION;
+
+/// JSON-RPC error code for a transaction refused by a full pool
+pub const POOL_FULL_ERROR_CODE: i32 = -32003;
+
 /// JSON-RPC request
 #[derive(serde::Deserialize)]
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
     pub method: String,
+    #[serde(default)]
     pub params: serde_json::Value,
+    #[serde(default)]
     pub id: serde_json::Value,
 }
 
@@ -25,15 +45,298 @@ pub struct JsonRpcError {
     pub message: String,
 }
 
+/// Encode a transaction as a JSON-RPC transaction object
+pub fn transaction_to_json(transaction: &Transaction) -> serde_json::Value {
+    serde_json::json!({
+        "hash": format!("{:#x}", transaction.hash()),
+        "nonce": format!("{:#x}", transaction.nonce()),
+        "from": format!("{:#x}", transaction.from()),
+        "to": if transaction.is_creation() {
+            serde_json::Value::Null
+        } else {
+            serde_json::json!(format!("{:#x}", transaction.receive_address()))
+        },
+        "value": format!("{:#x}", transaction.value()),
+        "gas": format!("{:#x}", transaction.gas()),
+        "gasPrice": format!("{:#x}", transaction.gas_price()),
+        "maxPriorityFeePerGas": transaction.max_priority_fee_per_gas.map(|fee| format!("{:#x}", fee)),
+        "input": format!("0x{}", hex::encode(transaction.data())),
+        "chainId": transaction.chain_id().map(|id| format!("{:#x}", id)),
+    })
+}
+
+/// Stored blocks served by the block query methods
+pub trait BlockProvider: Send + Sync {
+    /// Get a block by hash
+    fn block(&self, hash: H256) -> Result<Option<Block>>;
+    /// Get the hash of the canonical block at a height
+    fn block_hash(&self, number: u64) -> Result<Option<H256>>;
+    /// Get a transaction by hash
+    fn transaction(&self, hash: H256) -> Result<Option<Transaction>>;
+}
+
+/// Encode a block as a JSON-RPC block object
+///
+/// Transactions are listed by hash, or as full objects if `full` is set, in
+/// which case transactions missing from storage are left out.
+fn block_to_json(block: &Block, provider: &dyn BlockProvider, full: bool) -> Result<serde_json::Value> {
+    let transactions = if full {
+        let mut transactions = Vec::with_capacity(block.links.len());
+        for hash in &block.links {
+            if let Some(transaction) = provider.transaction(*hash)? {
+                transactions.push(transaction_to_json(&transaction));
+            }
+        }
+        transactions
+    } else {
+        block.links.iter().map(|hash| serde_json::json!(format!("{:#x}", hash))).collect()
+    };
+
+    Ok(serde_json::json!({
+        "hash": format!("{:#x}", block.hash()),
+        "parentHash": format!("{:#x}", block.previous),
+        "from": format!("{:#x}", block.from),
+        "timestamp": format!("{:#x}", block.exec_timestamp),
+        "gasUsed": format!("{:#x}", block.gas_used),
+        "transactions": transactions,
+    }))
+}
+
+/// Parse a hex-encoded address parameter
+fn parse_address(value: &serde_json::Value) -> std::result::Result<Address, String> {
+    value.as_str()
+        .and_then(|s| s.parse::<Address>().ok())
+        .ok_or_else(|| format!("Invalid address: {}", value))
+}
+
+/// Check an optional block tag parameter
+///
+/// Only the current state is kept, so the tag must be `latest` or `pending`.
+fn check_block_tag(tag: Option<&serde_json::Value>) -> std::result::Result<(), String> {
+    match tag.filter(|tag| !tag.is_null()) {
+        None => Ok(()),
+        Some(tag) if matches!(tag.as_str(), Some("latest" | "pending")) => Ok(()),
+        Some(tag) => Err(format!("Unsupported block tag: {}", tag)),
+    }
+}
+
+/// Parse `[address, blockTag?]` account query params
+fn parse_account_params(params: &serde_json::Value) -> std::result::Result<Address, String> {
+    let address = parse_address(params.get(0).ok_or("Missing address")?)?;
+    check_block_tag(params.get(1))?;
+    Ok(address)
+}
+
+/// Parse a hex-encoded 32-byte hash parameter
+fn parse_hash(value: &serde_json::Value) -> std::result::Result<H256, String> {
+    value.as_str()
+        .and_then(|s| s.parse::<H256>().ok())
+        .ok_or_else(|| format!("Invalid hash: {}", value))
+}
+
+/// Parse a hex-encoded quantity parameter
+fn parse_quantity(value: &serde_json::Value) -> std::result::Result<U256, String> {
+    value.as_str()
+        .and_then(|s| s.strip_prefix("0x"))
+        .and_then(|s| U256::from_str_radix(s, 16).ok())
+        .ok_or_else(|| format!("Invalid quantity: {}", value))
+}
+
+/// Parse a hex-encoded 32-byte word parameter
+fn parse_word(value: &serde_json::Value) -> std::result::Result<H256, String> {
+    let mut word = [0u8; 32];
+    parse_quantity(value)?.to_big_endian(&mut word);
+    Ok(H256::from(word))
+}
+
+/// Parse a hex-encoded byte string parameter
+fn parse_bytes(value: &serde_json::Value) -> std::result::Result<Vec<u8>, String> {
+    value.as_str()
+        .and_then(|s| s.strip_prefix("0x"))
+        .and_then(|s| hex::decode(s).ok())
+        .ok_or_else(|| format!("Invalid data: {}", value))
+}
+
+/// Parse a storage map parameter `{slot: value}`
+fn parse_storage(value: &serde_json::Value) -> std::result::Result<std::collections::HashMap<H256, H256>, String> {
+    let slots = value.as_object().ok_or_else(|| format!("Invalid storage: {}", value))?;
+    slots.iter()
+        .map(|(slot, value)| Ok((parse_word(&serde_json::json!(slot))?, parse_word(value)?)))
+        .collect()
+}
+
+/// Parse an `eth_call` state override object `{address: {balance, nonce, code, state, stateDiff}}`
+pub fn parse_state_override(value: &serde_json::Value) -> std::result::Result<StateOverride, String> {
+    let accounts = value.as_object().ok_or_else(|| format!("Invalid state override: {}", value))?;
+    let mut overrides = StateOverride::new();
+
+    for (address, fields) in accounts {
+        let address = parse_address(&serde_json::json!(address))?;
+        let mut account = AccountOverride::default();
+        if let Some(balance) = fields.get("balance") {
+            account.balance = Some(parse_quantity(balance)?);
+        }
+        if let Some(nonce) = fields.get("nonce") {
+            let nonce = parse_quantity(nonce)?;
+            if nonce > U256::from(u64::MAX) {
+                return Err(format!("Nonce out of range: {}", nonce));
+            }
+            account.nonce = Some(nonce.as_u64());
+        }
+        if let Some(code) = fields.get("code") {
+            account.code = Some(parse_bytes(code)?);
+        }
+        if let Some(state) = fields.get("state") {
+            account.state = Some(parse_storage(state)?);
+        }
+        if let Some(state_diff) = fields.get("stateDiff") {
+            if account.state.is_some() {
+                return Err(format!("Both state and stateDiff set for {:#x}", address));
+            }
+            account.state_diff = parse_storage(state_diff)?;
+        }
+        overrides.insert(address, account);
+    }
+
+    Ok(overrides)
+}
+
+/// Node state reported by `olympus_nodeInfo`
+#[derive(Debug, Clone)]
+pub struct NodeInfo {
+    /// Client name and version
+    pub client_version: String,
+    /// Chain ID
+    pub chain_id: u64,
+    /// Current head block number
+    pub head_block: u64,
+    /// Current head block hash
+    pub head_block_hash: H256,
+    /// Current epoch
+    pub epoch: Epoch,
+    /// Whether this node is in the active witness set
+    pub is_witness: bool,
+    /// Number of connected peers
+    pub peer_count: usize,
+}
+
+impl NodeInfo {
+    /// Create node info for a freshly started node
+    pub fn new() -> Self {
+        Self {
+            client_version: format!("olympus/v{}", env!("CARGO_PKG_VERSION")),
+            chain_id: CHAIN_ID,
+            head_block: 0,
+            head_block_hash: H256::zero(),
+            epoch: 0,
+            is_witness: false,
+            peer_count: 0,
+        }
+    }
+}
+
+impl Default for NodeInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// RPC method handler
 pub struct RpcMethods {
-    // TODO: Add method handlers
+    /// Canonical genesis hash of the chain served by this node
+    genesis_hash: Option<H256>,
+    /// Node state reported by `olympus_nodeInfo`
+    node_info: NodeInfo,
+    /// Consensus state reported by `debug_consensusState`
+    consensus_state: Option<ConsensusSnapshot>,
+    /// Step and time limits of `eth_call` executions
+    call_limits: ExecutionLimits,
+    /// Witness sets reported by `olympus_getWitnesses`
+    witness_sets: Option<WitnessHistory>,
+    /// Pool receiving submitted transactions
+    transaction_pool: Option<Arc<Mutex<TransactionPool>>>,
+    /// State queried by account methods
+    state: Option<SharedState>,
+    /// Blocks served by the block query methods
+    blocks: Option<Arc<dyn BlockProvider>>,
+    /// Results of `eth_call` executions without state overrides
+    call_cache: Option<Mutex<CallCache>>,
 }
 
 impl RpcMethods {
     /// Create new RPC methods
     pub fn new() -> Self {
-        Self {}
+        Self {
+            genesis_hash: None,
+            node_info: NodeInfo::new(),
+            consensus_state: None,
+            call_limits: ExecutionLimits::default(),
+            witness_sets: None,
+            transaction_pool: None,
+            state: None,
+            blocks: None,
+            call_cache: None,
+        }
+    }
+
+    /// Set the genesis hash reported by `olympus_genesisHash`
+    pub fn set_genesis_hash(&mut self, genesis_hash: H256) {
+        self.genesis_hash = Some(genesis_hash);
+    }
+
+    /// Set the node state reported by `olympus_nodeInfo`
+    pub fn set_node_info(&mut self, node_info: NodeInfo) {
+        self.node_info = node_info;
+    }
+
+    /// Get the node state reported by `olympus_nodeInfo`
+    pub fn node_info_mut(&mut self) -> &mut NodeInfo {
+        &mut self.node_info
+    }
+
+    /// Set the step and time limits of `eth_call` executions
+    pub fn set_call_limits(&mut self, limits: ExecutionLimits) {
+        self.call_limits = limits;
+    }
+
+    /// Set the consensus state reported by `debug_consensusState`
+    pub fn set_consensus_state(&mut self, snapshot: ConsensusSnapshot) {
+        self.consensus_state = Some(snapshot);
+    }
+
+    /// Set the witness sets reported by `olympus_getWitnesses`
+    pub fn set_witness_sets(&mut self, witness_sets: WitnessHistory) {
+        self.witness_sets = Some(witness_sets);
+    }
+
+    /// Set the pool receiving submitted transactions
+    pub fn set_transaction_pool(&mut self, pool: Arc<Mutex<TransactionPool>>) {
+        self.transaction_pool = Some(pool);
+    }
+
+    /// Set the state queried by account methods
+    pub fn set_state(&mut self, state: SharedState) {
+        self.state = Some(state);
+    }
+
+    /// Set the blocks served by the block query methods
+    pub fn set_block_provider(&mut self, blocks: Arc<dyn BlockProvider>) {
+        self.blocks = Some(blocks);
+    }
+
+    /// Set the cache of `eth_call` results
+    ///
+    /// Cached results are served until `on_block_committed` reports a new
+    /// state root, so the node must call it for every block it commits.
+    pub fn set_call_cache(&mut self, call_cache: CallCache) {
+        self.call_cache = Some(Mutex::new(call_cache));
+    }
+
+    /// Notify the methods of a committed block's state root, invalidating cached calls
+    pub fn on_block_committed(&self, state_root: H256) {
+        if let Some(call_cache) = &self.call_cache {
+            call_cache.lock().on_block_committed(state_root);
+        }
     }
 
     /// Handle RPC request
@@ -41,30 +344,82 @@ impl RpcMethods {
         match request.method.as_str() {
             "eth_blockNumber" => self.get_block_number(request.id),
             "eth_getBalance" => self.get_balance(request.params, request.id),
-            _ => JsonRpcResponse {
-                jsonrpc: "2.0".to_string(),
-                result: None,
-                error: Some(JsonRpcError {
-                    code: -32601,
-                    message: "Method not found".to_string(),
-                }),
-                id: request.id,
-            },
+            "eth_getTransactionCount" => self.get_transaction_count(request.params, request.id),
+            "eth_call" => self.call(request.params, request.id),
+            "eth_getBlockByHash" => self.get_block_by_hash(request.params, request.id),
+            "eth_getBlockByNumber" => self.get_block_by_number(request.params, request.id),
+            "eth_protocolVersion" => self.get_protocol_version(request.id),
+            "olympus_genesisHash" => self.get_genesis_hash(request.id),
+            "olympus_nodeInfo" => self.get_node_info(request.id),
+            "eth_sendRawTransaction" => self.send_raw_transaction(request.params, request.id),
+            "olympus_sendRawTransactions" => self.send_raw_transactions(request.params, request.id),
+            "olympus_cancelTransaction" => self.cancel_transaction(request.params, request.id),
+            "olympus_decodeRawTransaction" => self.decode_raw_transaction(request.params, request.id),
+            "olympus_getCodeSize" => self.get_code_size(request.params, request.id),
+            "olympus_getWitnesses" => self.get_witnesses(request.params, request.id),
+            "debug_consensusState" => self.get_consensus_state(request.id),
+            _ => Self::error(request.id, -32601, "Method not found"),
         }
     }
 
-    /// Get current block number
-    fn get_block_number(&self, id: serde_json::Value) -> JsonRpcResponse {
+    /// Handle a JSON-RPC body holding a single request or a batch of them
+    ///
+    /// Batches are answered with an array of responses in request order.
+    /// Notifications, requests without an `id`, are executed but not
+    /// answered, so `None` means nothing should be sent back.
+    pub fn handle_body(&self, body: serde_json::Value) -> Option<serde_json::Value> {
+        match body {
+            serde_json::Value::Array(requests) if requests.is_empty() => {
+                Some(Self::response_json(Self::error(serde_json::Value::Null, -32600, "Empty batch")))
+            }
+            serde_json::Value::Array(requests) => {
+                let responses: Vec<_> = requests.into_iter().filter_map(|request| self.handle_single(request)).collect();
+                (!responses.is_empty()).then_some(serde_json::Value::Array(responses))
+            }
+            request => self.handle_single(request),
+        }
+    }
+
+    /// Handle one request of a body, returning its response unless it is a notification
+    fn handle_single(&self, request: serde_json::Value) -> Option<serde_json::Value> {
+        let notification = request.is_object() && request.get("id").is_none();
+        let response = match serde_json::from_value::<JsonRpcRequest>(request) {
+            Ok(request) => self.handle_request(request),
+            Err(_) => Self::error(serde_json::Value::Null, -32600, "Invalid request"),
+        };
+        (!notification).then(|| Self::response_json(response))
+    }
+
+    /// Encode a response as JSON
+    fn response_json(response: JsonRpcResponse) -> serde_json::Value {
+        serde_json::to_value(response).unwrap_or(serde_json::Value::Null)
+    }
+
+    /// Build a successful response
+    fn success(id: serde_json::Value, result: serde_json::Value) -> JsonRpcResponse {
         JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
-            result: Some(serde_json::Value::String("0x0".to_string())),
+            result: Some(result),
             error: None,
             id,
         }
     }
 
-    /// Get account balance
-    fn get_balance(&self, _params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+    /// Build an error response
+    fn error(id: serde_json::Value, code: i32, message: &str) -> JsonRpcResponse {
+        JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: None,
+            error: Some(JsonRpcError {
+                code,
+                message: message.to_string(),
+            }),
+            id,
+        }
+    }
+
+    /// Get current block number
+    fn get_block_number(&self, id: serde_json::Value) -> JsonRpcResponse {
         JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
             result: Some(serde_json::Value::String("0x0".to_string())),
@@ -72,4 +427,825 @@ impl RpcMethods {
             id,
         }
     }
+
+    /// Get account balance
+    ///
+    /// Params are `[address, blockTag?]`.
+    fn get_balance(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(state) = &self.state else {
+            return Self::error(id, -32000, "State not available");
+        };
+        let address = match parse_account_params(&params) {
+            Ok(address) => address,
+            Err(message) => return Self::error(id, -32602, &message),
+        };
+        Self::success(id, serde_json::json!(format!("{:#x}", state.read().get_balance(address))))
+    }
+
+    /// Get account nonce
+    ///
+    /// Params are `[address, blockTag?]`.
+    fn get_transaction_count(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(state) = &self.state else {
+            return Self::error(id, -32000, "State not available");
+        };
+        let address = match parse_account_params(&params) {
+            Ok(address) => address,
+            Err(message) => return Self::error(id, -32602, &message),
+        };
+        Self::success(id, serde_json::json!(format!("{:#x}", state.read().get_nonce(address))))
+    }
+
+    /// Get a block by hash
+    ///
+    /// Params are `[hash, fullTransactions]`. Unknown blocks give a null result.
+    fn get_block_by_hash(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(blocks) = &self.blocks else {
+            return Self::error(id, -32000, "Blocks not available");
+        };
+        let hash = match params.get(0).ok_or_else(|| "Missing block hash".to_string()).and_then(parse_hash) {
+            Ok(hash) => hash,
+            Err(message) => return Self::error(id, -32602, &message),
+        };
+        let full = params.get(1).and_then(serde_json::Value::as_bool).unwrap_or(false);
+        Self::block_response(id, blocks.as_ref(), Some(hash), full)
+    }
+
+    /// Get the canonical block at a height
+    ///
+    /// Params are `[number | "earliest" | "latest", fullTransactions]`.
+    /// Unknown blocks give a null result.
+    fn get_block_by_number(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(blocks) = &self.blocks else {
+            return Self::error(id, -32000, "Blocks not available");
+        };
+        let number = match params.get(0) {
+            Some(tag) if tag.as_str() == Some("earliest") => 0,
+            Some(tag) if matches!(tag.as_str(), Some("latest" | "pending")) => self.node_info.head_block,
+            Some(number) => match parse_quantity(number) {
+                Ok(number) if number <= U256::from(u64::MAX) => number.as_u64(),
+                Ok(number) => return Self::error(id, -32602, &format!("Invalid block number: {:#x}", number)),
+                Err(message) => return Self::error(id, -32602, &message),
+            },
+            None => return Self::error(id, -32602, "Missing block number"),
+        };
+        let full = params.get(1).and_then(serde_json::Value::as_bool).unwrap_or(false);
+        match blocks.block_hash(number) {
+            Ok(hash) => Self::block_response(id, blocks.as_ref(), hash, full),
+            Err(e) => Self::error(id, -32000, &e.to_string()),
+        }
+    }
+
+    /// Look up a block and encode it, or null if it is unknown
+    fn block_response(id: serde_json::Value, blocks: &dyn BlockProvider, hash: Option<H256>, full: bool) -> JsonRpcResponse {
+        let block = match hash.map(|hash| blocks.block(hash)).transpose() {
+            Ok(block) => block.flatten(),
+            Err(e) => return Self::error(id, -32000, &e.to_string()),
+        };
+        match block.map(|block| block_to_json(&block, blocks, full)).transpose() {
+            Ok(result) => Self::success(id, result.unwrap_or(serde_json::Value::Null)),
+            Err(e) => Self::error(id, -32000, &e.to_string()),
+        }
+    }
+
+    /// Execute a read-only call, optionally against overridden state
+    ///
+    /// Params are `[{from, to, data}, blockTag?, stateOverride?]`.
+    fn call(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let parsed = (|| {
+            let call = params.get(0).ok_or("Missing call object")?;
+            let from = match call.get("from") {
+                Some(from) => parse_address(from)?,
+                None => Address::zero(),
+            };
+            let to = parse_address(call.get("to").ok_or("Missing call target")?)?;
+            let data = match call.get("data").or_else(|| call.get("input")) {
+                Some(data) => parse_bytes(data)?,
+                None => vec![],
+            };
+            check_block_tag(params.get(1))?;
+            let block_tag = params.get(1).and_then(|tag| tag.as_str()).unwrap_or("latest").to_string();
+            let overrides = match params.get(2) {
+                Some(overrides) => parse_state_override(overrides)?,
+                None => StateOverride::new(),
+            };
+            Ok::<_, String>((from, to, data, block_tag, overrides))
+        })();
+
+        let (from, to, data, block_tag, overrides) = match parsed {
+            Ok(parsed) => parsed,
+            Err(message) => return Self::error(id, -32602, &message),
+        };
+
+        let execute = |data: Vec<u8>| {
+            let mut executive = match &self.state {
+                Some(state) => Executive::new_with_state(Box::new(Arc::clone(state))),
+                None => Executive::new(),
+            };
+            executive.set_execution_limits(self.call_limits);
+            executive.call_with_overrides(from, to, data, &overrides)
+        };
+        // Overridden state is per request, so only plain calls are cached
+        let output = match self.call_cache.as_ref().filter(|_| overrides.is_empty()) {
+            Some(call_cache) => {
+                let state_root = call_cache.lock().state_root();
+                let key = CallCacheKey { from, to, data: data.clone(), block_tag, state_root };
+                let cached = call_cache.lock().get(&key);
+                match cached {
+                    Some(output) => Ok(output),
+                    None => execute(data).inspect(|output| call_cache.lock().insert(key, output.clone())),
+                }
+            }
+            None => execute(data),
+        };
+        match output {
+            Ok(output) => Self::success(id, serde_json::json!(format!("0x{}", hex::encode(output)))),
+            Err(e) => Self::error(id, -32000, &e.to_string()),
+        }
+    }
+
+    /// Admit a batch of raw signed transactions to the pool
+    ///
+    /// Each transaction is handled independently; the result holds its hash or
+    /// the reason it was rejected, in submission order.
+    fn send_raw_transactions(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(pool) = &self.transaction_pool else {
+            return Self::error(id, -32000, "Transaction pool not available");
+        };
+        let Some(raw_transactions) = params.get(0).and_then(|raw| raw.as_array()) else {
+            return Self::error(id, -32602, "Expected an array of raw transactions");
+        };
+
+        let mut pool = pool.lock();
+        let results = raw_transactions.iter().map(|raw| {
+            let admitted = parse_bytes(raw).and_then(|bytes| {
+                let transaction: Transaction = rlp::decode(&bytes).map_err(|e| format!("Invalid transaction RLP: {}", e))?;
+                transaction.validate(CheckTransaction::Everything).map_err(|e| e.to_string())?;
+                transaction.sender().map_err(|e| e.to_string())?;
+                let hash = transaction.hash();
+                pool.add_transaction(transaction).map_err(|e| e.to_string())?;
+                Ok(hash)
+            });
+            match admitted {
+                Ok(hash) => serde_json::json!({ "hash": format!("{:#x}", hash) }),
+                Err(message) => serde_json::json!({ "error": message }),
+            }
+        }).collect::<Vec<_>>();
+
+        Self::success(id, serde_json::Value::Array(results))
+    }
+
+    /// Decode a raw transaction, validate it as the batch path does and submit
+    /// it to the pool, returning its hash
+    fn send_raw_transaction(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(pool) = &self.transaction_pool else {
+            return Self::error(id, -32000, "Transaction pool not available");
+        };
+        let decoded = params.get(0)
+            .ok_or_else(|| "Missing raw transaction".to_string())
+            .and_then(parse_bytes)
+            .and_then(|bytes| Transaction::from_bytes(&bytes).map_err(|e| format!("Invalid transaction: {}", e)));
+        let transaction = match decoded {
+            Ok(transaction) => transaction,
+            Err(message) => return Self::error(id, -32602, &message),
+        };
+        if let Err(e) = transaction.validate(CheckTransaction::Everything).and_then(|_| transaction.sender()) {
+            return Self::error(id, -32000, &e.to_string());
+        }
+
+        let hash = transaction.hash();
+        let mut pool = pool.lock();
+        if !pool.can_admit(&transaction) {
+            return Self::error(id, POOL_FULL_ERROR_CODE, "Transaction pool is full");
+        }
+        match pool.add_transaction(transaction) {
+            Ok(_) => Self::success(id, serde_json::json!(format!("{:#x}", hash))),
+            Err(e) => Self::error(id, -32000, &e.to_string()),
+        }
+    }
+
+    /// Replace a pending transaction with a higher-fee cancel from the same sender
+    ///
+    /// Params are `[originalHash, rawCancelTransaction]`; returns the cancel's hash.
+    fn cancel_transaction(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(pool) = &self.transaction_pool else {
+            return Self::error(id, -32000, "Transaction pool not available");
+        };
+        let parsed = (|| {
+            let hash = parse_hash(params.get(0).ok_or("Missing transaction hash")?)?;
+            let bytes = parse_bytes(params.get(1).ok_or("Missing cancel transaction")?)?;
+            let cancel: Transaction = rlp::decode(&bytes).map_err(|e| format!("Invalid transaction RLP: {}", e))?;
+            Ok::<_, String>((hash, cancel))
+        })();
+        let (hash, cancel) = match parsed {
+            Ok(parsed) => parsed,
+            Err(message) => return Self::error(id, -32602, &message),
+        };
+
+        let cancel_hash = cancel.hash();
+        let cancelled = cancel.validate(CheckTransaction::Everything)
+            .and_then(|_| pool.lock().cancel_transaction(hash, cancel));
+        match cancelled {
+            Ok(()) => Self::success(id, serde_json::Value::String(format!("{:#x}", cancel_hash))),
+            Err(e) => Self::error(id, -32000, &e.to_string()),
+        }
+    }
+
+    /// Decode a raw transaction without submitting it
+    ///
+    /// Returns its fields, type, signature and recovered sender; `from` is null
+    /// and `senderError` explains why when the sender cannot be recovered.
+    fn decode_raw_transaction(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let decoded = params.get(0)
+            .ok_or_else(|| "Missing raw transaction".to_string())
+            .and_then(parse_bytes)
+            .and_then(|bytes| Transaction::from_bytes(&bytes).map_err(|e| format!("Invalid transaction: {}", e)));
+        let transaction = match decoded {
+            Ok(transaction) => transaction,
+            Err(message) => return Self::error(id, -32602, &message),
+        };
+
+        let mut result = transaction_to_json(&transaction);
+        result["type"] = serde_json::json!(format!("{:#x}", transaction.transaction_type().type_byte()));
+        match transaction.sender() {
+            Ok(sender) => result["from"] = serde_json::json!(format!("{:#x}", sender)),
+            Err(e) => {
+                result["from"] = serde_json::Value::Null;
+                result["senderError"] = serde_json::json!(e.to_string());
+            }
+        }
+        if let Some(signature) = &transaction.signature {
+            result["v"] = serde_json::json!(format!("{:#x}", signature.v));
+            result["r"] = serde_json::json!(format!("{:#x}", signature.r));
+            result["s"] = serde_json::json!(format!("{:#x}", signature.s));
+        }
+        Self::success(id, result)
+    }
+
+    /// Get the canonical genesis hash
+    fn get_genesis_hash(&self, id: serde_json::Value) -> JsonRpcResponse {
+        match self.genesis_hash {
+            Some(hash) => Self::success(id, serde_json::Value::String(format!("{:#x}", hash))),
+            None => Self::error(id, -32000, "Genesis not initialized"),
+        }
+    }
+
+    /// Get the P2P protocol version
+    fn get_protocol_version(&self, id: serde_json::Value) -> JsonRpcResponse {
+        Self::success(id, serde_json::Value::String(format!("{:#x}", PROTOCOL_VERSION)))
+    }
+
+    /// Get structured node info
+    fn get_node_info(&self, id: serde_json::Value) -> JsonRpcResponse {
+        let info = &self.node_info;
+        Self::success(id, serde_json::json!({
+            "clientVersion": info.client_version,
+            "chainId": format!("{:#x}", info.chain_id),
+            "genesisHash": self.genesis_hash.map(|hash| format!("{:#x}", hash)),
+            "headBlock": {
+                "number": format!("{:#x}", info.head_block),
+                "hash": format!("{:#x}", info.head_block_hash),
+            },
+            "epoch": format!("{:#x}", info.epoch),
+            "isWitness": info.is_witness,
+            "peerCount": format!("{:#x}", info.peer_count),
+        }))
+    }
+
+    /// Get the length of an account's code, without transferring it
+    fn get_code_size(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(state) = &self.state else {
+            return Self::error(id, -32000, "State not available");
+        };
+        let address = match params.get(0).ok_or_else(|| "Missing address".to_string()).and_then(parse_address) {
+            Ok(address) => address,
+            Err(message) => return Self::error(id, -32602, &message),
+        };
+        Self::success(id, serde_json::json!(format!("{:#x}", state.read().code_size(address))))
+    }
+
+    /// Get the witnesses and stakes of the current or a given past epoch
+    fn get_witnesses(&self, params: serde_json::Value, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(history) = &self.witness_sets else {
+            return Self::error(id, -32000, "Consensus not initialized");
+        };
+        let epoch = match params.get(0).filter(|epoch| !epoch.is_null()).map(parse_quantity) {
+            None => history.current_epoch,
+            Some(Ok(epoch)) if epoch <= U256::from(u64::MAX) => epoch.as_u64(),
+            Some(Ok(epoch)) => return Self::error(id, -32602, &format!("Invalid epoch: {:#x}", epoch)),
+            Some(Err(e)) => return Self::error(id, -32602, &e),
+        };
+        let Some(witnesses) = history.get(Some(epoch)) else {
+            return Self::error(id, -32000, &format!("Unknown epoch: {:#x}", epoch));
+        };
+
+        Self::success(id, serde_json::json!({
+            "epoch": format!("{:#x}", epoch),
+            "witnesses": witnesses.iter().map(|witness| serde_json::json!({
+                "address": format!("{:#x}", witness.address),
+                "stake": format!("{:#x}", witness.stake),
+            })).collect::<Vec<_>>(),
+        }))
+    }
+
+    /// Get a snapshot of the consensus state
+    fn get_consensus_state(&self, id: serde_json::Value) -> JsonRpcResponse {
+        let Some(state) = &self.consensus_state else {
+            return Self::error(id, -32000, "Consensus not initialized");
+        };
+        Self::success(id, serde_json::json!({
+            "epoch": format!("{:#x}", state.epoch),
+            "witnesses": state.witnesses.iter().map(|w| format!("{:#x}", w)).collect::<Vec<_>>(),
+            "blockCount": format!("{:#x}", state.block_count),
+            "confirmedCount": format!("{:#x}", state.confirmed_count),
+            "stableCount": format!("{:#x}", state.stable_count),
+            "head": state.head.map(|hash| format!("{:#x}", hash)),
+            "rejectedApprovals": format!("{:#x}", state.rejected_approvals),
+        }))
+    }
+}
+
+impl Default for RpcMethods {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::state::{MemoryState, State};
+
+    fn request(method: &str, params: serde_json::Value) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+            id: serde_json::json!(1),
+        }
+    }
+
+    #[test]
+    fn test_genesis_hash_rpc() {
+        let genesis = crate::core::genesis::Genesis::default();
+        let mut methods = RpcMethods::new();
+        methods.set_genesis_hash(genesis.hash());
+
+        let response = methods.handle_request(request("olympus_genesisHash", serde_json::json!([])));
+        assert_eq!(response.result, Some(serde_json::json!(format!("{:#x}", genesis.hash()))));
+    }
+
+    #[test]
+    fn test_protocol_version_rpc() {
+        let methods = RpcMethods::new();
+        let response = methods.handle_request(request("eth_protocolVersion", serde_json::json!([])));
+        assert!(response.error.is_none());
+        assert_eq!(response.result, Some(serde_json::json!(format!("{:#x}", PROTOCOL_VERSION))));
+    }
+
+    #[test]
+    fn test_node_info_reflects_node_state() {
+        let genesis = crate::core::genesis::Genesis::default();
+        let mut methods = RpcMethods::new();
+        methods.set_genesis_hash(genesis.hash());
+        {
+            let info = methods.node_info_mut();
+            info.head_block = 42;
+            info.head_block_hash = H256::repeat_byte(0xab);
+            info.epoch = 3;
+            info.is_witness = true;
+            info.peer_count = 5;
+        }
+
+        let response = methods.handle_request(request("olympus_nodeInfo", serde_json::json!([])));
+        let result = response.result.unwrap();
+        assert!(result["clientVersion"].as_str().unwrap().starts_with("olympus/v"));
+        assert_eq!(result["chainId"], format!("{:#x}", CHAIN_ID).as_str());
+        assert_eq!(result["genesisHash"], format!("{:#x}", genesis.hash()).as_str());
+        assert_eq!(result["headBlock"]["number"], "0x2a");
+        assert_eq!(result["headBlock"]["hash"], format!("{:#x}", H256::repeat_byte(0xab)).as_str());
+        assert_eq!(result["epoch"], "0x3");
+        assert_eq!(result["isWitness"], true);
+        assert_eq!(result["peerCount"], "0x5");
+    }
+
+    #[test]
+    fn test_consensus_state_rpc() {
+        let mut methods = RpcMethods::new();
+        let response = methods.handle_request(request("debug_consensusState", serde_json::json!([])));
+        assert!(response.error.is_some());
+
+        let mut consensus = crate::consensus::dag::DagConsensus::new_default();
+        consensus.current_epoch = 2;
+        consensus.witnesses = vec![crate::Address::repeat_byte(0x11)];
+        methods.set_consensus_state(consensus.snapshot());
+
+        let result = methods.handle_request(request("debug_consensusState", serde_json::json!([]))).result.unwrap();
+        assert_eq!(result["epoch"], "0x2");
+        assert_eq!(result["witnesses"][0], format!("{:#x}", crate::Address::repeat_byte(0x11)).as_str());
+        assert_eq!(result["confirmedCount"], "0x0");
+        assert_eq!(result["stableCount"], "0x0");
+        assert!(result["head"].is_null());
+        assert_eq!(result["rejectedApprovals"], "0x0");
+    }
+
+    #[test]
+    fn test_get_witnesses_returns_historical_sets() {
+        let witness = |byte: u8| crate::Address::repeat_byte(byte);
+        let mut consensus = crate::consensus::dag::DagConsensus::new(1, 21, 100);
+        consensus.witness_manager.set_stake(witness(0x11), 100);
+        consensus.witness_manager.set_stake(witness(0x22), 200);
+        consensus.witness_manager.set_stake(witness(0x33), 300);
+        consensus.witnesses = vec![witness(0x11)];
+        consensus.advance_epoch(vec![witness(0x22), witness(0x33)]).unwrap();
+        consensus.advance_epoch(vec![witness(0x33)]).unwrap();
+
+        let mut methods = RpcMethods::new();
+        methods.set_witness_sets(consensus.witness_sets());
+        let witnesses = |params: serde_json::Value| {
+            let result = methods.handle_request(request("olympus_getWitnesses", params)).result.unwrap();
+            let set: Vec<_> = result["witnesses"].as_array().unwrap().iter()
+                .map(|w| (w["address"].as_str().unwrap().to_string(), w["stake"].as_str().unwrap().to_string()))
+                .collect();
+            (result["epoch"].as_str().unwrap().to_string(), set)
+        };
+        let entry = |byte: u8, stake: &str| (format!("{:#x}", witness(byte)), stake.to_string());
+
+        assert_eq!(witnesses(serde_json::json!([])), ("0x2".to_string(), vec![entry(0x33, "0x12c")]));
+        assert_eq!(witnesses(serde_json::json!(["0x0"])), ("0x0".to_string(), vec![entry(0x11, "0x64")]));
+        assert_eq!(witnesses(serde_json::json!(["0x1"])), ("0x1".to_string(), vec![entry(0x22, "0xc8"), entry(0x33, "0x12c")]));
+
+        let response = methods.handle_request(request("olympus_getWitnesses", serde_json::json!(["0x3"])));
+        assert_eq!(response.error.unwrap().code, -32000);
+    }
+
+    #[test]
+    fn test_send_raw_transactions_reports_each_result() {
+        let pool = Arc::new(Mutex::new(TransactionPool::new(10)));
+        let mut methods = RpcMethods::new();
+        methods.set_transaction_pool(Arc::clone(&pool));
+
+        let transaction = |nonce: u64, signed: bool| {
+            let mut tx = Transaction::new(U256::from(1000), U256::from(2_000_000_000u64), U256::from(21000), Address::repeat_byte(0x42), vec![], U256::from(nonce));
+            if signed {
+                tx.sign_with_secret(&[0x77u8; 32]).unwrap();
+            }
+            tx
+        };
+        let raw = |tx: &Transaction| format!("0x{}", hex::encode(rlp::encode(tx)));
+        let (first, second, unsigned) = (transaction(0, true), transaction(1, true), transaction(2, false));
+
+        let params = serde_json::json!([[raw(&first), "0xzz", raw(&unsigned), "0xc0", raw(&second)]]);
+        let results = methods.handle_request(request("olympus_sendRawTransactions", params)).result.unwrap();
+        let results = results.as_array().unwrap();
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0]["hash"], format!("{:#x}", first.hash()).as_str());
+        assert!(results[1]["error"].is_string());
+        assert!(results[2]["error"].as_str().unwrap().contains("signed"));
+        assert!(results[3]["error"].is_string());
+        assert_eq!(results[4]["hash"], format!("{:#x}", second.hash()).as_str());
+
+        let pending: Vec<_> = pool.lock().get_pending_transactions().iter().map(|tx| tx.hash()).collect();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.contains(&first.hash()) && pending.contains(&second.hash()));
+    }
+
+    #[test]
+    fn test_send_raw_transactions_admits_pre_eip155_legacy() {
+        let pool = Arc::new(Mutex::new(TransactionPool::new(10)));
+        let mut methods = RpcMethods::new();
+        methods.set_transaction_pool(Arc::clone(&pool));
+
+        let mut tx = Transaction::new(U256::from(1000), U256::from(2_000_000_000u64), U256::from(21000), Address::repeat_byte(0x42), vec![], U256::zero());
+        tx.chain_id = None;
+        tx.sign_with_secret(&[0x77u8; 32]).unwrap();
+        let raw = format!("0x{}", hex::encode(rlp::encode(&tx)));
+
+        let results = methods.handle_request(request("olympus_sendRawTransactions", serde_json::json!([[raw]]))).result.unwrap();
+        assert_eq!(results[0]["hash"], format!("{:#x}", tx.hash()).as_str());
+        let pooled = pool.lock().get_transaction(tx.hash()).cloned().unwrap();
+        assert_eq!(pooled.chain_id, None);
+        assert_eq!(pooled.sender().unwrap(), tx.sender().unwrap());
+    }
+
+    #[test]
+    fn test_decode_raw_transaction_recovers_sender() {
+        let methods = RpcMethods::new();
+        let mut tx = Transaction::new_eip1559(U256::from(1000), U256::from(3_000_000_000u64), U256::from(2), U256::from(21000), Address::repeat_byte(0x42), vec![0xab], U256::from(7));
+        tx.sign_with_secret(&[0x77u8; 32]).unwrap();
+        let raw = format!("0x{}", hex::encode(tx.rlp_bytes(crate::core::transaction::IncludeSignature::WithSignature)));
+
+        let decoded = methods.handle_request(request("olympus_decodeRawTransaction", serde_json::json!([raw]))).result.unwrap();
+        assert_eq!(decoded["from"], format!("{:#x}", tx.sender().unwrap()).as_str());
+        assert_eq!(decoded["hash"], format!("{:#x}", tx.hash()).as_str());
+        assert_eq!(decoded["type"], "0x2");
+        assert_eq!(decoded["nonce"], "0x7");
+        assert_eq!(decoded["value"], "0x3e8");
+        assert_eq!(decoded["maxPriorityFeePerGas"], "0x2");
+        assert_eq!(decoded["to"], format!("{:#x}", Address::repeat_byte(0x42)).as_str());
+        assert_eq!(decoded["input"], "0xab");
+
+        let malformed = methods.handle_request(request("olympus_decodeRawTransaction", serde_json::json!(["0xc3010203"])));
+        let error = malformed.error.unwrap();
+        assert_eq!(error.code, -32602);
+        assert!(error.message.starts_with("Invalid transaction"));
+    }
+
+    #[test]
+    fn test_send_raw_transaction_returns_hash() {
+        let pool = Arc::new(Mutex::new(TransactionPool::new(1)));
+        let mut methods = RpcMethods::new();
+        methods.set_transaction_pool(Arc::clone(&pool));
+
+        let transaction = |nonce: u64| {
+            let mut tx = Transaction::new(U256::from(1000), U256::from(2_000_000_000u64), U256::from(21000), Address::repeat_byte(0x42), vec![], U256::from(nonce));
+            tx.sign_with_secret(&[0x77u8; 32]).unwrap();
+            tx
+        };
+        let send = |raw: String| methods.handle_request(request("eth_sendRawTransaction", serde_json::json!([raw])));
+        let tx = transaction(0);
+
+        let response = send(format!("0x{}", hex::encode(rlp::encode(&tx))));
+        assert_eq!(response.result.unwrap(), format!("{:#x}", tx.hash()).as_str());
+        assert!(pool.lock().get_transaction(tx.hash()).is_some());
+
+        assert_eq!(send("0xzz".to_string()).error.unwrap().code, -32602);
+        let unsigned = Transaction::new(U256::from(1000), U256::from(2_000_000_000u64), U256::from(21000), Address::repeat_byte(0x42), vec![], U256::from(1));
+        let rejected = send(format!("0x{}", hex::encode(rlp::encode(&unsigned)))).error.unwrap();
+        assert_eq!(rejected.code, -32000);
+        assert!(rejected.message.contains("signed"));
+        assert!(pool.lock().get_transaction(unsigned.hash()).is_none());
+        let full = send(format!("0x{}", hex::encode(rlp::encode(&transaction(1)))));
+        assert_eq!(full.error.unwrap().code, POOL_FULL_ERROR_CODE);
+    }
+
+    #[test]
+    fn test_cancel_transaction_evicts_original() {
+        let pool = Arc::new(Mutex::new(TransactionPool::new(10)));
+        let mut methods = RpcMethods::new();
+        methods.set_transaction_pool(Arc::clone(&pool));
+
+        let signed = |to: Address, gas_price: u64, secret: u8| {
+            let mut tx = Transaction::new(U256::from(1000), U256::from(gas_price), U256::from(21000), to, vec![], U256::zero());
+            tx.sign_with_secret(&[secret; 32]).unwrap();
+            tx
+        };
+        let raw = |tx: &Transaction| format!("0x{}", hex::encode(rlp::encode(tx)));
+        let original = signed(Address::repeat_byte(0x42), 2_000_000_000, 0x77);
+        pool.lock().add_transaction(original.clone()).unwrap();
+        let cancel = |tx: &Transaction| {
+            let params = serde_json::json!([format!("{:#x}", original.hash()), raw(tx)]);
+            methods.handle_request(request("olympus_cancelTransaction", params))
+        };
+
+        // Another signer, or a fee that is not higher, cannot cancel
+        let sender = original.from();
+        assert!(cancel(&signed(sender, 3_000_000_000, 0x78)).error.is_some());
+        assert!(cancel(&signed(sender, 2_000_000_000, 0x77)).error.is_some());
+        assert_eq!(pool.lock().get_pending_transactions().len(), 1);
+
+        let replacement = signed(sender, 3_000_000_000, 0x77);
+        assert_eq!(cancel(&replacement).result.unwrap(), format!("{:#x}", replacement.hash()).as_str());
+        let pending: Vec<_> = pool.lock().get_pending_transactions().iter().map(|tx| tx.hash()).collect();
+        assert_eq!(pending, vec![replacement.hash()]);
+    }
+
+    #[test]
+    fn test_get_code_size_rpc() {
+        let contract = Address::repeat_byte(0xc0);
+        let account = Address::repeat_byte(0xee);
+        let mut state = MemoryState::new();
+        state.set_code(contract, vec![0x60; 300]);
+        state.set_balance(account, U256::from(1000));
+
+        let mut methods = RpcMethods::new();
+        methods.set_state(Arc::new(parking_lot::RwLock::new(Box::new(state))));
+        let code_size = |address: Address| {
+            let params = serde_json::json!([format!("{:#x}", address)]);
+            methods.handle_request(request("olympus_getCodeSize", params)).result.unwrap()
+        };
+
+        assert_eq!(code_size(contract), "0x12c");
+        assert_eq!(code_size(account), "0x0");
+        let response = methods.handle_request(request("olympus_getCodeSize", serde_json::json!(["0x12"])));
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_account_queries_read_state() {
+        let account = Address::repeat_byte(0xee);
+        let mut state = MemoryState::new();
+        state.set_balance(account, U256::from(1000));
+        state.set_nonce(account, 5);
+
+        let mut methods = RpcMethods::new();
+        methods.set_state(Arc::new(parking_lot::RwLock::new(Box::new(state))));
+        let query = |method: &str, params: serde_json::Value| methods.handle_request(request(method, params));
+        let address = format!("{:#x}", account);
+
+        assert_eq!(query("eth_getBalance", serde_json::json!([address, "latest"])).result.unwrap(), "0x3e8");
+        assert_eq!(query("eth_getTransactionCount", serde_json::json!([address])).result.unwrap(), "0x5");
+        let unknown = format!("{:#x}", Address::repeat_byte(0x01));
+        assert_eq!(query("eth_getBalance", serde_json::json!([unknown, "pending"])).result.unwrap(), "0x0");
+
+        for params in [serde_json::json!([]), serde_json::json!(["0x12"]), serde_json::json!([address, "earliest"])] {
+            assert_eq!(query("eth_getTransactionCount", params).error.unwrap().code, -32602);
+        }
+    }
+
+    #[test]
+    fn test_call_executes_overridden_code() {
+        let methods = RpcMethods::new();
+        let contract = format!("{:#x}", Address::repeat_byte(0xc0));
+        // PUSH1 0x2a PUSH1 0x00 MSTORE PUSH1 0x20 PUSH1 0x00 RETURN
+        let code = "0x602a60005260206000f3";
+        let call = serde_json::json!({ "to": contract, "data": "0x" });
+
+        let response = methods.handle_request(request("eth_call", serde_json::json!([call, "latest"])));
+        assert_eq!(response.result, Some(serde_json::json!("0x")));
+
+        let overrides = serde_json::json!({ contract.clone(): { "code": code, "balance": "0x1" } });
+        let response = methods.handle_request(request("eth_call", serde_json::json!([call, "latest", overrides])));
+        assert!(response.error.is_none());
+        assert_eq!(response.result, Some(serde_json::json!(format!("0x{:064x}", 0x2a))));
+
+        // The override is discarded after the call
+        let response = methods.handle_request(request("eth_call", serde_json::json!([call, "latest"])));
+        assert_eq!(response.result, Some(serde_json::json!("0x")));
+    }
+
+    #[test]
+    fn test_call_results_cached_until_block_commit() {
+        // PUSH1 0 SLOAD PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+        let contract = Address::repeat_byte(0xc2);
+        let mut state = MemoryState::new();
+        state.set_code(contract, vec![0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+        state.set_storage(contract, H256::zero(), H256::from_low_u64_be(1));
+        let state: SharedState = Arc::new(parking_lot::RwLock::new(Box::new(state)));
+
+        let mut methods = RpcMethods::new();
+        methods.set_state(Arc::clone(&state));
+        methods.set_call_cache(CallCache::new(16, std::time::Duration::from_secs(60)));
+        let call = || {
+            let call = serde_json::json!({ "to": format!("{:#x}", contract) });
+            methods.handle_request(request("eth_call", serde_json::json!([call, "latest"]))).result.unwrap()
+        };
+        let word = |value: u64| serde_json::json!(format!("0x{:064x}", value));
+
+        assert_eq!(call(), word(1));
+        state.write().set_storage(contract, H256::zero(), H256::from_low_u64_be(2));
+        assert_eq!(call(), word(1));
+        assert_eq!(methods.call_cache.as_ref().unwrap().lock().hits(), 1);
+
+        // Committing the block that changed the state drops the cached result
+        let state_root = state.read().state_root();
+        methods.on_block_committed(state_root);
+        assert_eq!(call(), word(2));
+    }
+
+    #[test]
+    fn test_call_echoes_identity_and_reports_revert_reason() {
+        let mut payload = crate::evm::executive::REVERT_REASON_SELECTOR.to_vec();
+        payload.extend_from_slice(H256::from_low_u64_be(32).as_bytes());
+        payload.extend_from_slice(H256::from_low_u64_be(4).as_bytes());
+        payload.extend_from_slice(&[b"nope".as_slice(), &[0u8; 28]].concat());
+        // PUSH1 100 PUSH1 12 PUSH1 0 CODECOPY PUSH1 100 PUSH1 0 REVERT, followed by the payload
+        let reverter = Address::repeat_byte(0xc1);
+        let mut state = MemoryState::new();
+        state.set_code(reverter, [vec![0x60, 0x64, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, 0x64, 0x60, 0x00, 0xfd], payload].concat());
+
+        let mut methods = RpcMethods::new();
+        methods.set_state(Arc::new(parking_lot::RwLock::new(Box::new(state))));
+        let call = |to: Address, data: &str| {
+            let call = serde_json::json!({ "to": format!("{:#x}", to), "data": data });
+            methods.handle_request(request("eth_call", serde_json::json!([call, "latest"])))
+        };
+
+        let identity = Address::from_low_u64_be(4);
+        assert_eq!(call(identity, "0xdeadbeef").result, Some(serde_json::json!("0xdeadbeef")));
+
+        let error = call(reverter, "0x").error.unwrap();
+        assert_eq!(error.code, -32000);
+        assert!(error.message.contains("Execution reverted: nope"));
+
+        let call_object = serde_json::json!({ "to": format!("{:#x}", identity) });
+        let response = methods.handle_request(request("eth_call", serde_json::json!([call_object, "0x1"])));
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_batch_answers_requests_but_not_notifications() {
+        let methods = RpcMethods::new();
+        let batch = serde_json::json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "eth_protocolVersion" },
+            { "jsonrpc": "2.0", "method": "eth_protocolVersion" },
+            { "jsonrpc": "2.0", "id": "two", "method": "eth_unknown", "params": [] },
+            42,
+        ]);
+
+        let responses = methods.handle_body(batch).unwrap();
+        let responses = responses.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"], format!("{:#x}", PROTOCOL_VERSION).as_str());
+        assert_eq!(responses[1]["id"], "two");
+        assert_eq!(responses[1]["error"]["code"], -32601);
+        assert_eq!(responses[2]["error"]["code"], -32600);
+
+        // Single objects get a single response, and a lone notification none
+        let single = methods.handle_body(serde_json::json!({ "jsonrpc": "2.0", "id": 7, "method": "eth_protocolVersion" }));
+        assert_eq!(single.unwrap()["id"], 7);
+        assert!(methods.handle_body(serde_json::json!([{ "jsonrpc": "2.0", "method": "eth_blockNumber" }])).is_none());
+    }
+
+    #[test]
+    fn test_call_rejects_conflicting_storage_overrides() {
+        let methods = RpcMethods::new();
+        let contract = format!("{:#x}", Address::repeat_byte(0xc0));
+        let call = serde_json::json!({ "to": contract });
+        let overrides = serde_json::json!({ contract.clone(): { "state": {}, "stateDiff": {} } });
+
+        let response = methods.handle_request(request("eth_call", serde_json::json!([call, "latest", overrides])));
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[test]
+    fn test_call_reports_timeout() {
+        let mut methods = RpcMethods::new();
+        methods.set_call_limits(ExecutionLimits { max_steps: Some(100), ..Default::default() });
+        let contract = format!("{:#x}", Address::repeat_byte(0xc0));
+        let call = serde_json::json!({ "to": contract });
+        // JUMPDEST PUSH1 0x00 JUMP
+        let overrides = serde_json::json!({ contract.clone(): { "code": "0x5b600056" } });
+
+        let response = methods.handle_request(request("eth_call", serde_json::json!([call, "latest", overrides])));
+        let error = response.error.unwrap();
+        assert_eq!(error.code, -32000);
+        assert!(error.message.contains("Execution timed out"));
+    }
+
+    #[derive(Default)]
+    struct MemoryBlocks {
+        blocks: std::collections::HashMap<H256, Block>,
+        canonical: Vec<H256>,
+        transactions: std::collections::HashMap<H256, Transaction>,
+    }
+
+    impl BlockProvider for MemoryBlocks {
+        fn block(&self, hash: H256) -> Result<Option<Block>> {
+            Ok(self.blocks.get(&hash).cloned())
+        }
+
+        fn block_hash(&self, number: u64) -> Result<Option<H256>> {
+            Ok(self.canonical.get(number as usize).copied())
+        }
+
+        fn transaction(&self, hash: H256) -> Result<Option<Transaction>> {
+            Ok(self.transactions.get(&hash).cloned())
+        }
+    }
+
+    #[test]
+    fn test_get_block_by_hash_and_number() {
+        let transaction = Transaction::new(U256::one(), U256::from(1_000_000_000u64), U256::from(21_000), Address::repeat_byte(0x22), vec![], U256::zero());
+        let mut block = crate::core::genesis::Genesis::default().genesis_block();
+        block.from = Address::repeat_byte(0x11);
+        block.previous = H256::repeat_byte(0x01);
+        block.exec_timestamp = 1_600_000_000;
+        block.gas_used = U256::from(21_000);
+        block.links = vec![transaction.hash()];
+        let hash = block.hash();
+
+        let mut blocks = MemoryBlocks { canonical: vec![H256::zero(), hash], ..Default::default() };
+        blocks.blocks.insert(hash, block);
+        blocks.transactions.insert(transaction.hash(), transaction.clone());
+        let mut methods = RpcMethods::new();
+        methods.set_block_provider(Arc::new(blocks));
+        methods.node_info_mut().head_block = 1;
+
+        let by_hash = methods.handle_request(request("eth_getBlockByHash", serde_json::json!([format!("{:#x}", hash), false]))).result.unwrap();
+        assert_eq!(by_hash["hash"], format!("{:#x}", hash).as_str());
+        assert_eq!(by_hash["parentHash"], format!("{:#x}", H256::repeat_byte(0x01)).as_str());
+        assert_eq!(by_hash["timestamp"], "0x5f5e1000");
+        assert_eq!(by_hash["gasUsed"], "0x5208");
+        assert_eq!(by_hash["transactions"], serde_json::json!([format!("{:#x}", transaction.hash())]));
+
+        let by_number = methods.handle_request(request("eth_getBlockByNumber", serde_json::json!(["0x1", true]))).result.unwrap();
+        assert_eq!(by_number["hash"], by_hash["hash"]);
+        assert_eq!(by_number["transactions"][0], transaction_to_json(&transaction));
+        let latest = methods.handle_request(request("eth_getBlockByNumber", serde_json::json!(["latest", false]))).result.unwrap();
+        assert_eq!(latest, by_hash);
+
+        // Unknown blocks are null, not errors
+        for (method, params) in [
+            ("eth_getBlockByHash", serde_json::json!([format!("{:#x}", H256::repeat_byte(0xee)), false])),
+            ("eth_getBlockByNumber", serde_json::json!(["0x0", false])),
+            ("eth_getBlockByNumber", serde_json::json!(["0x7", false])),
+        ] {
+            let response = methods.handle_request(request(method, params));
+            assert!(response.error.is_none());
+            assert_eq!(response.result, Some(serde_json::Value::Null));
+        }
+    }
 }
@@ -2,6 +2,10 @@
 
 pub mod server;
 pub mod methods;
+pub mod call_cache;
+pub mod subscription;
 
 pub use server::*;
 pub use methods::*;
+pub use call_cache::*;
+pub use subscription::*;
@@ -1,7 +1,24 @@
 //! RPC server
 
 use crate::Result;
+use crate::core::transaction::Transaction;
+use crate::rpc::methods::RpcMethods;
+use crate::rpc::subscription::websocket_route;
+use std::sync::Arc;
+use tokio::sync::broadcast;
 use warp::Filter;
+use warp::Reply;
+
+/// JSON-RPC route serving single and batched requests at `/rpc`
+pub fn rpc_route(methods: Arc<RpcMethods>) -> impl Filter<Extract = (warp::reply::Response,), Error = warp::Rejection> + Clone {
+    warp::path("rpc")
+        .and(warp::post())
+        .and(warp::body::json())
+        .map(move |body: serde_json::Value| match methods.handle_body(body) {
+            Some(response) => warp::reply::json(&response).into_response(),
+            None => warp::http::StatusCode::NO_CONTENT.into_response(),
+        })
+}
 
 /// RPC server
 pub struct RpcServer {
@@ -9,33 +26,78 @@ pub struct RpcServer {
     pub address: String,
     /// Server port
     pub port: u16,
+    /// Pool admission events served to WebSocket subscribers
+    pending_transactions: Option<broadcast::Sender<Transaction>>,
+    /// Handlers of JSON-RPC requests
+    methods: Arc<RpcMethods>,
 }
 
 impl RpcServer {
     /// Create new RPC server
     pub fn new(address: String, port: u16) -> Self {
-        Self { address, port }
+        Self {
+            address,
+            port,
+            pending_transactions: None,
+            methods: Arc::new(RpcMethods::new()),
+        }
+    }
+
+    /// Set the handlers serving JSON-RPC requests
+    pub fn set_methods(&mut self, methods: RpcMethods) {
+        self.methods = Arc::new(methods);
+    }
+
+    /// Enable WebSocket subscriptions backed by the pool's admission events
+    pub fn enable_subscriptions(&mut self, pending_transactions: broadcast::Sender<Transaction>) {
+        self.pending_transactions = Some(pending_transactions);
     }
 
     /// Start RPC server
     pub async fn start(&self) -> Result<()> {
-        let routes = warp::path("rpc")
-            .and(warp::post())
-            .and(warp::body::json())
-            .map(|_body: serde_json::Value| {
-                warp::reply::json(&serde_json::json!({
-                    "jsonrpc": "2.0",
-                    "id": 1,
-                    "result": "Hello from Olympus RPC!"
-                }))
-            });
+        let routes = rpc_route(Arc::clone(&self.methods));
 
         let addr = format!("{}:{}", self.address, self.port);
         let addr: std::net::SocketAddr = addr.parse().unwrap();
-        warp::serve(routes)
-            .run(addr)
-            .await;
+        match &self.pending_transactions {
+            Some(events) => {
+                warp::serve(routes.or(websocket_route(events.clone())))
+                    .run(addr)
+                    .await
+            }
+            None => {
+                warp::serve(routes)
+                    .run(addr)
+                    .await
+            }
+        }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rpc_route_serves_batches() {
+        let route = rpc_route(Arc::new(RpcMethods::new()));
+        let batch = serde_json::json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "eth_blockNumber" },
+            { "jsonrpc": "2.0", "method": "eth_blockNumber" },
+            { "jsonrpc": "2.0", "id": 2, "method": "eth_unknown" },
+        ]);
+
+        let response = warp::test::request().method("POST").path("/rpc").json(&batch).reply(&route).await;
+        let responses: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(responses[1]["error"]["code"], -32601);
+        assert_eq!(responses.as_array().unwrap().len(), 2);
+
+        let notification = serde_json::json!({ "jsonrpc": "2.0", "method": "eth_blockNumber" });
+        let response = warp::test::request().method("POST").path("/rpc").json(&notification).reply(&route).await;
+        assert_eq!(response.status(), warp::http::StatusCode::NO_CONTENT);
+    }
+}
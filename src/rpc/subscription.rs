@@ -0,0 +1,177 @@
+//! WebSocket subscriptions (eth_subscribe / eth_unsubscribe)
+
+use crate::core::transaction::Transaction;
+use crate::rpc::methods::transaction_to_json;
+use futures::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+use warp::ws::{Message, WebSocket};
+use warp::Filter;
+
+/// Subscription kinds supported over WebSocket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    /// Transactions admitted to the pool, as hashes or full objects
+    NewPendingTransactions { full_transactions: bool },
+}
+
+/// WebSocket route serving subscriptions at `/ws`
+pub fn websocket_route(
+    pending_transactions: broadcast::Sender<Transaction>,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path("ws")
+        .and(warp::ws())
+        .map(move |ws: warp::ws::Ws| {
+            let events = pending_transactions.subscribe();
+            ws.on_upgrade(move |socket| handle_socket(socket, events))
+        })
+}
+
+/// Drive one WebSocket connection until the client disconnects
+async fn handle_socket(socket: WebSocket, mut events: broadcast::Receiver<Transaction>) {
+    let (mut sink, mut stream) = socket.split();
+    let mut subscriptions: HashMap<String, SubscriptionKind> = HashMap::new();
+
+    loop {
+        tokio::select! {
+            incoming = stream.next() => {
+                let message = match incoming {
+                    Some(Ok(message)) => message,
+                    _ => break,
+                };
+                if message.is_close() {
+                    break;
+                }
+                let text = match message.to_str() {
+                    Ok(text) => text,
+                    Err(_) => continue,
+                };
+
+                let reply = handle_subscription_request(text, &mut subscriptions);
+                if sink.send(Message::text(reply.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                let transaction = match event {
+                    Ok(transaction) => transaction,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                for (id, kind) in &subscriptions {
+                    let SubscriptionKind::NewPendingTransactions { full_transactions } = kind;
+                    let result = if *full_transactions {
+                        transaction_to_json(&transaction)
+                    } else {
+                        serde_json::json!(format!("{:#x}", transaction.hash()))
+                    };
+
+                    let notification = serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "method": "eth_subscription",
+                        "params": { "subscription": id, "result": result },
+                    });
+                    if sink.send(Message::text(notification.to_string())).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Handle an eth_subscribe / eth_unsubscribe request and build the reply
+fn handle_subscription_request(
+    text: &str,
+    subscriptions: &mut HashMap<String, SubscriptionKind>,
+) -> serde_json::Value {
+    let request: serde_json::Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(_) => return reply_error(serde_json::Value::Null, -32700, "Parse error"),
+    };
+    let id = request.get("id").cloned().unwrap_or(serde_json::Value::Null);
+    let params = request.get("params").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+
+    match request.get("method").and_then(|m| m.as_str()) {
+        Some("eth_subscribe") => {
+            let kind = match params.first().and_then(|p| p.as_str()) {
+                Some("newPendingTransactions") => SubscriptionKind::NewPendingTransactions {
+                    full_transactions: params.get(1).and_then(|p| p.as_bool()).unwrap_or(false),
+                },
+                _ => return reply_error(id, -32602, "Unsupported subscription"),
+            };
+
+            let subscription_id = format!("{:#x}", rand::random::<u64>());
+            subscriptions.insert(subscription_id.clone(), kind);
+            reply_result(id, serde_json::json!(subscription_id))
+        }
+        Some("eth_unsubscribe") => {
+            let removed = params.first()
+                .and_then(|p| p.as_str())
+                .map(|subscription_id| subscriptions.remove(subscription_id).is_some())
+                .unwrap_or(false);
+            reply_result(id, serde_json::json!(removed))
+        }
+        _ => reply_error(id, -32601, "Method not found"),
+    }
+}
+
+fn reply_result(id: serde_json::Value, result: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn reply_error(id: serde_json::Value, code: i32, message: &str) -> serde_json::Value {
+    serde_json::json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::transaction_executor::TransactionPool;
+    use crate::{Address, U256};
+    use std::time::Duration;
+
+    fn transaction(nonce: u64) -> Transaction {
+        Transaction::new(
+            U256::from(1000),
+            U256::from(20_000_000_000u64),
+            U256::from(21000),
+            Address::from([0x42; 20]),
+            vec![],
+            U256::from(nonce),
+        )
+    }
+
+    async fn recv_json(client: &mut warp::test::WsClient) -> serde_json::Value {
+        let message = client.recv().await.unwrap();
+        serde_json::from_str(message.to_str().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_new_pending_transactions_subscription() {
+        let mut pool = TransactionPool::new(10);
+        let route = websocket_route(pool.pending_transaction_events());
+        let mut client = warp::test::ws().path("/ws").handshake(route).await.unwrap();
+
+        client.send_text(r#"{"jsonrpc":"2.0","id":1,"method":"eth_subscribe","params":["newPendingTransactions"]}"#).await;
+        let subscription_id = recv_json(&mut client).await["result"].as_str().unwrap().to_string();
+
+        let tx = transaction(0);
+        pool.add_transaction(tx.clone()).unwrap();
+        let notification = recv_json(&mut client).await;
+        assert_eq!(notification["method"], "eth_subscription");
+        assert_eq!(notification["params"]["subscription"], subscription_id.as_str());
+        assert_eq!(notification["params"]["result"], format!("{:#x}", tx.hash()).as_str());
+
+        client.send_text(format!(
+            r#"{{"jsonrpc":"2.0","id":2,"method":"eth_unsubscribe","params":["{}"]}}"#,
+            subscription_id
+        )).await;
+        assert_eq!(recv_json(&mut client).await["result"], true);
+
+        // No further notifications after unsubscribing
+        pool.add_transaction(transaction(1)).unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(100), client.recv()).await.is_err());
+    }
+}
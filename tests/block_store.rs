@@ -0,0 +1,61 @@
+//! BlockStore tests
+//!
+//! Kept out of the library test binary, like the other sled-backed tests.
+
+use olympus::core::genesis::Genesis;
+use olympus::core::transaction::Transaction;
+use olympus::db::{BlockStore, SledDatabase};
+use olympus::{Address, H256, U256};
+
+#[test]
+fn test_blocks_round_trip_by_hash_and_number() {
+    let store = BlockStore::new(SledDatabase::temporary().unwrap());
+    let genesis = Genesis::default().genesis_block();
+    let mut child = genesis.clone();
+    child.from = Address::repeat_byte(0x11);
+    child.previous = genesis.hash();
+    child.parents = vec![genesis.hash()];
+    child.links = vec![H256::repeat_byte(0xaa)];
+    child.gas_used = U256::from(21_000);
+
+    for (number, block) in [genesis.clone(), child.clone()].iter().enumerate() {
+        store.put_block(block).unwrap();
+        store.put_block_number(number as u64, block.hash()).unwrap();
+    }
+
+    let stored = store.get_block(child.hash()).unwrap().unwrap();
+    assert_eq!(stored.hash(), child.hash());
+    assert_eq!((stored.previous, stored.parents.clone(), stored.links.clone()), (child.previous, child.parents.clone(), child.links.clone()));
+    assert_eq!(store.get_block_by_number(0).unwrap().unwrap().hash(), genesis.hash());
+    assert_eq!(store.get_block_hash(1).unwrap(), Some(child.hash()));
+    assert!(store.get_block(H256::repeat_byte(0xee)).unwrap().is_none());
+    assert!(store.get_block_by_number(2).unwrap().is_none());
+}
+
+#[test]
+fn test_head_updates_and_transactions_round_trip() {
+    let store = BlockStore::new(SledDatabase::temporary().unwrap());
+    assert_eq!(store.get_head().unwrap(), None);
+
+    store.set_head(H256::repeat_byte(0x01)).unwrap();
+    store.set_head(H256::repeat_byte(0x02)).unwrap();
+    assert_eq!(store.get_head().unwrap(), Some(H256::repeat_byte(0x02)));
+
+    let mut transaction = Transaction::new(U256::one(), U256::from(1_000_000_000u64), U256::from(21_000), Address::repeat_byte(0x22), vec![], U256::zero());
+    transaction.sign_with_secret(&[0x42; 32]).unwrap();
+    store.put_transaction(&transaction).unwrap();
+    let stored = store.get_transaction(transaction.hash()).unwrap().unwrap();
+    assert_eq!(stored.hash(), transaction.hash());
+    assert_eq!(stored.sender().unwrap(), transaction.sender().unwrap());
+}
+
+#[test]
+fn test_store_bound_to_first_genesis() {
+    let store = BlockStore::new(SledDatabase::temporary().unwrap());
+    let genesis = Genesis::default();
+    let other = Genesis { timestamp: 1, ..Genesis::default() };
+
+    store.check_genesis(genesis.hash()).unwrap();
+    store.check_genesis(genesis.hash()).unwrap();
+    assert!(store.check_genesis(other.hash()).is_err());
+}
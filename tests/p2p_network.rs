@@ -0,0 +1,47 @@
+use olympus::evm::transaction_executor::TransactionPool;
+use olympus::p2p::{NetworkManager, NetworkMessage};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_ping_published_on_one_node_reaches_the_other() {
+    let mut pool = TransactionPool::new(100);
+    let mut sender = NetworkManager::new().unwrap();
+    let mut receiver = NetworkManager::new().unwrap();
+    sender.start("/ip4/127.0.0.1/tcp/0".parse().unwrap()).unwrap();
+    receiver.start("/ip4/127.0.0.1/tcp/0".parse().unwrap()).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while sender.listen_addresses().is_empty() {
+            sender.poll_network().await.unwrap();
+        }
+        receiver.dial(sender.listen_addresses()[0].clone()).unwrap();
+
+        // Both sides send their Status on connecting
+        while sender.get_handshaken_peers().is_empty() || receiver.get_handshaken_peers().is_empty() {
+            tokio::select! {
+                polled = sender.poll_network() => polled.unwrap(),
+                polled = receiver.poll_network() => polled.unwrap(),
+            }
+            sender.handle_inbound(&mut pool);
+            receiver.handle_inbound(&mut pool);
+        }
+        assert!(receiver.get_peer_info(sender.peer_id).unwrap().connected);
+
+        // The receiver answers the ping, so the sender seeing a pong proves delivery
+        sender.broadcast_message(NetworkMessage::Ping).unwrap();
+        loop {
+            tokio::select! {
+                polled = sender.poll_network() => polled.unwrap(),
+                polled = receiver.poll_network() => polled.unwrap(),
+            }
+            receiver.handle_inbound(&mut pool);
+            if let Some((peer_id, message)) = sender.drain_inbound().pop() {
+                assert_eq!(peer_id, receiver.peer_id);
+                assert!(matches!(message, NetworkMessage::Pong));
+                break;
+            }
+        }
+    })
+    .await
+    .expect("ping was not delivered");
+}
@@ -0,0 +1,151 @@
+//! PersistentState tests
+//!
+//! Kept out of the library test binary: sled holds on to process-global
+//! allocations, which would skew the allocation-counting tests there.
+
+use olympus::core::config::{CommitDurability, DatabaseConfig};
+use olympus::evm::state::{State, KECCAK_EMPTY};
+use olympus::evm::PersistentState;
+use olympus::common::keccak256;
+use olympus::{Address, H256, U256};
+use std::collections::HashMap;
+
+#[test]
+fn test_iter_accounts_yields_all_accounts() {
+    let mut state = PersistentState::temporary().unwrap();
+    let mut expected = HashMap::new();
+    for i in 1..=5u8 {
+        let address = Address::from([i; 20]);
+        state.set_balance(address, U256::from(i as u64 * 1000));
+        state.set_nonce(address, i as u64);
+        expected.insert(address, (U256::from(i as u64 * 1000), i as u64));
+    }
+
+    assert_eq!(state.account_count(), 5);
+    let accounts: HashMap<_, _> = state.iter_accounts()
+        .map(|(address, info)| (address, (info.balance, info.nonce)))
+        .collect();
+    assert_eq!(accounts, expected);
+}
+
+#[test]
+fn test_iter_accounts_merges_uncommitted_writes_in_order() {
+    let mut state = PersistentState::temporary().unwrap();
+    for i in [1u8, 3, 5] {
+        state.set_balance(Address::from([i; 20]), U256::from(i));
+    }
+    state.commit();
+
+    // Overwrite, insert between and after, and delete committed accounts
+    state.set_balance(Address::from([3; 20]), U256::from(30));
+    state.set_balance(Address::from([2; 20]), U256::from(2));
+    state.set_balance(Address::from([6; 20]), U256::from(6));
+    state.delete_account(Address::from([1; 20]));
+
+    let accounts: Vec<_> = state.iter_accounts().map(|(address, info)| (address, info.balance)).collect();
+    assert_eq!(accounts, vec![
+        (Address::from([2; 20]), U256::from(2)),
+        (Address::from([3; 20]), U256::from(30)),
+        (Address::from([5; 20]), U256::from(5)),
+        (Address::from([6; 20]), U256::from(6)),
+    ]);
+    assert_eq!(state.account_count(), 4);
+}
+
+#[test]
+fn test_committed_state_survives_reopen_in_both_durability_modes() {
+    for durability in [CommitDurability::Durable, CommitDurability::Fast] {
+        let path = std::env::temp_dir().join(format!("olympus-durability-{:?}-{}", durability, std::process::id()));
+        let path = path.to_str().unwrap();
+        let config = DatabaseConfig { durability, ..DatabaseConfig::default() };
+        let address = Address::from([0x42; 20]);
+
+        {
+            let mut state = PersistentState::open(path, &config).unwrap();
+            assert_eq!(state.durability(), durability);
+            state.set_balance(address, U256::from(1234));
+            state.commit();
+        }
+
+        // sled's background flusher lets go of the file lock shortly after the last handle drops
+        let state = (0..100)
+            .find_map(|_| PersistentState::open(path, &config).ok().or_else(|| {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                None
+            }))
+            .unwrap();
+        assert_eq!(state.get_balance(address), U256::from(1234));
+        drop(state);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
+
+#[test]
+fn test_code_size_read_from_length_index() {
+    let mut state = PersistentState::temporary().unwrap();
+    let contract = Address::from([0xc0; 20]);
+    state.set_code(contract, vec![0x60; 300]);
+
+    assert_eq!(state.code_size(contract), 300);
+    assert_eq!(state.code_size(Address::from([0xee; 20])), 0);
+}
+
+#[test]
+fn test_code_hash_tracks_code() {
+    let mut state = PersistentState::temporary().unwrap();
+    let contract = Address::from([0xc0; 20]);
+    state.set_code(contract, vec![0x60, 0x00]);
+
+    assert_eq!(state.get_code(contract), vec![0x60, 0x00]);
+    assert_eq!(state.get_code_hash(contract), keccak256(&[0x60, 0x00]));
+
+    state.delete_account(contract);
+    assert!(state.get_code(contract).is_empty());
+    assert_eq!(state.get_code_hash(contract), KECCAK_EMPTY);
+}
+
+#[test]
+fn test_export_account_returns_all_slots() {
+    let mut state = PersistentState::temporary().unwrap();
+    let account = Address::from([0x11; 20]);
+    let neighbour = Address::from([0x12; 20]);
+    state.set_balance(account, U256::from(7));
+    let slots: Vec<(H256, H256)> = (1..=5u8).map(|i| (H256::from([i; 32]), H256::from([i + 0x10; 32]))).collect();
+    for (slot, value) in &slots {
+        state.set_storage(account, *slot, *value);
+    }
+    state.set_balance(neighbour, U256::from(1));
+    state.set_storage(neighbour, H256::from([0x01; 32]), H256::from([0xff; 32]));
+    state.commit();
+
+    let (info, exported) = state.export_account(account).unwrap();
+    assert_eq!(info.balance, U256::from(7));
+    assert_eq!(exported, slots);
+    assert!(state.export_account(Address::from([0x99; 20])).is_err());
+
+    let all: Vec<_> = state.export_all().collect();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[1].0, neighbour);
+    assert_eq!(all[1].2.len(), 1);
+}
+
+#[test]
+fn test_revert_discards_writes_after_checkpoint() {
+    let mut state = PersistentState::temporary().unwrap();
+    let (first, second) = (Address::from([0x01; 20]), Address::from([0x02; 20]));
+    state.set_balance(first, U256::from(100));
+    state.commit();
+
+    state.set_balance(first, U256::from(200));
+    state.create_checkpoint(1).unwrap();
+    state.set_balance(first, U256::from(300));
+    state.set_balance(second, U256::from(5));
+    state.revert();
+
+    assert_eq!(state.get_balance(first), U256::from(200));
+    assert!(!state.exists(second));
+
+    // Without a checkpoint, revert falls back to the last commit
+    state.revert();
+    assert_eq!(state.get_balance(first), U256::from(100));
+}
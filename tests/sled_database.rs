@@ -0,0 +1,51 @@
+//! SledDatabase tests
+//!
+//! Kept out of the library test binary, like the other sled-backed tests.
+
+use olympus::db::{BatchOp, SledDatabase};
+use olympus::OlympusError;
+
+#[test]
+fn test_scan_prefix_returns_only_matching_keys() {
+    let db = SledDatabase::temporary().unwrap();
+    for key in [&b"acct:01"[..], b"acct:02", b"acct:03", b"acc", b"code:01", b"acct"] {
+        db.insert("state", key, key).unwrap();
+    }
+
+    let keys: Vec<Vec<u8>> = db.scan_prefix("state", b"acct:").unwrap().into_iter().map(|(key, _)| key).collect();
+    assert_eq!(keys, vec![b"acct:01".to_vec(), b"acct:02".to_vec(), b"acct:03".to_vec()]);
+    assert!(db.scan_prefix("state", b"none").unwrap().is_empty());
+
+    let range = db.range("state", b"acct:02", b"code:01").unwrap();
+    assert_eq!(range, vec![(b"acct:02".to_vec(), b"acct:02".to_vec()), (b"acct:03".to_vec(), b"acct:03".to_vec())]);
+
+    db.delete("state", b"acct:02").unwrap();
+    db.delete("state", b"missing").unwrap();
+    assert_eq!(db.get("state", b"acct:02").unwrap(), None);
+    assert_eq!(db.scan_prefix("state", b"acct:").unwrap().len(), 2);
+}
+
+#[test]
+fn test_batch_applies_across_trees_or_not_at_all() {
+    let db = SledDatabase::temporary().unwrap();
+    db.insert("blocks", b"stale", b"1").unwrap();
+
+    db.apply_batch(vec![
+        BatchOp::Insert { tree: "accounts".into(), key: b"alice".to_vec(), value: b"100".to_vec() },
+        BatchOp::Insert { tree: "blocks".into(), key: b"head".to_vec(), value: b"0x01".to_vec() },
+        BatchOp::Delete { tree: "blocks".into(), key: b"stale".to_vec() },
+    ]).unwrap();
+    assert_eq!(db.get("accounts", b"alice").unwrap(), Some(b"100".to_vec()));
+    assert_eq!(db.get("blocks", b"head").unwrap(), Some(b"0x01".to_vec()));
+    assert_eq!(db.get("blocks", b"stale").unwrap(), None);
+
+    // The failed expectation comes after writes to both trees, which are rolled back
+    let result = db.apply_batch(vec![
+        BatchOp::Insert { tree: "accounts".into(), key: b"alice".to_vec(), value: b"50".to_vec() },
+        BatchOp::Insert { tree: "blocks".into(), key: b"head".to_vec(), value: b"0x02".to_vec() },
+        BatchOp::Expect { tree: "blocks".into(), key: b"stale".to_vec(), value: Some(b"1".to_vec()) },
+    ]);
+    assert!(matches!(result, Err(OlympusError::Database(_))));
+    assert_eq!(db.get("accounts", b"alice").unwrap(), Some(b"100".to_vec()));
+    assert_eq!(db.get("blocks", b"head").unwrap(), Some(b"0x01".to_vec()));
+}
@@ -0,0 +1,31 @@
+//! TxIndex tests
+//!
+//! Kept out of the library test binary, like the other sled-backed tests.
+
+use olympus::core::transaction::Transaction;
+use olympus::db::{SledDatabase, TxIndex};
+use olympus::{Address, H256, U256};
+
+fn signed_transfer(nonce: u64) -> Transaction {
+    let mut transaction = Transaction::new(U256::one(), U256::from(1_000_000_000u64), U256::from(21_000), Address::repeat_byte(0x22), vec![], U256::from(nonce));
+    transaction.sign_with_secret(&[0x42; 32]).unwrap();
+    transaction
+}
+
+#[test]
+fn test_indexed_transaction_found_with_block_location() {
+    let index = TxIndex::new(SledDatabase::temporary().unwrap());
+    let block_hash = H256::repeat_byte(0xb1);
+    let transactions: Vec<Transaction> = (0..3).map(signed_transfer).collect();
+
+    index.index_block(block_hash, 7, &transactions).unwrap();
+    // Indexing the same block again changes nothing
+    index.index_block(block_hash, 7, &transactions).unwrap();
+
+    let middle = index.get(transactions[1].hash()).unwrap().unwrap();
+    assert_eq!(middle.transaction_index, 1);
+    assert_eq!(middle.block_number, 7);
+    assert_eq!(middle.block_hash, block_hash);
+    assert_eq!(middle.transaction.hash(), transactions[1].hash());
+    assert!(index.get(signed_transfer(3).hash()).unwrap().is_none());
+}